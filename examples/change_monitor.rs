@@ -10,11 +10,9 @@ fn main() {
 
 fn run() -> Result<(), UsnError> {
     let drive_letter = common::drive_letter_from_args_or('C');
-    let volume = Volume::from_drive_letter(drive_letter)?;
+    let (volume, journal_data) = Volume::open_for_journal(drive_letter)?;
     let usn_journal = volume.journal();
 
-    let journal_data = usn_journal.query(true)?;
-
     let enum_options = journal::EnumOptions {
         start_usn: journal_data.next_usn,
         only_on_close: false,
@@ -0,0 +1,1483 @@
+//! Iterator adapters for USN journal entries.
+//!
+//! These adapters wrap iterators yielding `UsnResult` entries, such as
+//! [`UsnJournalIter`](crate::journal::UsnJournalIter), and add stateful context or batching.
+
+use crate::{
+    FileId, Usn, UsnReason, UsnResult, errors::UsnError, journal::UsnEntry, mft::MftEntry,
+};
+use lru::LruCache;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ffi::{OsStr, OsString},
+    num::NonZeroUsize,
+    time::{Duration, SystemTime},
+};
+use windows::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_DIRECTORY, FILE_FLAGS_AND_ATTRIBUTES};
+
+/// Iterator adapter that pairs each entry with the previously yielded entry for the same file.
+///
+/// Created by [`UsnJournalIter::with_previous`](crate::journal::UsnJournalIter::with_previous).
+///
+/// # Memory
+/// The last entry seen for each file ID is kept in an LRU map bounded to `capacity` files.
+/// Once the map is full, the least recently seen file is evicted, so memory stays proportional
+/// to `capacity` entries (each holding its file name) regardless of how long the journal is
+/// read. An evicted file simply gets `None` as its predecessor the next time it shows up.
+pub struct WithPrevious<I> {
+    iter: I,
+    previous: LruCache<FileId, UsnEntry>,
+}
+
+impl<I> WithPrevious<I> {
+    pub(crate) fn new(iter: I, capacity: NonZeroUsize) -> Self {
+        WithPrevious {
+            iter,
+            previous: LruCache::new(capacity),
+        }
+    }
+}
+
+impl<I> Iterator for WithPrevious<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<(UsnEntry, Option<UsnEntry>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.iter.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let previous = self.previous.put(entry.fid, entry.clone());
+        Some(Ok((entry, previous)))
+    }
+}
+
+/// Iterator adapter that groups entries into batches.
+///
+/// Created by [`UsnJournalIter::chunks`](crate::journal::UsnJournalIter::chunks). Each item
+/// holds up to `size` entries; only the last batch may be shorter. When the underlying
+/// iterator yields an error, the entries collected so far are yielded first and the error is
+/// yielded on the following call.
+pub struct Chunks<I> {
+    iter: I,
+    size: NonZeroUsize,
+    pending_error: Option<UsnError>,
+}
+
+impl<I> Chunks<I> {
+    pub(crate) fn new(iter: I, size: NonZeroUsize) -> Self {
+        Chunks {
+            iter,
+            size,
+            pending_error: None,
+        }
+    }
+}
+
+impl<I, E> Iterator for Chunks<I>
+where
+    I: Iterator<Item = UsnResult<E>>,
+{
+    type Item = UsnResult<Vec<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending_error.take() {
+            return Some(Err(err));
+        }
+
+        let mut batch = Vec::with_capacity(self.size.get());
+        while batch.len() < self.size.get() {
+            match self.iter.next() {
+                Some(Ok(entry)) => batch.push(entry),
+                Some(Err(err)) if batch.is_empty() => return Some(Err(err)),
+                Some(Err(err)) => {
+                    self.pending_error = Some(err);
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        (!batch.is_empty()).then_some(Ok(batch))
+    }
+}
+
+/// Reasons that directories accumulate as their contents change, without any change of
+/// their own worth reporting.
+const DIRECTORY_METADATA_REASONS: UsnReason = UsnReason::BASIC_INFO_CHANGE
+    .union(UsnReason::INDEXABLE_CHANGE)
+    .union(UsnReason::CLOSE);
+
+/// Iterator adapter that drops metadata-only records for directories.
+///
+/// Created by
+/// [`UsnJournalIter::exclude_directory_metadata`](crate::journal::UsnJournalIter::exclude_directory_metadata).
+/// A directory record is dropped when its reasons are limited to basic-info, indexing and
+/// close changes. Directory creations, deletions and renames, all file records, and errors
+/// are passed through.
+pub struct ExcludeDirectoryMetadata<I> {
+    iter: I,
+}
+
+impl<I> ExcludeDirectoryMetadata<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        ExcludeDirectoryMetadata { iter }
+    }
+}
+
+impl<I> Iterator for ExcludeDirectoryMetadata<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<UsnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.find(|result| match result {
+            Ok(entry) => !(entry.is_dir() && DIRECTORY_METADATA_REASONS.contains(entry.reason)),
+            Err(_) => true,
+        })
+    }
+}
+
+/// Iterator adapter that checks entries are yielded in strictly increasing USN order.
+///
+/// Created by [`UsnJournalIter::checked`](crate::journal::UsnJournalIter::checked). An entry
+/// whose USN is not greater than the highest USN yielded so far is replaced by
+/// `UsnError::NonMonotonicUsn`, which points at a parsing bug or a journal that was reset
+/// underneath the reader. Iteration continues afterwards, still compared against that
+/// highest USN. Errors from the inner iterator are passed through.
+pub struct Checked<I> {
+    iter: I,
+    prev: Option<Usn>,
+}
+
+impl<I> Checked<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        Checked { iter, prev: None }
+    }
+}
+
+impl<I> Iterator for Checked<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<UsnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.iter.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+        match self.prev {
+            Some(prev) if entry.usn <= prev => Some(Err(UsnError::NonMonotonicUsn {
+                prev,
+                current: entry.usn,
+            })),
+            _ => {
+                self.prev = Some(entry.usn);
+                Some(Ok(entry))
+            }
+        }
+    }
+}
+
+/// An item yielded by [`WatchDirectoryShallow`].
+#[derive(Debug, Clone)]
+pub enum DirectoryEvent {
+    /// A change to an immediate child of the watched directory.
+    Child(UsnEntry),
+    /// The watched directory was renamed or moved; the entry carries its new name and parent.
+    Renamed(UsnEntry),
+    /// The watched directory was deleted. No further events follow.
+    Deleted(UsnEntry),
+}
+
+/// Iterator adapter that only reports changes to the immediate children of one directory.
+///
+/// Created by
+/// [`UsnJournalIter::watch_directory_shallow`](crate::journal::UsnJournalIter::watch_directory_shallow).
+/// An entry is a child change when its `parent_fid` is the watched directory, so changes in
+/// subdirectories are skipped, while the subdirectories' own creations, deletions and renames
+/// are reported. A file moved into the directory shows up through its `RENAME_NEW_NAME`
+/// record and a file moved out through its `RENAME_OLD_NAME` record.
+///
+/// Records of the directory itself are reduced to [`DirectoryEvent::Renamed`] on
+/// `RENAME_NEW_NAME` and [`DirectoryEvent::Deleted`] on `FILE_DELETE`, after which the
+/// iterator ends. Errors are passed through.
+pub struct WatchDirectoryShallow<I> {
+    iter: I,
+    dir_fid: FileId,
+    deleted: bool,
+}
+
+impl<I> WatchDirectoryShallow<I> {
+    pub(crate) fn new(iter: I, dir_fid: FileId) -> Self {
+        WatchDirectoryShallow {
+            iter,
+            dir_fid,
+            deleted: false,
+        }
+    }
+}
+
+impl<I> Iterator for WatchDirectoryShallow<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<DirectoryEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.deleted {
+            return None;
+        }
+        loop {
+            let entry = match self.iter.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if entry.fid == self.dir_fid {
+                if entry.reason.contains(UsnReason::FILE_DELETE) {
+                    self.deleted = true;
+                    return Some(Ok(DirectoryEvent::Deleted(entry)));
+                }
+                if entry.reason.contains(UsnReason::RENAME_NEW_NAME) {
+                    return Some(Ok(DirectoryEvent::Renamed(entry)));
+                }
+            } else if entry.parent_fid == self.dir_fid {
+                return Some(Ok(DirectoryEvent::Child(entry)));
+            }
+        }
+    }
+}
+
+/// Smallest number of tracked files at which [`RateLimitPerFile`] prunes expired windows.
+const RATE_LIMIT_PRUNE_THRESHOLD: usize = 1024;
+
+/// Iterator adapter that caps how many entries are yielded for each file per time window.
+///
+/// Created by
+/// [`UsnJournalIter::rate_limit_per_file`](crate::journal::UsnJournalIter::rate_limit_per_file).
+/// Windows are fixed and per file: the first entry for a file opens a window of length
+/// `window`, measured on the entries' own `time`, and at most `max_per_window` entries for
+/// that file are yielded until an entry falls past the window's end, which opens a new one.
+/// Excess entries are dropped; other files and errors are unaffected.
+///
+/// Unlike debouncing, which merges bursts across the whole journal, this only stops a single
+/// busy file from crowding out the rest. Files whose window has expired are forgotten once
+/// enough files are tracked, so memory stays proportional to the number of recently active
+/// files.
+pub struct RateLimitPerFile<I> {
+    iter: I,
+    max_per_window: usize,
+    window: Duration,
+    windows: HashMap<FileId, (SystemTime, usize)>,
+    prune_at: usize,
+}
+
+impl<I> RateLimitPerFile<I> {
+    pub(crate) fn new(iter: I, max_per_window: usize, window: Duration) -> Self {
+        RateLimitPerFile {
+            iter,
+            max_per_window,
+            window,
+            windows: HashMap::new(),
+            prune_at: RATE_LIMIT_PRUNE_THRESHOLD,
+        }
+    }
+
+    /// Counts `entry` against its file's window and returns whether it may be yielded.
+    fn admit(&mut self, entry: &UsnEntry) -> bool {
+        let window = self.window;
+        let expired = |start: SystemTime| {
+            entry
+                .time
+                .duration_since(start)
+                .is_ok_and(|elapsed| elapsed >= window)
+        };
+
+        if self.windows.len() >= self.prune_at {
+            self.windows.retain(|_, (start, _)| !expired(*start));
+            self.prune_at = (self.windows.len() * 2).max(RATE_LIMIT_PRUNE_THRESHOLD);
+        }
+
+        let (start, count) = self.windows.entry(entry.fid).or_insert((entry.time, 0));
+        if expired(*start) {
+            *start = entry.time;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.max_per_window
+    }
+}
+
+impl<I> Iterator for RateLimitPerFile<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<UsnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.iter.next()? {
+                Ok(entry) if !self.admit(&entry) => continue,
+                result => return Some(result),
+            }
+        }
+    }
+}
+
+/// A file or directory record from either the MFT or the USN journal.
+///
+/// Lets one pipeline consume both sources: MFT entries and journal entries convert into it,
+/// and [`FileRecords`] does so for a whole iterator. Only journal records describe a change,
+/// so `change` is `None` for records from the MFT.
+///
+/// With the `serde` feature, `file_name` is serialized as a UTF-8 string, in which invalid
+/// UTF-16 is replaced with U+FFFD.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileRecord {
+    pub usn: Usn,
+    pub fid: FileId,
+    pub parent_fid: FileId,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::lossy_os_string"))]
+    pub file_name: OsString,
+    pub file_attributes: u32,
+    pub change: Option<FileChange>,
+}
+
+/// The change a journal record reports, as carried by [`FileRecord::change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileChange {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
+    pub time: SystemTime,
+    pub reason: UsnReason,
+    pub source_info: u32,
+}
+
+impl FileRecord {
+    /// Returns true if the record is for a directory.
+    pub fn is_dir(&self) -> bool {
+        FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes).contains(FILE_ATTRIBUTE_DIRECTORY)
+    }
+}
+
+impl From<UsnEntry> for FileRecord {
+    fn from(entry: UsnEntry) -> Self {
+        FileRecord {
+            usn: entry.usn,
+            fid: entry.fid,
+            parent_fid: entry.parent_fid,
+            file_name: entry.file_name,
+            file_attributes: entry.file_attributes,
+            change: Some(FileChange {
+                time: entry.time,
+                reason: entry.reason,
+                source_info: entry.source_info,
+            }),
+        }
+    }
+}
+
+impl From<MftEntry> for FileRecord {
+    fn from(entry: MftEntry) -> Self {
+        FileRecord {
+            usn: entry.usn,
+            fid: entry.fid,
+            parent_fid: entry.parent_fid,
+            file_name: entry.file_name,
+            file_attributes: entry.file_attributes,
+            change: None,
+        }
+    }
+}
+
+/// Iterator adapter that converts MFT or journal entries into [`FileRecord`]s.
+///
+/// Created by [`MftIter::file_records`](crate::mft::MftIter::file_records) and
+/// [`UsnJournalIter::file_records`](crate::journal::UsnJournalIter::file_records). Errors are
+/// passed through.
+pub struct FileRecords<I> {
+    iter: I,
+}
+
+impl<I> FileRecords<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        FileRecords { iter }
+    }
+}
+
+impl<I, E> Iterator for FileRecords<I>
+where
+    I: Iterator<Item = UsnResult<E>>,
+    E: Into<FileRecord>,
+{
+    type Item = UsnResult<FileRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|result| result.map(Into::into))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Heuristics used by [`AtomicSaves`] to recognise an atomic save.
+#[derive(Debug, Clone, Copy)]
+pub struct AtomicSaveOptions {
+    /// Returns true for names used for temporary and backup copies, such as `report.txt.tmp`
+    /// or `report.txt~`.
+    pub is_temporary_name: fn(&OsStr) -> bool,
+    /// Number of records a temporary or backup file may accumulate before it is given up on
+    /// and its records are yielded as ordinary entries.
+    pub max_pending_records: usize,
+}
+
+impl Default for AtomicSaveOptions {
+    fn default() -> Self {
+        AtomicSaveOptions {
+            is_temporary_name: default_is_temporary_name,
+            max_pending_records: 64,
+        }
+    }
+}
+
+/// The default [`AtomicSaveOptions::is_temporary_name`] heuristic.
+///
+/// Matches names starting or ending with `~`, starting with `.#`, containing `.tmp`, or
+/// ending with `.temp`, `.bak` or `.swp` (case-insensitively).
+pub fn default_is_temporary_name(name: &OsStr) -> bool {
+    let name = name.to_string_lossy().to_ascii_lowercase();
+    name.starts_with('~')
+        || name.ends_with('~')
+        || name.starts_with(".#")
+        || name.contains(".tmp")
+        || [".temp", ".bak", ".swp"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+}
+
+/// An item yielded by [`AtomicSaves`].
+#[derive(Debug, Clone)]
+pub enum SaveEvent {
+    /// A journal entry that is not part of a recognised atomic save.
+    Entry(UsnEntry),
+    /// A file was replaced by writing a temporary copy and renaming it over the original.
+    AtomicSave(AtomicSave),
+}
+
+/// A coalesced atomic save of the file `file_name` in the directory `parent_fid`.
+///
+/// The saved file's path can be resolved with
+/// [`PathResolver::resolve_from_parts`](crate::path::PathResolver::resolve_from_parts).
+#[derive(Debug, Clone)]
+pub struct AtomicSave {
+    /// File ID of the new file, i.e. the former temporary copy.
+    pub fid: FileId,
+    /// File ID of the directory holding the saved file.
+    pub parent_fid: FileId,
+    /// Name of the saved file.
+    pub file_name: OsString,
+    /// The raw entries that make up the save, in USN order.
+    pub entries: Vec<UsnEntry>,
+}
+
+enum PendingRole {
+    /// Created under a temporary name; completes the save when renamed to a regular name.
+    Temporary,
+    /// Renamed from `original_name` to a backup name, to be replaced by a temporary copy.
+    Backup { original_name: OsString },
+}
+
+struct PendingFile {
+    role: PendingRole,
+    entries: Vec<UsnEntry>,
+}
+
+/// Iterator adapter that coalesces editor-style atomic saves into single events.
+///
+/// Created by [`UsnJournalIter::atomic_saves`](crate::journal::UsnJournalIter::atomic_saves).
+/// Recognises the sequence: create a file under a temporary name and write it, optionally
+/// rename the original to a backup name, rename the temporary file to the original name,
+/// and optionally delete the backup. The records of that sequence are yielded as one
+/// [`SaveEvent::AtomicSave`]; everything else is yielded as [`SaveEvent::Entry`].
+///
+/// Records of temporary and backup files are held back until the pattern completes or is
+/// abandoned, so they may be yielded after unrelated later entries. When the underlying
+/// iterator ends, held records are released as ordinary entries.
+pub struct AtomicSaves<I> {
+    iter: I,
+    options: AtomicSaveOptions,
+    pending: HashMap<FileId, PendingFile>,
+    // The RENAME_OLD_NAME record of a rename whose new name is not known yet.
+    rename_old: HashMap<FileId, UsnEntry>,
+    // Files whose trailing close (and, for backups, delete) records belong to a save that was
+    // already yielded, mapped to the reasons such records may carry.
+    absorbed: HashMap<FileId, UsnReason>,
+    ready: VecDeque<UsnResult<SaveEvent>>,
+}
+
+impl<I> AtomicSaves<I> {
+    pub(crate) fn new(iter: I, options: AtomicSaveOptions) -> Self {
+        AtomicSaves {
+            iter,
+            options,
+            pending: HashMap::new(),
+            rename_old: HashMap::new(),
+            absorbed: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn is_temporary(&self, name: &OsStr) -> bool {
+        (self.options.is_temporary_name)(name)
+    }
+
+    fn release(&mut self, entries: impl IntoIterator<Item = UsnEntry>) {
+        self.ready
+            .extend(entries.into_iter().map(|entry| Ok(SaveEvent::Entry(entry))));
+    }
+
+    fn flush(&mut self) {
+        let mut held = self
+            .rename_old
+            .drain()
+            .map(|(_, entry)| entry)
+            .collect::<Vec<_>>();
+        held.extend(self.pending.drain().flat_map(|(_, file)| file.entries));
+        held.sort_by_key(|entry| entry.usn);
+        self.release(held);
+    }
+
+    fn handle(&mut self, entry: UsnEntry) {
+        if let Some(close_mask) = self.absorbed.remove(&entry.fid)
+            && close_mask.contains(entry.reason)
+        {
+            if !entry.reason.contains(UsnReason::CLOSE) {
+                self.absorbed.insert(entry.fid, close_mask);
+            }
+            return;
+        }
+
+        if let Some(old) = self.rename_old.remove(&entry.fid) {
+            if entry.reason.contains(UsnReason::RENAME_NEW_NAME)
+                && self.is_temporary(&entry.file_name)
+            {
+                let original_name = old.file_name.clone();
+                self.pending.insert(
+                    entry.fid,
+                    PendingFile {
+                        role: PendingRole::Backup { original_name },
+                        entries: vec![old, entry],
+                    },
+                );
+                return;
+            }
+            self.release([old]);
+        }
+
+        if let Some(mut file) = self.pending.remove(&entry.fid) {
+            let fid = entry.fid;
+            let deleted = entry.reason.contains(UsnReason::FILE_DELETE);
+            let renamed_to_regular_name = entry.reason.contains(UsnReason::RENAME_NEW_NAME)
+                && !self.is_temporary(&entry.file_name);
+            file.entries.push(entry);
+
+            if renamed_to_regular_name && matches!(file.role, PendingRole::Temporary) {
+                self.complete_save(file.entries);
+            } else if deleted
+                || renamed_to_regular_name
+                || file.entries.len() > self.options.max_pending_records
+            {
+                // The file went away, got a regular name back, or is taking too long.
+                self.release(file.entries);
+            } else {
+                self.pending.insert(fid, file);
+            }
+            return;
+        }
+
+        if entry.reason.contains(UsnReason::FILE_CREATE)
+            && !entry.is_dir()
+            && self.is_temporary(&entry.file_name)
+        {
+            self.pending.insert(
+                entry.fid,
+                PendingFile {
+                    role: PendingRole::Temporary,
+                    entries: vec![entry],
+                },
+            );
+        } else if entry.reason.contains(UsnReason::RENAME_OLD_NAME)
+            && !entry.reason.contains(UsnReason::RENAME_NEW_NAME)
+            && !entry.is_dir()
+            && !self.is_temporary(&entry.file_name)
+        {
+            self.rename_old.insert(entry.fid, entry);
+        } else {
+            self.ready.push_back(Ok(SaveEvent::Entry(entry)));
+        }
+    }
+
+    fn complete_save(&mut self, mut entries: Vec<UsnEntry>) {
+        let Some(last) = entries.last() else {
+            return;
+        };
+        let (fid, parent_fid, file_name) = (last.fid, last.parent_fid, last.file_name.clone());
+
+        let backups = self
+            .pending
+            .iter()
+            .filter(|(_, file)| match &file.role {
+                PendingRole::Backup { original_name } => {
+                    *original_name == file_name && file.entries[0].parent_fid == parent_fid
+                }
+                PendingRole::Temporary => false,
+            })
+            .map(|(&backup_fid, _)| backup_fid)
+            .collect::<Vec<_>>();
+        for backup_fid in backups {
+            if let Some(backup) = self.pending.remove(&backup_fid) {
+                entries.extend(backup.entries);
+                self.absorbed
+                    .insert(backup_fid, UsnReason::FILE_DELETE | UsnReason::CLOSE);
+            }
+        }
+        self.absorbed
+            .insert(fid, UsnReason::RENAME_NEW_NAME | UsnReason::CLOSE);
+
+        entries.sort_by_key(|entry| entry.usn);
+        self.ready.push_back(Ok(SaveEvent::AtomicSave(AtomicSave {
+            fid,
+            parent_fid,
+            file_name,
+            entries,
+        })));
+    }
+}
+
+impl<I> Iterator for AtomicSaves<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<SaveEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Some(event);
+            }
+
+            match self.iter.next() {
+                Some(Ok(entry)) => self.handle(entry),
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.flush();
+                    return self.ready.pop_front();
+                }
+            }
+        }
+    }
+}
+
+/// An item yielded by [`CoalesceRenames`].
+#[derive(Debug, Clone)]
+pub enum UsnEvent {
+    /// A journal entry that is not part of a coalesced rename.
+    Entry(UsnEntry),
+    /// A file or directory was renamed or moved.
+    ///
+    /// `old_name` and `old_parent` are `None` when the `RENAME_OLD_NAME` record was not seen,
+    /// e.g. because iteration started between the two records or the journal was read with
+    /// `only_on_close`.
+    Renamed {
+        fid: FileId,
+        old_name: Option<OsString>,
+        old_parent: Option<FileId>,
+        new_name: OsString,
+        new_parent: FileId,
+        /// USN of the `RENAME_NEW_NAME` record.
+        usn: Usn,
+    },
+}
+
+/// Iterator adapter that pairs the two records of a rename into one event.
+///
+/// Created by
+/// [`UsnJournalIter::coalesce_renames`](crate::journal::UsnJournalIter::coalesce_renames).
+/// A rename is journaled as a `RENAME_OLD_NAME` record followed by a `RENAME_NEW_NAME` record
+/// for the same file. The old-name record is held back until its counterpart arrives and the
+/// pair is yielded as [`UsnEvent::Renamed`]; pairing works on the entry stream, so it does not
+/// matter whether the records came from the same journal read. A new-name record without a
+/// held old-name record still yields a `Renamed` event, without the old name. Later records of
+/// a renamed file up to its `CLOSE`, such as the `RENAME_NEW_NAME | CLOSE` record that usually
+/// follows a rename, and every other record, are yielded as [`UsnEvent::Entry`].
+///
+/// Old-name records still held when the underlying iterator ends are released as ordinary
+/// entries.
+pub struct CoalesceRenames<I> {
+    iter: I,
+    // RENAME_OLD_NAME records waiting for their RENAME_NEW_NAME counterpart.
+    old_names: HashMap<FileId, UsnEntry>,
+    // Files whose rename was yielded and that have not been closed since; their further
+    // RENAME_NEW_NAME records are passed through until the CLOSE record.
+    renamed: HashSet<FileId>,
+    ready: VecDeque<UsnEvent>,
+}
+
+impl<I> CoalesceRenames<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        CoalesceRenames {
+            iter,
+            old_names: HashMap::new(),
+            renamed: HashSet::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn handle(&mut self, entry: UsnEntry) {
+        let follows_rename = if entry.is_close() {
+            self.renamed.remove(&entry.fid)
+        } else {
+            self.renamed.contains(&entry.fid)
+        };
+
+        if let Some(old) = self.old_names.remove(&entry.fid) {
+            if entry.reason.contains(UsnReason::RENAME_NEW_NAME) {
+                self.push_renamed(Some(old), entry);
+                return;
+            }
+            self.ready.push_back(UsnEvent::Entry(old));
+        }
+
+        if entry.reason.contains(UsnReason::RENAME_OLD_NAME)
+            && !entry.reason.contains(UsnReason::RENAME_NEW_NAME)
+        {
+            self.old_names.insert(entry.fid, entry);
+        } else if entry.reason.contains(UsnReason::RENAME_NEW_NAME) && !follows_rename {
+            self.push_renamed(None, entry);
+        } else {
+            self.ready.push_back(UsnEvent::Entry(entry));
+        }
+    }
+
+    fn push_renamed(&mut self, old: Option<UsnEntry>, new: UsnEntry) {
+        if !new.is_close() {
+            self.renamed.insert(new.fid);
+        }
+        let (old_name, old_parent) = match old {
+            Some(old) => (Some(old.file_name), Some(old.parent_fid)),
+            None => (None, None),
+        };
+        self.ready.push_back(UsnEvent::Renamed {
+            fid: new.fid,
+            old_name,
+            old_parent,
+            new_name: new.file_name,
+            new_parent: new.parent_fid,
+            usn: new.usn,
+        });
+    }
+
+    fn flush(&mut self) {
+        let mut held = self
+            .old_names
+            .drain()
+            .map(|(_, entry)| entry)
+            .collect::<Vec<_>>();
+        held.sort_by_key(|entry| entry.usn);
+        self.ready.extend(held.into_iter().map(UsnEvent::Entry));
+    }
+}
+
+impl<I> Iterator for CoalesceRenames<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<UsnEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Some(Ok(event));
+            }
+
+            match self.iter.next() {
+                Some(Ok(entry)) => self.handle(entry),
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.flush();
+                    return self.ready.pop_front().map(Ok);
+                }
+            }
+        }
+    }
+}
+
+/// An item yielded by [`Transactions`].
+#[derive(Debug, Clone)]
+pub enum TransactionEvent {
+    /// A journal entry that is not marked as a transacted change.
+    Entry(UsnEntry),
+    /// A run of transacted changes assumed to belong to one transaction.
+    Transaction(Transaction),
+}
+
+/// Transacted (TxF) changes grouped by [`Transactions`].
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// The entries of the transaction, in USN order.
+    pub entries: Vec<UsnEntry>,
+}
+
+/// Iterator adapter that groups transacted changes into single events.
+///
+/// Created by [`UsnJournalIter::transactions`](crate::journal::UsnJournalIter::transactions).
+/// Changes made inside a TxF transaction are journaled when the transaction commits, as
+/// records carrying `TRANSACTED_CHANGE`. The journal does not record a transaction ID, so
+/// grouping is a heuristic: consecutive transacted records whose timestamps are at most
+/// `max_gap` apart form one [`Transaction`]. Any other record ends the current group and is
+/// yielded as [`TransactionEvent::Entry`].
+///
+/// Two transactions committed back to back within `max_gap` are therefore merged, and a
+/// transaction interleaved with unrelated changes is split. A group is held until the record
+/// that ends it arrives, or until the underlying iterator yields an error or ends.
+pub struct Transactions<I> {
+    iter: I,
+    max_gap: Duration,
+    current: Vec<UsnEntry>,
+    ready: VecDeque<UsnResult<TransactionEvent>>,
+}
+
+impl<I> Transactions<I> {
+    pub(crate) fn new(iter: I, max_gap: Duration) -> Self {
+        Transactions {
+            iter,
+            max_gap,
+            current: Vec::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn handle(&mut self, entry: UsnEntry) {
+        if !entry.reason.contains(UsnReason::TRANSACTED_CHANGE) {
+            self.flush();
+            self.ready.push_back(Ok(TransactionEvent::Entry(entry)));
+            return;
+        }
+
+        if let Some(last) = self.current.last()
+            && entry
+                .time
+                .duration_since(last.time)
+                .unwrap_or(Duration::ZERO)
+                > self.max_gap
+        {
+            self.flush();
+        }
+        self.current.push(entry);
+    }
+
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            let entries = std::mem::take(&mut self.current);
+            self.ready
+                .push_back(Ok(TransactionEvent::Transaction(Transaction { entries })));
+        }
+    }
+}
+
+impl<I> Iterator for Transactions<I>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+{
+    type Item = UsnResult<TransactionEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Some(event);
+            }
+
+            match self.iter.next() {
+                Some(Ok(entry)) => self.handle(entry),
+                Some(Err(err)) => {
+                    self.flush();
+                    self.ready.push_back(Err(err));
+                }
+                None => {
+                    self.flush();
+                    return self.ready.pop_front();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reasons;
+    use std::{ffi::OsString, time::SystemTime};
+
+    fn mock_entry(usn: i64, fid: u64, reason: u32) -> UsnEntry {
+        UsnEntry {
+            usn,
+            time: SystemTime::UNIX_EPOCH,
+            fid: fid.into(),
+            parent_fid: FileId(0x5),
+            reason: reason.into(),
+            source_info: 0,
+            file_name: OsString::from(format!("file{fid}.txt")),
+            file_attributes: 0,
+        }
+    }
+
+    #[test]
+    fn test_with_previous_attaches_prior_entry_for_same_fid() {
+        let entries = vec![
+            Ok(mock_entry(0x100, 0x1, 0x1)),
+            Ok(mock_entry(0x200, 0x2, 0x1)),
+            Ok(mock_entry(0x300, 0x1, 0x2)),
+        ];
+
+        let results = WithPrevious::new(entries.into_iter(), NonZeroUsize::new(16).unwrap())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert!(results[0].1.is_none());
+        assert!(results[1].1.is_none());
+        let (entry, previous) = &results[2];
+        assert_eq!(entry.usn, 0x300);
+        let previous = previous.as_ref().unwrap();
+        assert_eq!(previous.usn, 0x100);
+        assert_eq!(previous.reason, UsnReason::DATA_OVERWRITE);
+    }
+
+    #[test]
+    fn test_chunks_batches_entries_by_requested_size() {
+        let entries = (1..=7)
+            .map(|i| Ok(mock_entry(i * 0x100, i as u64, 0x1)))
+            .collect::<Vec<_>>();
+
+        let batches = Chunks::new(entries.into_iter(), NonZeroUsize::new(3).unwrap())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        let sizes = batches.iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(sizes, vec![3, 3, 1]);
+        let usns = batches.concat().iter().map(|e| e.usn).collect::<Vec<_>>();
+        assert_eq!(usns, (1..=7).map(|i| i * 0x100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_chunks_flushes_batch_before_error() {
+        let entries = vec![
+            Ok(mock_entry(0x100, 0x1, 0x1)),
+            Ok(mock_entry(0x200, 0x2, 0x1)),
+            Err(UsnError::OtherError("boom".to_string())),
+            Ok(mock_entry(0x300, 0x3, 0x1)),
+        ];
+
+        let mut chunks = Chunks::new(entries.into_iter(), NonZeroUsize::new(3).unwrap());
+
+        assert_eq!(chunks.next().unwrap().unwrap().len(), 2);
+        assert!(matches!(chunks.next(), Some(Err(UsnError::OtherError(_)))));
+        assert_eq!(chunks.next().unwrap().unwrap()[0].usn, 0x300);
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn test_exclude_directory_metadata_drops_only_directory_noise() {
+        const DIRECTORY: u32 = 0x10;
+        let with_attributes = |mut entry: UsnEntry, attributes: u32| {
+            entry.file_attributes = attributes;
+            entry
+        };
+        let entries = vec![
+            // Directory noise: dropped
+            Ok(with_attributes(
+                mock_entry(0x100, 0x1, reasons::BASIC_INFO_CHANGE | reasons::CLOSE),
+                DIRECTORY,
+            )),
+            Ok(with_attributes(
+                mock_entry(0x200, 0x1, reasons::INDEXABLE_CHANGE),
+                DIRECTORY,
+            )),
+            // Directory structural changes: kept
+            Ok(with_attributes(
+                mock_entry(
+                    0x300,
+                    0x2,
+                    reasons::FILE_CREATE | reasons::BASIC_INFO_CHANGE,
+                ),
+                DIRECTORY,
+            )),
+            Ok(with_attributes(
+                mock_entry(0x400, 0x2, reasons::RENAME_NEW_NAME),
+                DIRECTORY,
+            )),
+            Ok(with_attributes(
+                mock_entry(0x500, 0x2, reasons::FILE_DELETE | reasons::CLOSE),
+                DIRECTORY,
+            )),
+            // File metadata changes: kept
+            Ok(mock_entry(0x600, 0x3, reasons::BASIC_INFO_CHANGE)),
+            Ok(mock_entry(0x700, 0x3, reasons::DATA_EXTEND)),
+        ];
+
+        let usns = ExcludeDirectoryMetadata::new(entries.into_iter())
+            .map(|result| result.map(|entry| entry.usn))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(usns, vec![0x300, 0x400, 0x500, 0x600, 0x700]);
+    }
+
+    fn named_entry(usn: i64, fid: u64, reason: u32, name: &str) -> UsnResult<UsnEntry> {
+        let mut entry = mock_entry(usn, fid, reason);
+        entry.file_name = OsString::from(name);
+        Ok(entry)
+    }
+
+    #[test]
+    fn test_atomic_saves_coalesces_temp_write_and_rename() {
+        use reasons::*;
+        const TEMP: u64 = 0x20;
+        const ORIGINAL: u64 = 0x10;
+        let entries = vec![
+            named_entry(0x100, TEMP, FILE_CREATE, "doc.txt.tmp"),
+            named_entry(0x110, TEMP, FILE_CREATE | DATA_EXTEND, "doc.txt.tmp"),
+            named_entry(
+                0x120,
+                TEMP,
+                FILE_CREATE | DATA_EXTEND | CLOSE,
+                "doc.txt.tmp",
+            ),
+            named_entry(0x130, 0x30, DATA_EXTEND, "other.txt"),
+            named_entry(0x140, ORIGINAL, RENAME_OLD_NAME, "doc.txt"),
+            named_entry(0x150, ORIGINAL, RENAME_NEW_NAME, "doc.txt~"),
+            named_entry(0x160, ORIGINAL, RENAME_NEW_NAME | CLOSE, "doc.txt~"),
+            named_entry(0x170, TEMP, RENAME_OLD_NAME, "doc.txt.tmp"),
+            named_entry(0x180, TEMP, RENAME_NEW_NAME, "doc.txt"),
+            named_entry(0x190, TEMP, RENAME_NEW_NAME | CLOSE, "doc.txt"),
+            named_entry(0x1A0, ORIGINAL, FILE_DELETE | CLOSE, "doc.txt~"),
+            named_entry(0x1B0, 0x30, DATA_EXTEND | CLOSE, "other.txt"),
+        ];
+
+        let events = AtomicSaves::new(entries.into_iter(), AtomicSaveOptions::default())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], SaveEvent::Entry(e) if e.usn == 0x130));
+        let SaveEvent::AtomicSave(save) = &events[1] else {
+            panic!("expected an atomic save, got {:?}", events[1]);
+        };
+        assert_eq!(save.fid, TEMP);
+        assert_eq!(save.file_name, "doc.txt");
+        let usns = save.entries.iter().map(|e| e.usn).collect::<Vec<_>>();
+        assert_eq!(
+            usns,
+            vec![0x100, 0x110, 0x120, 0x140, 0x150, 0x160, 0x170, 0x180]
+        );
+        assert!(matches!(&events[2], SaveEvent::Entry(e) if e.usn == 0x1B0));
+    }
+
+    #[test]
+    fn test_atomic_saves_releases_abandoned_temp_files() {
+        use reasons::*;
+        let entries = vec![
+            named_entry(0x100, 0x20, FILE_CREATE, "scratch.tmp"),
+            named_entry(0x110, 0x10, RENAME_OLD_NAME, "a.txt"),
+            named_entry(0x120, 0x10, RENAME_NEW_NAME, "b.txt"),
+            named_entry(0x130, 0x20, FILE_DELETE | CLOSE, "scratch.tmp"),
+        ];
+
+        let usns = AtomicSaves::new(entries.into_iter(), AtomicSaveOptions::default())
+            .map(|event| match event.unwrap() {
+                SaveEvent::Entry(entry) => entry.usn,
+                SaveEvent::AtomicSave(save) => panic!("unexpected save: {save:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(usns, vec![0x110, 0x120, 0x100, 0x130]);
+    }
+
+    #[test]
+    fn test_with_previous_evicts_least_recent_file_when_full() {
+        let entries = vec![
+            Ok(mock_entry(0x100, 0x1, 0x1)),
+            Ok(mock_entry(0x200, 0x2, 0x1)),
+            Ok(mock_entry(0x300, 0x1, 0x1)),
+            Ok(mock_entry(0x400, 0x2, 0x1)),
+        ];
+
+        let results = WithPrevious::new(entries.into_iter(), NonZeroUsize::new(1).unwrap())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert!(results.iter().all(|(_, previous)| previous.is_none()));
+    }
+
+    fn event_summary(event: UsnEvent) -> (Usn, Option<OsString>, OsString) {
+        match event {
+            UsnEvent::Entry(entry) => (entry.usn, None, entry.file_name),
+            UsnEvent::Renamed {
+                usn,
+                old_name,
+                new_name,
+                ..
+            } => (usn, old_name, new_name),
+        }
+    }
+
+    #[test]
+    fn test_coalesce_renames_pairs_old_and_new_name() {
+        use reasons::*;
+        let mut moved = mock_entry(0x120, 0x10, RENAME_NEW_NAME);
+        moved.file_name = OsString::from("b.txt");
+        moved.parent_fid = FileId(0x7);
+        let entries = vec![
+            named_entry(0x100, 0x10, RENAME_OLD_NAME, "a.txt"),
+            // Another file's record between the pair, as when they land in separate reads.
+            named_entry(0x110, 0x30, DATA_EXTEND, "other.txt"),
+            Ok(moved),
+            named_entry(0x130, 0x10, RENAME_NEW_NAME | CLOSE, "b.txt"),
+        ];
+
+        let events = CoalesceRenames::new(entries.into_iter())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], UsnEvent::Entry(e) if e.usn == 0x110));
+        let UsnEvent::Renamed {
+            fid,
+            old_name,
+            old_parent,
+            new_name,
+            new_parent,
+            usn,
+        } = &events[1]
+        else {
+            panic!("expected a rename, got {:?}", events[1]);
+        };
+        assert_eq!(*fid, 0x10);
+        assert_eq!(old_name.as_deref(), Some(OsStr::new("a.txt")));
+        assert_eq!(*old_parent, Some(FileId(0x5)));
+        assert_eq!(new_name, "b.txt");
+        assert_eq!(*new_parent, FileId(0x7));
+        assert_eq!(*usn, 0x120);
+        assert!(matches!(&events[2], UsnEvent::Entry(e) if e.usn == 0x130));
+    }
+
+    #[test]
+    fn test_coalesce_renames_pairs_interleaved_renames() {
+        use reasons::*;
+        let entries = vec![
+            named_entry(0x100, 0x10, RENAME_OLD_NAME, "a.txt"),
+            named_entry(0x110, 0x20, RENAME_OLD_NAME, "c.txt"),
+            named_entry(0x120, 0x10, RENAME_NEW_NAME, "b.txt"),
+            named_entry(0x130, 0x20, RENAME_NEW_NAME, "d.txt"),
+            named_entry(0x140, 0x10, RENAME_NEW_NAME | DATA_EXTEND, "b.txt"),
+            named_entry(0x150, 0x10, RENAME_NEW_NAME | DATA_EXTEND | CLOSE, "b.txt"),
+            named_entry(0x160, 0x20, RENAME_NEW_NAME | CLOSE, "d.txt"),
+            // Closed since its last rename, so a new-name record is a rename again.
+            named_entry(0x170, 0x10, RENAME_NEW_NAME, "e.txt"),
+        ];
+
+        let events = CoalesceRenames::new(entries.into_iter())
+            .map(|event| match event.unwrap() {
+                UsnEvent::Entry(entry) => (entry.usn, false),
+                UsnEvent::Renamed { usn, .. } => (usn, true),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                (0x120, true),
+                (0x130, true),
+                (0x140, false),
+                (0x150, false),
+                (0x160, false),
+                (0x170, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_renames_keeps_pending_old_name_across_errors() {
+        use reasons::*;
+        let entries = vec![
+            named_entry(0x100, 0x10, RENAME_OLD_NAME, "a.txt"),
+            Err(UsnError::OtherError("read failed".to_string())),
+            named_entry(0x120, 0x10, RENAME_NEW_NAME, "b.txt"),
+        ];
+
+        let mut events = CoalesceRenames::new(entries.into_iter());
+
+        assert!(matches!(events.next(), Some(Err(UsnError::OtherError(_)))));
+        let (usn, old_name, new_name) = event_summary(events.next().unwrap().unwrap());
+        assert_eq!(usn, 0x120);
+        assert_eq!(old_name.as_deref(), Some(OsStr::new("a.txt")));
+        assert_eq!(new_name, "b.txt");
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn test_coalesce_renames_handles_unpaired_records() {
+        use reasons::*;
+        let entries = vec![
+            // New name whose old-name record was never read: best-effort rename.
+            named_entry(0x100, 0x10, RENAME_NEW_NAME, "b.txt"),
+            // Old name followed by an unrelated change: released as a plain entry.
+            named_entry(0x110, 0x20, RENAME_OLD_NAME, "c.txt"),
+            named_entry(0x120, 0x20, DATA_EXTEND, "c.txt"),
+            // Old name still pending at the end: released as a plain entry.
+            named_entry(0x130, 0x30, RENAME_OLD_NAME, "d.txt"),
+        ];
+
+        let events = CoalesceRenames::new(entries.into_iter())
+            .map(|event| event_summary(event.unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                (0x100, None, OsString::from("b.txt")),
+                (0x110, None, OsString::from("c.txt")),
+                (0x120, None, OsString::from("c.txt")),
+                (0x130, None, OsString::from("d.txt")),
+            ]
+        );
+        assert!(matches!(
+            CoalesceRenames::new(
+                vec![named_entry(0x100, 0x10, RENAME_NEW_NAME, "b.txt")].into_iter()
+            )
+            .next(),
+            Some(Ok(UsnEvent::Renamed { old_name: None, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_transactions_groups_consecutive_transacted_changes() {
+        use reasons::*;
+        let at = |usn: i64, fid: u64, reason: u32, millis: u64| {
+            let mut entry = mock_entry(usn, fid, reason);
+            entry.time = SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+            Ok(entry)
+        };
+        let entries = vec![
+            at(0x100, 0x1, DATA_EXTEND, 0),
+            at(0x110, 0x2, TRANSACTED_CHANGE | FILE_CREATE, 1000),
+            at(0x120, 0x3, TRANSACTED_CHANGE | DATA_EXTEND, 1010),
+            at(0x130, 0x2, TRANSACTED_CHANGE | CLOSE, 1020),
+            // Too long after the previous transacted record: a separate transaction.
+            at(0x140, 0x4, TRANSACTED_CHANGE | FILE_DELETE, 5000),
+            Err(UsnError::OtherError("read failed".to_string())),
+            at(0x150, 0x5, TRANSACTED_CHANGE | FILE_CREATE, 5010),
+            at(0x160, 0x1, CLOSE, 5020),
+        ];
+
+        let events = Transactions::new(entries.into_iter(), Duration::from_millis(100))
+            .map(|event| match event {
+                Ok(TransactionEvent::Entry(entry)) => format!("entry {:x}", entry.usn),
+                Ok(TransactionEvent::Transaction(tx)) => {
+                    let usns = tx.entries.iter().map(|e| format!("{:x}", e.usn));
+                    format!("tx {}", usns.collect::<Vec<_>>().join(","))
+                }
+                Err(_) => "error".to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                "entry 100",
+                "tx 110,120,130",
+                "tx 140",
+                "error",
+                "tx 150",
+                "entry 160"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_per_file_throttles_only_the_busy_file() {
+        const BUSY: u64 = 0x1;
+        const QUIET: u64 = 0x2;
+        let at = |usn: i64, fid: u64, millis: u64| {
+            let mut entry = mock_entry(usn, fid, reasons::DATA_EXTEND);
+            entry.time = SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+            Ok(entry)
+        };
+        let mut entries = (0..20)
+            .map(|i| at(0x100 + i, BUSY, i as u64 * 10))
+            .collect::<Vec<_>>();
+        entries.insert(5, at(0x500, QUIET, 50));
+        entries.push(at(0x600, QUIET, 500));
+        // A new window for the busy file.
+        entries.push(at(0x700, BUSY, 1_000));
+
+        let kept = RateLimitPerFile::new(entries.into_iter(), 3, Duration::from_secs(1))
+            .map(|result| result.map(|entry| (entry.fid, entry.usn)))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        let usns_of = |fid: u64| {
+            kept.iter()
+                .filter(|(entry_fid, _)| *entry_fid == FileId::from(fid))
+                .map(|(_, usn)| *usn)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(usns_of(BUSY), vec![0x100, 0x101, 0x102, 0x700]);
+        assert_eq!(usns_of(QUIET), vec![0x500, 0x600]);
+    }
+
+    #[test]
+    fn test_file_records_unify_mft_and_journal_entries() {
+        fn summarize<I>(records: I) -> (usize, usize)
+        where
+            I: Iterator<Item = UsnResult<FileRecord>>,
+        {
+            records.fold((0, 0), |(dirs, changes), record| {
+                let record = record.unwrap();
+                (
+                    dirs + usize::from(record.is_dir()),
+                    changes + usize::from(record.change.is_some()),
+                )
+            })
+        }
+
+        let mft_entries = vec![
+            Ok(MftEntry {
+                usn: 0,
+                fid: FileId(0x10),
+                parent_fid: FileId(0x5),
+                file_name: OsString::from("docs"),
+                file_attributes: 0x10,
+                timestamps: None,
+                sizes: None,
+            }),
+            Ok(MftEntry {
+                usn: 0,
+                fid: FileId(0x11),
+                parent_fid: FileId(0x10),
+                file_name: OsString::from("notes.txt"),
+                file_attributes: 0x20,
+                timestamps: None,
+                sizes: None,
+            }),
+        ];
+        let journal_entries = || vec![Ok(mock_entry(0x100, 0x11, reasons::DATA_EXTEND))];
+
+        assert_eq!(summarize(FileRecords::new(mft_entries.into_iter())), (1, 0));
+        assert_eq!(
+            summarize(FileRecords::new(journal_entries().into_iter())),
+            (0, 1)
+        );
+
+        let record = FileRecords::new(journal_entries().into_iter())
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.fid, FileId(0x11));
+        assert_eq!(record.change.unwrap().reason, UsnReason::DATA_EXTEND);
+    }
+
+    #[test]
+    fn test_watch_directory_shallow_reports_immediate_children_only() {
+        const DIR: u64 = 0x5;
+        let with_parent = |mut entry: UsnEntry, parent: u64| {
+            entry.parent_fid = parent.into();
+            entry
+        };
+        let entries = vec![
+            // Immediate children: reported
+            Ok(mock_entry(0x100, 0x1, reasons::FILE_CREATE)),
+            Ok(mock_entry(
+                0x200,
+                0x2,
+                reasons::DATA_EXTEND | reasons::CLOSE,
+            )),
+            // Grandchild inside subdirectory 0x2: skipped
+            Ok(with_parent(
+                mock_entry(0x300, 0x3, reasons::FILE_CREATE),
+                0x2,
+            )),
+            // File moved in from elsewhere: only the new-name record is a child change
+            Ok(with_parent(
+                mock_entry(0x400, 0x4, reasons::RENAME_OLD_NAME),
+                0x9,
+            )),
+            Ok(mock_entry(0x500, 0x4, reasons::RENAME_NEW_NAME)),
+            // Metadata change on the watched directory itself: skipped
+            Ok(with_parent(
+                mock_entry(0x600, DIR, reasons::BASIC_INFO_CHANGE),
+                0x1,
+            )),
+        ];
+
+        let events = WatchDirectoryShallow::new(entries.into_iter(), DIR.into())
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        let usns: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                DirectoryEvent::Child(entry) => entry.usn,
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+        assert_eq!(usns, [0x100, 0x200, 0x500]);
+    }
+
+    #[test]
+    fn test_watch_directory_shallow_follows_directory_rename_and_delete() {
+        const DIR: u64 = 0x5;
+        let directory = |usn: i64, reason: u32| {
+            let mut entry = mock_entry(usn, DIR, reason);
+            entry.parent_fid = FileId(0x1);
+            entry
+        };
+        let entries = vec![
+            Ok(directory(0x100, reasons::RENAME_OLD_NAME)),
+            Ok(directory(0x200, reasons::RENAME_NEW_NAME)),
+            Err(UsnError::OtherError("read failed".to_string())),
+            Ok(mock_entry(
+                0x300,
+                0x1,
+                reasons::FILE_DELETE | reasons::CLOSE,
+            )),
+            Ok(directory(0x400, reasons::FILE_DELETE | reasons::CLOSE)),
+            // Never reached: the watch ends with the directory
+            Ok(mock_entry(0x500, 0x2, reasons::FILE_CREATE)),
+        ];
+
+        let mut events = WatchDirectoryShallow::new(entries.into_iter(), DIR.into());
+
+        assert!(matches!(
+            events.next(),
+            Some(Ok(DirectoryEvent::Renamed(entry))) if entry.usn == 0x200
+        ));
+        assert!(matches!(events.next(), Some(Err(UsnError::OtherError(_)))));
+        assert!(matches!(
+            events.next(),
+            Some(Ok(DirectoryEvent::Child(entry))) if entry.usn == 0x300
+        ));
+        assert!(matches!(
+            events.next(),
+            Some(Ok(DirectoryEvent::Deleted(entry))) if entry.usn == 0x400
+        ));
+        assert!(events.next().is_none());
+    }
+}
@@ -0,0 +1,275 @@
+//! Cancellation of blocking journal reads.
+//!
+//! With `wait_for_more` set, a journal read blocks inside `DeviceIoControl` until new records
+//! arrive. [`UsnJournal::iter_cancellable`](crate::journal::UsnJournal::iter_cancellable)
+//! reopens the volume for overlapped I/O and waits on both the read and a cancellation event,
+//! so a [`Canceller`] can end the iteration from another thread.
+
+use std::{sync::Arc, time::Instant};
+use windows::{
+    Win32::{
+        Foundation::{ERROR_IO_PENDING, HANDLE, WAIT_OBJECT_0},
+        Storage::FileSystem::{
+            FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED, FILE_GENERIC_READ,
+            FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, ReOpenFile,
+        },
+        System::{
+            IO::{CancelIoEx, DeviceIoControl, GetOverlappedResult, OVERLAPPED},
+            Threading::{
+                CreateEventW, INFINITE, SetEvent, WaitForMultipleObjects, WaitForSingleObject,
+            },
+        },
+    },
+    core::Owned,
+};
+
+/// Manual-reset event shared by a [`Canceller`] and the iterator it cancels.
+struct CancelEvent(Owned<HANDLE>);
+
+// SAFETY: an event handle is valid process-wide; it is only passed to `SetEvent` and the
+// wait functions, and closed once when the last owner drops it.
+unsafe impl Send for CancelEvent {}
+// SAFETY: as above, shared references only ever pass the handle value to thread-safe calls.
+unsafe impl Sync for CancelEvent {}
+
+impl CancelEvent {
+    fn new() -> windows::core::Result<Self> {
+        // SAFETY: creates an unnamed, initially unsignalled manual-reset event that is closed
+        // when `Owned` drops.
+        let event = unsafe { Owned::new(CreateEventW(None, true, false, None)?) };
+        Ok(CancelEvent(event))
+    }
+
+    fn is_set(&self) -> bool {
+        // SAFETY: the event handle is open for as long as `self` lives.
+        unsafe { WaitForSingleObject(*self.0, 0) == WAIT_OBJECT_0 }
+    }
+}
+
+/// Handle for ending a cancellable journal iteration from another thread.
+///
+/// Created by [`UsnJournal::iter_cancellable`](crate::journal::UsnJournal::iter_cancellable).
+/// Clones share the same iteration. Dropping every canceller leaves the iterator running.
+#[derive(Clone)]
+pub struct Canceller {
+    event: Arc<CancelEvent>,
+}
+
+impl Canceller {
+    /// Ends the iteration.
+    ///
+    /// A read blocked waiting for more records is aborted with `CancelIoEx`, and the iterator
+    /// returns `None` from then on. Calling this more than once has no further effect.
+    pub fn cancel(&self) {
+        // SAFETY: the event handle is open for as long as `self` lives.
+        let _ = unsafe { SetEvent(*self.event.0) };
+    }
+
+    /// Returns true once [`Canceller::cancel`] has been called on this or a cloned canceller.
+    pub fn is_cancelled(&self) -> bool {
+        self.event.is_set()
+    }
+}
+
+impl std::fmt::Debug for Canceller {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Canceller")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Overlapped volume handle that journal reads can be cancelled on.
+pub(crate) struct CancellableRead {
+    handle: Owned<HANDLE>,
+    // Signalled by the kernel when a read completes.
+    completion: Owned<HANDLE>,
+    cancel: Arc<CancelEvent>,
+    // Reads still pending at this point are aborted as if cancelled.
+    deadline: Option<Instant>,
+}
+
+// SAFETY: the handles are valid process-wide and only used through `&mut self` of the
+// owning iterator, which waits for every read to finish before returning.
+unsafe impl Send for CancellableRead {}
+// SAFETY: shared references only read `cancel`, which is itself `Sync`.
+unsafe impl Sync for CancellableRead {}
+
+impl CancellableRead {
+    /// Reopens `volume` for overlapped reads, returning the reader and its canceller.
+    ///
+    /// `unprivileged` must match how `volume` was opened, since a root-directory handle only
+    /// grants attribute access.
+    pub(crate) fn new(
+        volume: HANDLE,
+        unprivileged: bool,
+    ) -> windows::core::Result<(Self, Canceller)> {
+        let (access, flags) = if unprivileged {
+            (
+                FILE_READ_ATTRIBUTES.0,
+                FILE_FLAG_OVERLAPPED | FILE_FLAG_BACKUP_SEMANTICS,
+            )
+        } else {
+            (FILE_GENERIC_READ.0, FILE_FLAG_OVERLAPPED)
+        };
+        // SAFETY: `volume` is an open handle; the new handle is closed when `Owned` drops.
+        let handle = unsafe {
+            Owned::new(ReOpenFile(
+                volume,
+                access,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                flags,
+            )?)
+        };
+        // SAFETY: as for `CancelEvent::new`.
+        let completion = unsafe { Owned::new(CreateEventW(None, true, false, None)?) };
+        let cancel = Arc::new(CancelEvent::new()?);
+
+        let canceller = Canceller {
+            event: Arc::clone(&cancel),
+        };
+        Ok((
+            CancellableRead {
+                handle,
+                completion,
+                cancel,
+                deadline: None,
+            },
+            canceller,
+        ))
+    }
+
+    /// Cancels the iteration once `deadline` has passed, aborting a read still pending then.
+    pub(crate) fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_set()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns how long to wait for a pending read, in milliseconds.
+    fn wait_millis(&self) -> u32 {
+        match self.deadline {
+            // Round up, so the deadline has passed by the time the wait times out.
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .as_micros()
+                .div_ceil(1000)
+                .min(u128::from(INFINITE - 1)) as u32,
+            None => INFINITE,
+        }
+    }
+
+    /// Issues `control_code` like a synchronous `DeviceIoControl`, unless cancelled first.
+    ///
+    /// A cancelled read, or one still pending at the deadline, fails with
+    /// `ERROR_OPERATION_ABORTED`. The call always waits for the
+    /// read to finish, so `output` is never written to after it returns.
+    pub(crate) fn device_io_control<T>(
+        &self,
+        control_code: u32,
+        input: &T,
+        output: &mut [u8],
+        bytes_returned: &mut u32,
+    ) -> windows::core::Result<()> {
+        let mut overlapped = OVERLAPPED {
+            hEvent: *self.completion,
+            ..Default::default()
+        };
+        // SAFETY: `input`, `output` and `overlapped` stay borrowed until the read has
+        // completed or been aborted, which `GetOverlappedResult` waits for below.
+        let started = unsafe {
+            DeviceIoControl(
+                *self.handle,
+                control_code,
+                Some(input as *const T as *const _),
+                size_of::<T>() as u32,
+                Some(output.as_mut_ptr() as *mut _),
+                output.len() as u32,
+                None,
+                Some(&mut overlapped),
+            )
+        };
+        match started {
+            Ok(()) => {}
+            Err(err) if err.code() == ERROR_IO_PENDING.to_hresult() => {
+                let events = [*self.completion, *self.cancel.0];
+                // SAFETY: both events are open for as long as `self` lives.
+                let signalled =
+                    unsafe { WaitForMultipleObjects(&events, false, self.wait_millis()) };
+                if signalled != WAIT_OBJECT_0 {
+                    // SAFETY: `overlapped` identifies the read issued above on this handle.
+                    // Fails harmlessly if the read completed in the meantime.
+                    let _ = unsafe { CancelIoEx(*self.handle, Some(&overlapped)) };
+                }
+            }
+            Err(err) => return Err(err),
+        }
+
+        // SAFETY: `overlapped` belongs to the read issued above on this handle.
+        unsafe { GetOverlappedResult(*self.handle, &overlapped, bytes_returned, true) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{errors::UsnError, journal::EnumOptions, volume::Volume};
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn test_canceller_clones_share_the_event() -> windows::core::Result<()> {
+        let canceller = Canceller {
+            event: Arc::new(CancelEvent::new()?),
+        };
+        let clone = canceller.clone();
+        assert!(!canceller.is_cancelled());
+
+        clone.cancel();
+        assert!(canceller.is_cancelled());
+        clone.cancel();
+        assert!(clone.is_cancelled());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel_ends_waiting_iterator() -> Result<(), UsnError> {
+        let volume = match Volume::from_drive_letter('C') {
+            Ok(volume) => volume,
+            Err(UsnError::PermissionError(_)) => {
+                eprintln!("Skipping test - requires admin privileges");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let journal = volume.journal();
+        let (iter, canceller) = journal.iter_cancellable(EnumOptions {
+            start_usn: journal.query()?.next_usn,
+            wait_for_more: true,
+            timeout: 0,
+            ..Default::default()
+        })?;
+
+        let (done, finished) = mpsc::channel();
+        let reader = thread::spawn(move || {
+            let errors = iter.filter(Result::is_err).count();
+            let _ = done.send(errors);
+        });
+
+        thread::sleep(Duration::from_millis(200));
+        canceller.cancel();
+        let errors = finished
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| UsnError::OtherError("cancelled iterator kept blocking".to_string()))?;
+        reader
+            .join()
+            .map_err(|_| UsnError::OtherError("reader thread panicked".to_string()))?;
+
+        assert_eq!(errors, 0, "cancellation should end the iterator cleanly");
+        Ok(())
+    }
+}
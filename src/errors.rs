@@ -1,13 +1,16 @@
 //! This module defines the custom error types.
 
+use crate::{FileId, Usn};
 use thiserror::Error;
+use windows::Win32::Foundation::{ERROR_INVALID_FUNCTION, ERROR_NOT_SUPPORTED};
 
 /// Custom error type for USN Journal and MFT operations.
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum UsnError {
-    #[error("Access denied: Administrator privileges required.")]
-    PermissionError,
+    /// Carries the detected cause, such as `not elevated` or `missing SeBackupPrivilege`.
+    #[error("Access denied: {0}")]
+    PermissionError(String),
 
     #[error("Invalid mount point: {0}")]
     InvalidMountPointError(String),
@@ -18,8 +21,67 @@ pub enum UsnError {
     #[error("Windows API error: {0}")]
     WinApiError(#[from] windows::core::Error),
 
+    /// The volume rejected a USN journal or MFT control code, with the error it returned.
+    #[error(
+        "The volume does not support USN journals; it is probably not formatted as NTFS or ReFS (e.g. FAT32 or exFAT): {0}"
+    )]
+    UnsupportedFilesystem(windows::core::Error),
+
     #[error("Other error: {0}")]
     OtherError(String),
+
+    #[error("Unsupported USN record version: {0}")]
+    UnsupportedRecordVersion(u16),
+
+    #[error("USN journal is not active on this volume")]
+    JournalNotActive,
+
+    #[error("USN journal is being deleted")]
+    JournalDeleted,
+
+    /// The file has not changed since the journal was created, so it has no record yet.
+    #[error("USN journal has no record for file {0}")]
+    NoJournalRecord(FileId),
+
+    #[error("Invalid cursor token: {0}")]
+    InvalidCursorToken(String),
+
+    /// A file or cursor belongs to a different volume than the one it was used with.
+    #[error("Volume serial number mismatch: expected {expected:08X}, found {actual:08X}")]
+    VolumeMismatch { expected: u32, actual: u32 },
+
+    #[error("USN journal ID changed: expected 0x{expected:x}, found 0x{actual:x}")]
+    JournalIdChanged { expected: u64, actual: u64 },
+
+    #[error(
+        "USN journal records were overwritten before they were read: resumed at {resumed_at} instead of {requested}"
+    )]
+    JournalGap { requested: Usn, resumed_at: Usn },
+
+    /// An entry's USN was not greater than that of the entry yielded before it.
+    #[error("USN went backwards or repeated: {current} after {prev}")]
+    NonMonotonicUsn { prev: Usn, current: Usn },
+
+    #[error(
+        "USN journal wrapped past read position {read_usn}: lowest valid USN moved from {old_lowest_valid_usn} to {new_lowest_valid_usn}"
+    )]
+    JournalWrapped {
+        read_usn: Usn,
+        old_lowest_valid_usn: Usn,
+        new_lowest_valid_usn: Usn,
+    },
+}
+
+/// Converts the error of a journal or MFT control code, recognizing volumes without journals.
+///
+/// File systems such as FAT32 and exFAT do not implement these control codes and fail them
+/// with `ERROR_INVALID_FUNCTION` or `ERROR_NOT_SUPPORTED`.
+pub(crate) fn fsctl_error(err: windows::core::Error) -> UsnError {
+    if err.code() == ERROR_INVALID_FUNCTION.into() || err.code() == ERROR_NOT_SUPPORTED.into() {
+        UsnError::UnsupportedFilesystem(err)
+    } else {
+        UsnError::WinApiError(err)
+    }
 }
 
 #[cfg(test)]
@@ -34,12 +96,9 @@ mod tests {
 
         #[test]
         fn test_permission_error_display() {
-            let error = UsnError::PermissionError;
+            let error = UsnError::PermissionError("not elevated".to_string());
             let error_string = error.to_string();
-            assert_eq!(
-                error_string,
-                "Access denied: Administrator privileges required."
-            );
+            assert_eq!(error_string, "Access denied: not elevated");
         }
 
         #[test]
@@ -50,6 +109,12 @@ mod tests {
             assert_eq!(error_string, "Invalid mount point: C:\\invalid\\path");
         }
 
+        #[test]
+        fn test_no_journal_record_error_display() {
+            let error = UsnError::NoJournalRecord(FileId(0x2a));
+            assert_eq!(error.to_string(), "USN journal has no record for file 0x2a");
+        }
+
         #[test]
         fn test_other_error_display() {
             let message = "Custom error message";
@@ -58,6 +123,85 @@ mod tests {
             assert_eq!(error_string, "Other error: Custom error message");
         }
 
+        #[test]
+        fn test_unsupported_record_version_error_display() {
+            let error = UsnError::UnsupportedRecordVersion(9);
+            assert_eq!(error.to_string(), "Unsupported USN record version: 9");
+        }
+
+        #[test]
+        fn test_volume_mismatch_error_display() {
+            let error = UsnError::VolumeMismatch {
+                expected: 0x1A2B_3C4D,
+                actual: 0x0000_BEEF,
+            };
+            assert_eq!(
+                error.to_string(),
+                "Volume serial number mismatch: expected 1A2B3C4D, found 0000BEEF"
+            );
+        }
+
+        #[test]
+        fn test_journal_state_error_display() {
+            assert_eq!(
+                UsnError::JournalNotActive.to_string(),
+                "USN journal is not active on this volume"
+            );
+            assert_eq!(
+                UsnError::JournalDeleted.to_string(),
+                "USN journal is being deleted"
+            );
+        }
+
+        #[test]
+        fn test_non_monotonic_usn_error_display() {
+            let error = UsnError::NonMonotonicUsn {
+                prev: 0x200,
+                current: 0x100,
+            };
+            assert_eq!(
+                error.to_string(),
+                "USN went backwards or repeated: 256 after 512"
+            );
+        }
+
+        #[test]
+        fn test_journal_id_changed_error_display() {
+            let error = UsnError::JournalIdChanged {
+                expected: 0x1234,
+                actual: 0xabcd,
+            };
+            assert_eq!(
+                error.to_string(),
+                "USN journal ID changed: expected 0x1234, found 0xabcd"
+            );
+        }
+
+        #[test]
+        fn test_journal_gap_error_display() {
+            let error = UsnError::JournalGap {
+                requested: 0x1000,
+                resumed_at: 0x8000,
+            };
+            assert_eq!(
+                error.to_string(),
+                "USN journal records were overwritten before they were read: resumed at 32768 instead of 4096"
+            );
+        }
+
+        #[test]
+        fn test_journal_wrapped_error_display() {
+            let error = UsnError::JournalWrapped {
+                read_usn: 0x1000,
+                old_lowest_valid_usn: 0x800,
+                new_lowest_valid_usn: 0x2000,
+            };
+            assert_eq!(
+                error.to_string(),
+                "USN journal wrapped past read position 4096: lowest valid USN moved from 2048 to 8192"
+            );
+        }
+
         #[test]
         fn test_io_error_conversion() {
             let io_error = IoError::new(ErrorKind::NotFound, "File not found");
@@ -103,7 +247,7 @@ mod tests {
         fn test_result_type_integration() {
             // Test that UsnError works correctly with Result types
             fn returns_permission_error() -> Result<(), UsnError> {
-                Err(UsnError::PermissionError)
+                Err(UsnError::PermissionError("not elevated".to_string()))
             }
 
             fn returns_ok() -> Result<String, UsnError> {
@@ -137,12 +281,8 @@ mod tests {
         #[test]
         fn test_common_permission_scenarios() {
             // Test that permission errors have the expected message
-            let error = UsnError::PermissionError;
-            assert!(
-                error
-                    .to_string()
-                    .contains("Administrator privileges required")
-            );
+            let error = UsnError::PermissionError("missing SeBackupPrivilege".to_string());
+            assert!(error.to_string().contains("SeBackupPrivilege"));
         }
 
         #[test]
@@ -182,5 +322,26 @@ mod tests {
                 }
             }
         }
+
+        #[test]
+        fn test_fsctl_error_maps_unsupported_filesystem_codes() {
+            use windows::Win32::Foundation::ERROR_INVALID_HANDLE;
+
+            for code in [ERROR_INVALID_FUNCTION, ERROR_NOT_SUPPORTED] {
+                let usn_error = fsctl_error(windows::core::Error::from(code));
+                match usn_error {
+                    UsnError::UnsupportedFilesystem(ref e) => assert_eq!(e.code(), code.into()),
+                    _ => panic!("Expected UnsupportedFilesystem variant"),
+                }
+                assert!(
+                    usn_error
+                        .to_string()
+                        .contains("not formatted as NTFS or ReFS")
+                );
+            }
+
+            let usn_error = fsctl_error(windows::core::Error::from(ERROR_INVALID_HANDLE));
+            assert!(matches!(usn_error, UsnError::WinApiError(_)));
+        }
     }
 }
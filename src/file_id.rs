@@ -0,0 +1,101 @@
+//! File reference numbers that identify files and directories on a volume.
+
+use std::{fmt, num::TryFromIntError};
+
+/// A file reference number, wide enough for both NTFS and ReFS.
+///
+/// `USN_RECORD_V2` carries 64-bit file reference numbers, while `USN_RECORD_V3` (used by ReFS,
+/// and by NTFS on request) carries 128-bit `FILE_ID_128` values. 64-bit IDs convert to and
+/// from `FileId` without loss, and compare equal to the `u64` they were created from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileId(pub u128);
+
+impl FileId {
+    /// Creates a `FileId` from a 64-bit NTFS file reference number.
+    pub const fn from_u64(id: u64) -> Self {
+        FileId(id as u128)
+    }
+
+    /// Returns the ID as a 64-bit file reference number, or `None` if it needs more bits.
+    pub const fn as_u64(self) -> Option<u64> {
+        if self.0 <= u64::MAX as u128 {
+            Some(self.0 as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Creates a `FileId` from the little-endian bytes of a `FILE_ID_128`.
+    pub const fn from_le_bytes(bytes: [u8; 16]) -> Self {
+        FileId(u128::from_le_bytes(bytes))
+    }
+
+    /// Returns the ID as the little-endian bytes of a `FILE_ID_128`.
+    pub const fn to_le_bytes(self) -> [u8; 16] {
+        self.0.to_le_bytes()
+    }
+}
+
+impl From<u64> for FileId {
+    fn from(id: u64) -> Self {
+        FileId::from_u64(id)
+    }
+}
+
+impl TryFrom<FileId> for u64 {
+    type Error = TryFromIntError;
+
+    fn try_from(id: FileId) -> Result<Self, Self::Error> {
+        u64::try_from(id.0)
+    }
+}
+
+impl PartialEq<u64> for FileId {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == u128::from(*other)
+    }
+}
+
+impl fmt::Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_u64_ids_round_trip() {
+        let ntfs_id = 0x0005_0000_0000_1234u64;
+        let id = FileId::from(ntfs_id);
+
+        assert_eq!(id, ntfs_id);
+        assert_eq!(id.as_u64(), Some(ntfs_id));
+        assert_eq!(u64::try_from(id), Ok(ntfs_id));
+        assert_eq!(format!("{id}"), "0x5000000001234");
+    }
+
+    #[test]
+    fn test_128_bit_ids_do_not_fit_u64() {
+        let id = FileId(0x0001_0000_0000_0000_0000_0000_0000_0042);
+
+        assert_eq!(id.as_u64(), None);
+        assert!(u64::try_from(id).is_err());
+        assert_eq!(FileId::from_le_bytes(id.to_le_bytes()), id);
+    }
+}
@@ -5,49 +5,70 @@
 //!
 
 use crate::{
-    DEFAULT_BUFFER_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA, DEFAULT_JOURNAL_MAX_SIZE,
-    USN_REASON_MASK_ALL, Usn, UsnResult, time,
+    DEFAULT_BUFFER_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA, DEFAULT_JOURNAL_MAX_SIZE, FileId,
+    NameEncodingPolicy, USN_REASON_MASK_ALL, UnknownVersionPolicy, Usn, UsnReason, UsnResult, time,
+};
+use crate::{
+    adapters::{
+        AtomicSaveOptions, AtomicSaves, Checked, Chunks, CoalesceRenames, ExcludeDirectoryMetadata,
+        FileRecords, RateLimitPerFile, Transactions, WatchDirectoryShallow, WithPrevious,
+    },
+    cancel::{CancellableRead, Canceller},
+    errors::{self, UsnError},
+    mft::{Mft, MftEntry, MftIter, RECORD_NUMBER_MASK},
+    path::{self, CreationsUnder, IgnoreSubtrees, PathResolver, ResolvePaths, WithPaths},
+    token,
+    usn_record::{self, AlignedBuffer, ExtensionFilter},
+    volume::{self, Volume, VolumeHandle},
 };
-use crate::{errors::UsnError, usn_record, volume::Volume};
 use chrono::{DateTime, Local};
 use log::{debug, warn};
-use std::path::Path;
-use std::rc::Rc;
-use std::{ffi::OsString, time::SystemTime};
-use std::{ffi::c_void, mem::size_of};
-use windows::{
-    Win32::{
-        Foundation::{ERROR_HANDLE_EOF, ERROR_JOURNAL_NOT_ACTIVE, HANDLE},
-        Storage::FileSystem::{
-            FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_HIDDEN, FILE_FLAGS_AND_ATTRIBUTES,
-        },
-        System::{
-            IO::DeviceIoControl,
-            Ioctl::{
-                CREATE_USN_JOURNAL_DATA, DELETE_USN_JOURNAL_DATA, FSCTL_CREATE_USN_JOURNAL,
-                FSCTL_DELETE_USN_JOURNAL, FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_USN_JOURNAL,
-                READ_USN_JOURNAL_DATA_V0, USN_DELETE_FLAG_DELETE, USN_DELETE_FLAG_NOTIFY,
-                USN_DELETE_FLAGS, USN_JOURNAL_DATA_V0, USN_REASON_BASIC_INFO_CHANGE,
-                USN_REASON_CLOSE, USN_REASON_COMPRESSION_CHANGE, USN_REASON_DATA_EXTEND,
-                USN_REASON_DATA_OVERWRITE, USN_REASON_DATA_TRUNCATION,
-                USN_REASON_DESIRED_STORAGE_CLASS_CHANGE, USN_REASON_EA_CHANGE,
-                USN_REASON_ENCRYPTION_CHANGE, USN_REASON_FILE_CREATE, USN_REASON_FILE_DELETE,
-                USN_REASON_HARD_LINK_CHANGE, USN_REASON_INDEXABLE_CHANGE,
-                USN_REASON_INTEGRITY_CHANGE, USN_REASON_NAMED_DATA_EXTEND,
-                USN_REASON_NAMED_DATA_OVERWRITE, USN_REASON_NAMED_DATA_TRUNCATION,
-                USN_REASON_OBJECT_ID_CHANGE, USN_REASON_RENAME_NEW_NAME,
-                USN_REASON_RENAME_OLD_NAME, USN_REASON_REPARSE_POINT_CHANGE,
-                USN_REASON_SECURITY_CHANGE, USN_REASON_STREAM_CHANGE, USN_REASON_TRANSACTED_CHANGE,
-            },
+use std::hash::BuildHasher;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    time::{Duration, Instant, SystemTime},
+};
+use std::{
+    ffi::c_void,
+    mem::size_of,
+    os::windows::io::{AsHandle, AsRawHandle},
+};
+use windows::Win32::{
+    Foundation::{
+        ERROR_HANDLE_EOF, ERROR_INSUFFICIENT_BUFFER, ERROR_JOURNAL_DELETE_IN_PROGRESS,
+        ERROR_JOURNAL_ENTRY_DELETED, ERROR_JOURNAL_NOT_ACTIVE, ERROR_OPERATION_ABORTED, HANDLE,
+    },
+    Storage::FileSystem::{
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_DIRECTORY,
+        FILE_ATTRIBUTE_ENCRYPTED, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_OFFLINE,
+        FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_REPARSE_POINT, FILE_ATTRIBUTE_SPARSE_FILE,
+        FILE_ATTRIBUTE_SYSTEM, FILE_FLAGS_AND_ATTRIBUTES,
+    },
+    System::{
+        IO::DeviceIoControl,
+        Ioctl::{
+            CREATE_USN_JOURNAL_DATA, DELETE_USN_JOURNAL_DATA, FSCTL_CREATE_USN_JOURNAL,
+            FSCTL_DELETE_USN_JOURNAL, FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_FILE_USN_DATA,
+            FSCTL_READ_UNPRIVILEGED_USN_JOURNAL, FSCTL_READ_USN_JOURNAL,
+            FSCTL_WRITE_USN_CLOSE_RECORD, READ_FILE_USN_DATA, READ_USN_JOURNAL_DATA_V1,
+            USN_DELETE_FLAG_DELETE, USN_DELETE_FLAG_NOTIFY, USN_DELETE_FLAGS, USN_JOURNAL_DATA_V0,
         },
     },
-    core::Owned,
 };
 
 #[derive(Debug, Clone)]
 /// Options for enumerating the USN journal.
 ///
 /// Allows customization of the starting USN, reason mask, buffer size, and other parameters.
+/// With the `serde` feature, fields missing from the input take their default values.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct EnumOptions {
     pub start_usn: Usn,
     pub reason_mask: u32,
@@ -55,6 +76,45 @@ pub struct EnumOptions {
     pub timeout: u64,
     pub wait_for_more: bool,
     pub buffer_size: usize,
+    pub unknown_version_policy: UnknownVersionPolicy,
+    /// Read with `FSCTL_READ_UNPRIVILEGED_USN_JOURNAL` (Windows 8 and later), which works
+    /// without Administrator rights on a volume opened with
+    /// [`Volume::from_drive_letter_unprivileged`]. Records are filtered by the caller's access
+    /// rights, so changes to files the caller cannot access may be missing or lack a name.
+    pub unprivileged: bool,
+    /// How long a read may hold records back to batch them when `wait_for_more` is set.
+    ///
+    /// Zero (the default) returns each read as soon as a record is available. A larger budget
+    /// waits for more bytes per read, and so fewer system calls, at the cost of latency; the
+    /// budget, rounded up to whole seconds, replaces `timeout`. The iterator adapts the batch
+    /// size to keep the observed latency, reported by [`UsnJournalIter::stats`], within budget.
+    pub latency_budget: Duration,
+    /// Recover when the journal overwrites records before they are read.
+    ///
+    /// A slow consumer tailing a small, busy journal can fall behind `lowest_valid_usn`, which
+    /// otherwise fails every further read with `UsnError::JournalWrapped`. With this set,
+    /// the iterator re-queries the journal, moves forward to the oldest record still present,
+    /// yields a single `UsnError::JournalGap` describing the skipped range, and keeps going.
+    pub recover_from_gaps: bool,
+    /// Skip records timestamped before this time.
+    ///
+    /// The journal is ordered by USN, not by time. Timestamps almost always increase with
+    /// the USN, but clock adjustments can break that, so time filtering is best-effort: every
+    /// record is still read, and only those outside the range are dropped.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339_option"))]
+    pub start_time: Option<SystemTime>,
+    /// Skip records timestamped after this time; see `start_time`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339_option"))]
+    pub end_time: Option<SystemTime>,
+    /// End the iteration at the first record timestamped after `end_time`, instead of reading
+    /// on to the end of the journal. Records after it that are back within the range, e.g.
+    /// because the clock was set back, are then not reported.
+    pub stop_at_end_time: bool,
+    /// Only report entries whose file name ends with one of these extensions, such as `txt`
+    /// or `.log`, ignoring ASCII case. Names are matched before they are decoded, so skipped
+    /// records cost no allocation. Directories are filtered like files. Empty, the default,
+    /// reports every entry.
+    pub extensions: Vec<String>,
 }
 
 impl Default for EnumOptions {
@@ -66,13 +126,44 @@ impl Default for EnumOptions {
             timeout: 0,
             wait_for_more: false,
             buffer_size: DEFAULT_BUFFER_SIZE,
+            unknown_version_policy: UnknownVersionPolicy::default(),
+            unprivileged: false,
+            latency_budget: Duration::ZERO,
+            recover_from_gaps: false,
+            start_time: None,
+            end_time: None,
+            stop_at_end_time: false,
+            extensions: Vec::new(),
+        }
+    }
+}
+
+// Journal write rate assumed when turning a latency budget into a batch size: 64 KiB of
+// records per second, which a busy system volume easily sustains.
+const ASSUMED_JOURNAL_BYTES_PER_SEC: u64 = 64 * 1024;
+
+impl EnumOptions {
+    /// Returns the `Timeout` and `BytesToWaitFor` to read with.
+    pub(crate) fn read_wait(&self) -> (u64, u64) {
+        if !self.wait_for_more {
+            return (self.timeout, 0);
+        }
+        if self.latency_budget.is_zero() {
+            return (self.timeout, 1);
         }
+
+        let budget_bytes =
+            self.latency_budget.as_millis() as u64 * ASSUMED_JOURNAL_BYTES_PER_SEC / 1000;
+        let max_bytes = self.buffer_size.saturating_sub(size_of::<Usn>()) as u64;
+        let timeout = self.latency_budget.as_secs_f64().ceil() as u64;
+        (timeout, budget_bytes.min(max_bytes).max(1))
     }
 }
 
 /// Represents the USN journal state on an NTFS/ReFS volume.
 /// This is a thin wrapper around the USN_JOURNAL_DATA_V0 structure from the Windows API.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UsnJournalData {
     pub journal_id: u64,
     pub first_usn: i64,
@@ -83,6 +174,81 @@ pub struct UsnJournalData {
     pub allocation_delta: u64,
 }
 
+impl UsnJournalData {
+    /// Translates a USN into its approximate byte offset from the oldest record in the journal.
+    ///
+    /// On NTFS a USN is the byte offset of its record within the `$J` stream, so the result is
+    /// `usn - first_usn`. ReFS does not guarantee this layout, so the value is only approximate
+    /// there. Returns `None` if `usn` lies outside `first_usn..=next_usn`.
+    pub fn usn_to_offset(&self, usn: Usn) -> Option<u64> {
+        if !(self.first_usn..=self.next_usn).contains(&usn) {
+            return None;
+        }
+        u64::try_from(usn - self.first_usn).ok()
+    }
+
+    /// Returns the bytes of journal written between `earlier` and this snapshot.
+    ///
+    /// Returns 0 if the journal was recreated in between (the journal IDs differ), since USNs
+    /// from different journal instances cannot be compared.
+    pub fn bytes_written_since(&self, earlier: &UsnJournalData) -> u64 {
+        if self.journal_id != earlier.journal_id {
+            return 0;
+        }
+        u64::try_from(self.next_usn - earlier.next_usn).unwrap_or(0)
+    }
+
+    /// Returns the journal write rate in bytes per second between `earlier` and this snapshot.
+    ///
+    /// `elapsed` is the time between the two samples; a zero duration yields a rate of 0.
+    pub fn write_rate_since(&self, earlier: &UsnJournalData, elapsed: Duration) -> f64 {
+        let seconds = elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.bytes_written_since(earlier) as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the approximate number of bytes the journal currently holds, `next_usn - first_usn`.
+    ///
+    /// Like [`UsnJournalData::usn_to_offset`], this assumes USNs are byte offsets into the
+    /// `$J` stream, which holds on NTFS but is only approximate on ReFS. Records purged from
+    /// the start of the stream are no longer counted once `first_usn` moves past them.
+    pub fn used_bytes(&self) -> u64 {
+        u64::try_from(self.next_usn - self.first_usn).unwrap_or(0)
+    }
+
+    /// Returns [`UsnJournalData::used_bytes`] as a fraction of `maximum_size`.
+    ///
+    /// NTFS lets the journal grow up to `maximum_size + allocation_delta` before it purges the
+    /// oldest records, so the ratio can briefly exceed 1.0. Returns 0.0 if `maximum_size` is 0.
+    pub fn usage_ratio(&self) -> f64 {
+        if self.maximum_size == 0 {
+            return 0.0;
+        }
+        self.used_bytes() as f64 / self.maximum_size as f64
+    }
+
+    /// Returns true if [`UsnJournalData::usage_ratio`] has reached `threshold`, i.e. the journal
+    /// is close to purging its oldest records.
+    ///
+    /// A consumer that has not yet read the records near `first_usn` is then at risk of
+    /// losing them; e.g. `is_near_wrap(0.9)` flags a journal that is 90% full.
+    pub fn is_near_wrap(&self, threshold: f64) -> bool {
+        self.usage_ratio() >= threshold
+    }
+
+    /// Translates a byte offset from the oldest record back into a USN.
+    ///
+    /// This is the inverse of [`UsnJournalData::usn_to_offset`] and carries the same NTFS
+    /// assumption. Returns `None` if the resulting USN would lie past `next_usn`.
+    pub fn offset_to_usn(&self, offset: u64) -> Option<Usn> {
+        let usn = i64::try_from(offset).ok()?.checked_add(self.first_usn)?;
+        (usn <= self.next_usn).then_some(usn)
+    }
+}
+
 impl From<USN_JOURNAL_DATA_V0> for UsnJournalData {
     fn from(data: USN_JOURNAL_DATA_V0) -> Self {
         UsnJournalData {
@@ -97,6 +263,142 @@ impl From<USN_JOURNAL_DATA_V0> for UsnJournalData {
     }
 }
 
+/// A persisted read position: a USN together with the journal instance it belongs to.
+///
+/// A USN is only meaningful for the journal it was read from. Resuming with
+/// [`UsnJournal::iter_from_cursor`] checks the journal ID, so a cursor taken before the journal
+/// was deleted and recreated is rejected instead of silently reading the wrong records. A cursor
+/// taken on another volume is rejected the same way.
+///
+/// [`Cursor::to_token`] turns a cursor into a short string for storing outside the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cursor {
+    /// Serial number of the volume the cursor was taken on, or zero when unknown.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub volume_serial: u32,
+    pub journal_id: u64,
+    pub usn: Usn,
+}
+
+/// Version byte leading every [`Cursor::to_token`] payload.
+const CURSOR_TOKEN_VERSION: u8 = 1;
+
+/// Version byte, volume serial, journal ID, USN and CRC-32.
+const CURSOR_TOKEN_LEN: usize = 1 + 4 + 8 + 8 + 4;
+
+impl Cursor {
+    /// Checks that the cursor belongs to the journal described by `journal_data`.
+    ///
+    /// # Errors
+    /// Returns `UsnError::JournalIdChanged` if the journal IDs differ.
+    pub fn ensure_matches(&self, journal_data: &UsnJournalData) -> UsnResult<()> {
+        if self.journal_id != journal_data.journal_id {
+            return Err(UsnError::JournalIdChanged {
+                expected: self.journal_id,
+                actual: journal_data.journal_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the cursor was taken on the volume with serial number `volume_serial`.
+    ///
+    /// A cursor whose serial number is zero (unknown) matches any volume.
+    ///
+    /// # Errors
+    /// Returns `UsnError::VolumeMismatch` if the serial numbers differ.
+    pub fn ensure_volume(&self, volume_serial: u32) -> UsnResult<()> {
+        if self.volume_serial != 0 && self.volume_serial != volume_serial {
+            return Err(UsnError::VolumeMismatch {
+                expected: self.volume_serial,
+                actual: volume_serial,
+            });
+        }
+        Ok(())
+    }
+
+    /// Encodes the cursor as an opaque, versioned token string.
+    ///
+    /// The token is URL-safe base64 and carries a checksum, so [`Cursor::from_token`] rejects
+    /// tokens that were truncated or edited by hand.
+    pub fn to_token(&self) -> String {
+        let mut payload = Vec::with_capacity(CURSOR_TOKEN_LEN);
+        payload.push(CURSOR_TOKEN_VERSION);
+        payload.extend_from_slice(&self.volume_serial.to_le_bytes());
+        payload.extend_from_slice(&self.journal_id.to_le_bytes());
+        payload.extend_from_slice(&self.usn.to_le_bytes());
+        payload.extend_from_slice(&token::crc32(&payload).to_le_bytes());
+        token::encode_base64(&payload)
+    }
+
+    /// Decodes a token produced by [`Cursor::to_token`].
+    ///
+    /// # Errors
+    /// Returns `UsnError::InvalidCursorToken` if the token is malformed, has an unknown
+    /// version, or fails its checksum.
+    pub fn from_token(token: &str) -> UsnResult<Self> {
+        let invalid = |reason: &str| UsnError::InvalidCursorToken(reason.to_string());
+
+        let payload = token::decode_base64(token.trim()).ok_or_else(|| invalid("not base64"))?;
+        if payload.len() != CURSOR_TOKEN_LEN {
+            return Err(invalid("wrong length"));
+        }
+        if payload[0] != CURSOR_TOKEN_VERSION {
+            return Err(UsnError::InvalidCursorToken(format!(
+                "unsupported version {}",
+                payload[0]
+            )));
+        }
+
+        let (body, checksum) = payload.split_at(CURSOR_TOKEN_LEN - 4);
+        if token::crc32(body).to_le_bytes() != checksum {
+            return Err(invalid("checksum mismatch"));
+        }
+
+        let (volume_serial, rest) = body[1..]
+            .split_first_chunk()
+            .ok_or_else(|| invalid("wrong length"))?;
+        let (journal_id, rest) = rest
+            .split_first_chunk()
+            .ok_or_else(|| invalid("wrong length"))?;
+        let usn = rest.first_chunk().ok_or_else(|| invalid("wrong length"))?;
+        Ok(Cursor {
+            volume_serial: u32::from_le_bytes(*volume_serial),
+            journal_id: u64::from_le_bytes(*journal_id),
+            usn: Usn::from_le_bytes(*usn),
+        })
+    }
+}
+
+/// Read rates measured by [`UsnJournal::measure_read_throughput`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThroughputStats {
+    pub records_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub reads: u64,
+}
+
+/// Read counters kept by a [`UsnJournalIter`], returned by [`UsnJournalIter::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReadStats {
+    pub reads: u64,
+    /// Total bytes returned by journal reads, including each buffer's leading USN.
+    pub bytes: u64,
+    /// Total time spent blocked in journal reads.
+    pub total_latency: Duration,
+}
+
+impl ReadStats {
+    /// Returns the average time a read blocked, or zero if nothing was read yet.
+    pub fn average_latency(&self) -> Duration {
+        if self.reads == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.total_latency.as_nanos() / u128::from(self.reads)) as u64)
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Iterator for enumerating USN journal records on NTFS/ReFS volume.
 ///
@@ -117,19 +419,109 @@ impl<'a> UsnJournal<'a> {
     /// The iterator yields `Result<UsnEntry, UsnError>` items, allowing callers
     /// to handle individual entry errors gracefully without stopping iteration.
     pub fn iter(&self) -> UsnResult<UsnJournalIter> {
-        let journal_data = self.query(true)?;
-        Ok(UsnJournalIter {
+        self.resume_at(0)
+    }
+
+    /// Returns an iterator over the USN journal entries, starting at `usn`.
+    ///
+    /// This is the supported way to pause and resume a long-running tail: save the value of
+    /// [`UsnJournalIter::next_usn`] when tearing an iterator down, then pass it here to build a
+    /// new iterator that continues exactly where the previous one stopped, without gaps or
+    /// duplicates. To resume with custom options, set [`EnumOptions::start_usn`] to the saved
+    /// value and call [`UsnJournal::iter_with_options`] instead.
+    ///
+    /// The saved USN is only meaningful for the journal instance it was read from; if the
+    /// journal has been deleted and recreated in the meantime, start over from the beginning.
+    /// Persist a [`Cursor`] and use [`UsnJournal::iter_from_cursor`] to have that checked.
+    pub fn resume_at(&self, usn: Usn) -> UsnResult<UsnJournalIter> {
+        let journal_data = self.ensure_active_default()?;
+        Ok(self.iter_at(&journal_data, None, usn))
+    }
+
+    /// Returns a cursor pointing at the current end of the journal.
+    pub fn cursor(&self) -> UsnResult<Cursor> {
+        let journal_data = self.query()?;
+        Ok(Cursor {
+            volume_serial: self.volume.serial_number()?,
+            journal_id: journal_data.journal_id,
+            usn: journal_data.next_usn,
+        })
+    }
+
+    /// Returns an iterator resuming from a persisted [`Cursor`].
+    ///
+    /// # Errors
+    /// Returns `UsnError::VolumeMismatch` if the cursor was taken on another volume, and
+    /// `UsnError::JournalIdChanged` if the journal was recreated since the cursor was taken, in
+    /// which case its USN no longer refers to the same records.
+    pub fn iter_from_cursor(&self, cursor: Cursor) -> UsnResult<UsnJournalIter> {
+        // A cursor with an unknown volume matches any, so the serial number is not needed.
+        let volume_serial = match cursor.volume_serial {
+            0 => None,
+            _ => {
+                let volume_serial = self.volume.serial_number()?;
+                cursor.ensure_volume(volume_serial)?;
+                Some(volume_serial)
+            }
+        };
+        let journal_data = self.ensure_active_default()?;
+        cursor.ensure_matches(&journal_data)?;
+        Ok(self.iter_at(&journal_data, volume_serial, cursor.usn))
+    }
+
+    /// Returns an iterator resuming at `start_usn` of the journal identified by `journal_id`.
+    ///
+    /// This is the save/restore pattern for a monitor that must survive restarts: before
+    /// shutting down, save [`UsnJournalData::journal_id`] (or [`UsnJournalIter::cursor`]) and
+    /// [`UsnJournalIter::next_usn`]; on startup, pass both here to continue without gaps or
+    /// duplicates. Equivalent to [`UsnJournal::iter_from_cursor`] with the two values.
+    ///
+    /// # Errors
+    /// Returns `UsnError::JournalIdChanged` if the journal was deleted or recreated since the
+    /// values were saved. The saved USN is then meaningless; start over, e.g. with a full
+    /// [`Mft`] scan.
+    pub fn iter_from(&self, journal_id: u64, start_usn: Usn) -> UsnResult<UsnJournalIter> {
+        self.iter_from_cursor(Cursor {
+            volume_serial: 0,
+            journal_id,
+            usn: start_usn,
+        })
+    }
+
+    fn iter_at(
+        &self,
+        journal_data: &UsnJournalData,
+        volume_serial: Option<u32>,
+        usn: Usn,
+    ) -> UsnJournalIter {
+        UsnJournalIter {
             handle: self.volume.shared_handle(),
+            volume_serial,
             journal_id: journal_data.journal_id,
-            buffer: vec![0u8; DEFAULT_BUFFER_SIZE],
+            buffer: AlignedBuffer::new(DEFAULT_BUFFER_SIZE),
             bytes_read: 0,
             offset: 0,
-            next_start_usn: 0,
+            next_start_usn: usn,
             reason_mask: USN_REASON_MASK_ALL,
             return_only_on_close: 0,
             timeout: 0,
             bytes_to_wait_for: 1,
-        })
+            unknown_version_policy: UnknownVersionPolicy::default(),
+            unprivileged: false,
+            latency_budget: Duration::ZERO,
+            max_bytes_to_wait_for: 1,
+            stats: ReadStats::default(),
+            recover_from_gaps: false,
+            lowest_valid_usn: journal_data.lowest_valid_usn,
+            corrupted: false,
+            start_time: None,
+            end_time: None,
+            stop_at_end_time: false,
+            past_end_time: false,
+            extensions: ExtensionFilter::default(),
+            current_record: 0..0,
+            cancellable: None,
+        }
     }
 
     /// Returns an iterator over the USN journal entries with custom enumerate options.
@@ -137,44 +529,446 @@ impl<'a> UsnJournal<'a> {
     /// The iterator yields `Result<UsnEntry, UsnError>` items, allowing callers
     /// to handle individual entry errors gracefully without stopping iteration.
     pub fn iter_with_options(&self, options: EnumOptions) -> UsnResult<UsnJournalIter> {
-        let journal_data = self.query(true)?;
+        let journal_data = self.ensure_active_default()?;
+        let (timeout, bytes_to_wait_for) = options.read_wait();
         Ok(UsnJournalIter {
             handle: self.volume.shared_handle(),
+            volume_serial: None,
             journal_id: journal_data.journal_id,
-            buffer: vec![0u8; options.buffer_size],
+            buffer: AlignedBuffer::new(options.buffer_size),
             bytes_read: 0,
             offset: 0,
             next_start_usn: options.start_usn,
             reason_mask: options.reason_mask,
             return_only_on_close: options.only_on_close as u32,
-            timeout: options.timeout,
-            bytes_to_wait_for: options.wait_for_more as u64,
+            timeout,
+            bytes_to_wait_for,
+            unknown_version_policy: options.unknown_version_policy,
+            unprivileged: options.unprivileged,
+            latency_budget: options.latency_budget,
+            max_bytes_to_wait_for: bytes_to_wait_for,
+            stats: ReadStats::default(),
+            recover_from_gaps: options.recover_from_gaps,
+            lowest_valid_usn: journal_data.lowest_valid_usn,
+            corrupted: false,
+            start_time: options.start_time,
+            end_time: options.end_time,
+            stop_at_end_time: options.stop_at_end_time,
+            past_end_time: false,
+            extensions: ExtensionFilter::new(&options.extensions),
+            current_record: 0..0,
+            cancellable: None,
         })
     }
 
-    /// Query the USN journal state for a volume, optionally creating it if not active.
+    /// Returns an iterator like [`UsnJournal::iter_with_options`] that can be ended from
+    /// another thread.
     ///
-    /// # Arguments
-    /// * `create_if_not_active` - If true, create the journal if it does not exist.
+    /// With `wait_for_more` set, a read blocks until new records arrive. The returned iterator
+    /// reads through its own overlapped handle to the volume instead, so
+    /// [`Canceller::cancel`] can abort a pending read with `CancelIoEx`; the iterator then
+    /// returns `None`. The handles are closed when the iterator and cancellers are dropped.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use usn_journal_rs::{journal::{EnumOptions, UsnJournal}, volume::Volume};
+    ///
+    /// let volume = Volume::from_drive_letter('C')?;
+    /// let journal = UsnJournal::new(&volume);
+    /// let (iter, canceller) = journal.iter_cancellable(EnumOptions {
+    ///     wait_for_more: true,
+    ///     ..Default::default()
+    /// })?;
+    /// let reader = std::thread::spawn(move || iter.count());
+    /// canceller.cancel();
+    /// reader.join().unwrap();
+    /// # Ok::<(), usn_journal_rs::UsnError>(())
+    /// ```
+    pub fn iter_cancellable(&self, options: EnumOptions) -> UsnResult<(UsnJournalIter, Canceller)> {
+        let unprivileged = options.unprivileged;
+        let mut iter = self.iter_with_options(options)?;
+        let (read, canceller) = CancellableRead::new(**iter.handle, unprivileged)?;
+        iter.cancellable = Some(read);
+        Ok((iter, canceller))
+    }
+
+    /// Returns an async stream of the journal entries selected by `options`.
+    ///
+    /// The blocking reads run on Tokio's blocking thread pool, honouring `wait_for_more` and
+    /// `timeout` like [`UsnJournal::iter_with_options`]. Dropping the stream stops the
+    /// background task; see [`UsnJournalStream`](crate::stream::UsnJournalStream).
+    ///
+    /// # Errors
+    /// Returns the same errors as [`UsnJournal::iter_with_options`], or
+    /// `UsnError::OtherError` if called outside a Tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn stream(&self, options: EnumOptions) -> UsnResult<crate::stream::UsnJournalStream> {
+        crate::stream::UsnJournalStream::spawn(self.iter_with_options(options)?)
+    }
+
+    /// Returns an iterator that first reports every existing file, then tails live changes.
+    ///
+    /// The MFT is enumerated first and each file is yielded as [`JournalEvent::Existing`];
+    /// the journal is then read as [`JournalEvent::Changed`] events. The live tail starts from
+    /// the journal position captured *before* the scan, so no change made during the scan is
+    /// missed. Files changed during the scan may therefore be reported both as existing and as
+    /// changed. `options.start_usn` is ignored.
+    pub fn iter_with_initial_scan(&self, options: EnumOptions) -> UsnResult<InitialScanIter> {
+        let journal_data = self.ensure_active_default()?;
+        let live = self.iter_with_options(EnumOptions {
+            start_usn: journal_data.next_usn,
+            ..options
+        })?;
+
+        Ok(InitialScanIter {
+            scan: Some(Mft::new(self.volume).iter()),
+            live,
+        })
+    }
+
+    /// Returns the bytes of journal written since `prev` was sampled with [`UsnJournal::query`].
+    ///
+    /// To chart activity, keep the latest `UsnJournalData` from `query` and compare successive
+    /// samples with [`UsnJournalData::bytes_written_since`] or
+    /// [`UsnJournalData::write_rate_since`].
+    pub fn activity_sample(&self, prev: &UsnJournalData) -> UsnResult<u64> {
+        let current = self.query()?;
+        Ok(current.bytes_written_since(prev))
+    }
+
+    /// Reads the journal up to its current end and returns every change classified and resolved.
+    ///
+    /// This is the batteries-included counterpart to [`UsnJournal::iter_with_options`]: each
+    /// entry is classified with [`UsnEntry::change_kind`] and its path is resolved with
+    /// `resolver`. Entries whose path cannot be resolved get a `None` path. `wait_for_more` is
+    /// ignored so the call always returns once it has caught up.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while reading the journal.
+    pub fn read_changes(
+        &self,
+        options: EnumOptions,
+        resolver: &mut PathResolver,
+    ) -> UsnResult<Vec<ResolvedChange>> {
+        let mut iter = self.iter_with_options(options)?;
+        iter.bytes_to_wait_for = 0;
+
+        iter.map(|entry| {
+            let entry = entry?;
+            Ok(ResolvedChange {
+                kind: entry.change_kind(),
+                path: resolver.resolve_path(&entry),
+                time: entry.time,
+                fid: entry.fid,
+            })
+        })
+        .collect()
+    }
+
+    /// Blocks until the file or directory at `path` changes, or until `timeout` elapses.
+    ///
+    /// The path is resolved to its file reference number up front and the journal is then
+    /// tailed from its current end for the first record with that ID, which is returned.
+    /// Deleting or renaming the file counts as a change, so the delete record is returned if
+    /// the file is removed. Returns `Ok(None)` if nothing changed before the timeout.
+    ///
+    /// `path` must be on this journal's volume. A file replaced by a new one under the same
+    /// name (as some editors do on save) is reported through the old file's delete or rename.
+    ///
+    /// # Errors
+    /// Returns an error if `path` cannot be opened or the journal cannot be read.
+    pub fn wait_for_path(&self, path: &Path, timeout: Duration) -> UsnResult<Option<UsnEntry>> {
+        let fid = path::file_id_from_path(path)?;
+        let (mut iter, _canceller) = self.iter_cancellable(EnumOptions {
+            start_usn: self.ensure_active_default()?.next_usn,
+            wait_for_more: true,
+            ..Default::default()
+        })?;
+        // The read waiting for more records is aborted at the deadline, which ends the iterator.
+        if let (Some(read), Some(deadline)) =
+            (&mut iter.cancellable, Instant::now().checked_add(timeout))
+        {
+            read.set_deadline(deadline);
+        }
+
+        for entry in iter {
+            let entry = entry?;
+            if entry.fid == fid {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tails the journal from its current end, reporting changes to the immediate children
+    /// of the directory `dir_fid`.
+    ///
+    /// Changes inside subdirectories are skipped; renaming or deleting the directory itself is
+    /// reported as well, and deleting it ends the watch. See [`WatchDirectoryShallow`] for how
+    /// events are selected. The returned iterator blocks until more records are written.
+    ///
+    /// # Errors
+    /// Returns an error if the journal cannot be queried.
+    pub fn watch_directory_shallow(
+        &self,
+        dir_fid: FileId,
+    ) -> UsnResult<WatchDirectoryShallow<UsnJournalIter>> {
+        let iter = self.iter_with_options(EnumOptions {
+            start_usn: self.ensure_active_default()?.next_usn,
+            wait_for_more: true,
+            ..Default::default()
+        })?;
+        Ok(iter.watch_directory_shallow(dir_fid))
+    }
+
+    /// Returns the latest journal record of the file or directory at `path`.
+    ///
+    /// Issues `FSCTL_READ_FILE_USN_DATA` on the file itself, so no part of the journal is
+    /// scanned. The returned entry carries the USN, reasons and time of the file's most recent
+    /// change, as well as its current name, parent and attributes.
+    ///
+    /// # Errors
+    /// Returns `UsnError::NoJournalRecord` if the file has not changed since the journal was
+    /// created, `UsnError::JournalNotActive` if the volume has no journal, and
+    /// `UsnError::VolumeMismatch` if `path` is on another volume than this journal.
+    pub fn read_file_record(&self, path: &Path) -> UsnResult<UsnEntry> {
+        let file = path::open_for_query(path)?;
+        self.check_file_volume(HANDLE(file.as_raw_handle()))?;
+        let entry = read_file_usn_data(HANDLE(file.as_raw_handle()))?;
+        if entry.usn == 0 {
+            return Err(UsnError::NoJournalRecord(entry.fid));
+        }
+        Ok(entry)
+    }
+
+    /// Fails with `UsnError::VolumeMismatch` unless the open `file` is on this journal's volume.
+    ///
+    /// The file control codes act on the journal of whatever volume the file is on, so a file
+    /// from elsewhere would silently read or write another volume's journal.
+    fn check_file_volume(&self, file: HANDLE) -> UsnResult<()> {
+        let expected = self.volume.serial_number()?;
+        let actual = volume::serial_number_of(file)?;
+        if actual != expected {
+            return Err(UsnError::VolumeMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Writes a close record for the open file `file` and returns its USN.
+    ///
+    /// Changes to a file are only marked final by the `CLOSE` record written when its last
+    /// handle closes. This generates that record on demand, accumulating the reasons of the
+    /// changes made so far, so a consumer gets a deterministic final entry without waiting
+    /// for every handle to the file to be closed.
+    ///
+    /// `file` must be open with write access, e.g. with [`std::fs::OpenOptions::write`]. If
+    /// other processes hold the file, open it with a share mode that lets them keep writing,
+    /// or the open fails with a sharing violation before this is reached. No privilege beyond
+    /// the write access is needed; the journal must be active on the file's volume.
+    ///
+    /// # Errors
+    /// Returns `UsnError::JournalNotActive` if the volume has no journal,
+    /// `UsnError::VolumeMismatch` if `file` is on another volume than this journal, or the
+    /// underlying Windows error, e.g. access denied for a handle without write access.
+    pub fn write_close_record(&self, file: impl AsHandle) -> UsnResult<Usn> {
+        let file = HANDLE(file.as_handle().as_raw_handle());
+        self.check_file_volume(file)?;
+        let mut usn: Usn = 0;
+        let mut bytes_returned = 0u32;
+        unsafe {
+            DeviceIoControl(
+                file,
+                FSCTL_WRITE_USN_CLOSE_RECORD,
+                None,
+                0,
+                Some(&mut usn as *mut Usn as _),
+                size_of::<Usn>() as u32,
+                Some(&mut bytes_returned),
+                None,
+            )
+        }
+        .map_err(journal_error)?;
+        Ok(usn)
+    }
+
+    /// Recommends a journal `MaximumSize` that retains `desired_retention` worth of records.
+    ///
+    /// `observed_write_rate_bytes_per_sec` can be measured with
+    /// [`UsnJournalData::write_rate_since`]. The result is rounded up to a whole MiB and never
+    /// drops below [`DEFAULT_JOURNAL_MAX_SIZE`].
+    pub fn recommend_max_size(
+        &self,
+        observed_write_rate_bytes_per_sec: u64,
+        desired_retention: Duration,
+    ) -> u64 {
+        const MIB: u128 = 1024 * 1024;
+
+        let needed = u128::from(observed_write_rate_bytes_per_sec)
+            .saturating_mul(desired_retention.as_millis());
+        let needed = needed.div_ceil(1000).div_ceil(MIB) * MIB;
+        u64::try_from(needed)
+            .unwrap_or(u64::MAX)
+            .max(DEFAULT_JOURNAL_MAX_SIZE)
+    }
+
+    /// Returns true if the journal's current `MaximumSize` is below
+    /// [`UsnJournal::recommend_max_size`] for the given workload, meaning records would be
+    /// purged before a consumer reading `desired_retention` behind could see them.
+    pub fn needs_resize(
+        &self,
+        observed_write_rate_bytes_per_sec: u64,
+        desired_retention: Duration,
+    ) -> UsnResult<bool> {
+        let journal_data = self.query()?;
+        Ok(journal_data.maximum_size
+            < self.recommend_max_size(observed_write_rate_bytes_per_sec, desired_retention))
+    }
+
+    /// Returns the timestamps of the oldest and newest records currently in the journal.
+    ///
+    /// This answers "how far back does this journal go?" in wall-clock terms. The oldest
+    /// timestamp comes from the record at `first_usn`; the newest from the last record written
+    /// before this call. Records appended while the method runs are not considered.
+    ///
+    /// # Errors
+    /// Returns `UsnError::OtherError` if the journal contains no records.
+    pub fn time_span(&self) -> UsnResult<(SystemTime, SystemTime)> {
+        let journal_data = self.query()?;
+        let empty_journal_error = || UsnError::OtherError("USN journal is empty".to_string());
+
+        let oldest = self
+            .iter_with_options(EnumOptions {
+                start_usn: journal_data.first_usn,
+                ..Default::default()
+            })?
+            .next()
+            .ok_or_else(empty_journal_error)??;
+        if oldest.usn >= journal_data.next_usn {
+            return Err(empty_journal_error());
+        }
+
+        // USNs are byte offsets into the journal stream, so the newest record sits within the
+        // last few kilobytes before `next_usn`. Widen the window until it contains a record.
+        let mut window = DEFAULT_BUFFER_SIZE as i64;
+        loop {
+            let start_usn = journal_data
+                .next_usn
+                .saturating_sub(window)
+                .max(journal_data.first_usn);
+            let mut newest = None;
+            for entry in self.iter_with_options(EnumOptions {
+                start_usn,
+                ..Default::default()
+            })? {
+                let entry = entry?;
+                if entry.usn >= journal_data.next_usn {
+                    break;
+                }
+                newest = Some(entry.time);
+            }
+
+            if let Some(newest) = newest {
+                return Ok((oldest.time, newest));
+            }
+            if start_usn == journal_data.first_usn {
+                return Ok((oldest.time, oldest.time));
+            }
+            window = window.saturating_mul(2);
+        }
+    }
+
+    /// Counts how many records in `from..to` carry each reason flag.
+    ///
+    /// Keys are flag names as in [`UsnReason`]'s `Display` output, e.g. `FILE_CREATE`; a
+    /// record with several reasons counts towards each of them. Gives a quick profile of
+    /// volume activity over a window of the journal.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while reading the journal.
+    pub fn reason_histogram(&self, from: Usn, to: Usn) -> UsnResult<HashMap<&'static str, u64>> {
+        let iter = self.iter_with_options(EnumOptions {
+            start_usn: from,
+            ..Default::default()
+        })?;
+        tally_reasons(iter, to)
+    }
+
+    /// Counts the distinct files and directories changed by records in `from..to`.
+    ///
+    /// Files are told apart by MFT record number, ignoring the sequence number, so a file
+    /// that is deleted and whose record is reused within the window counts once. Only the
+    /// IDs are kept, which makes this far cheaper than resolving paths for a "N files
+    /// changed" summary.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while reading the journal.
+    pub fn distinct_files_changed(&self, from: Usn, to: Usn) -> UsnResult<usize> {
+        let iter = self.iter_with_options(EnumOptions {
+            start_usn: from,
+            ..Default::default()
+        })?;
+        count_distinct_files(iter, to)
+    }
+
+    /// Reads the journal with the given options for up to `duration` and reports the read rates.
+    ///
+    /// This is a diagnostic helper for tuning [`EnumOptions::buffer_size`]. Reading stops early
+    /// once it catches up with the end of the journal, and the rates are computed over the time
+    /// actually spent reading. `wait_for_more` is ignored so the measurement never blocks
+    /// waiting for new records.
+    pub fn measure_read_throughput(
+        &self,
+        options: EnumOptions,
+        duration: Duration,
+    ) -> UsnResult<ThroughputStats> {
+        let mut iter = self.iter_with_options(options)?;
+        iter.bytes_to_wait_for = 0;
+
+        let mut records = 0u64;
+        let started = Instant::now();
+        // The iterator ends once it has caught up with the end of the journal.
+        while started.elapsed() < duration {
+            match iter.next() {
+                Some(entry) => {
+                    entry?;
+                    records += 1;
+                }
+                None => break,
+            }
+        }
+
+        let stats = iter.stats();
+        let elapsed = started.elapsed().as_secs_f64();
+        let per_sec = |count: u64| {
+            if elapsed > 0.0 {
+                count as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        Ok(ThroughputStats {
+            records_per_sec: per_sec(records),
+            bytes_per_sec: per_sec(stats.bytes),
+            reads: stats.reads,
+        })
+    }
+
+    /// Query the USN journal state for a volume.
+    ///
+    /// Never creates or modifies the journal: if it is not active, this fails with
+    /// `UsnError::JournalNotActive`. Use [`UsnJournal::ensure_active`] to create it.
     ///
     /// # Returns
     /// * `Ok(UsnJournalData)` - The current journal state.
-    /// * `Err(UsnError)` - If the query or creation fails.
-    pub fn query(&self, create_if_not_active: bool) -> UsnResult<UsnJournalData> {
+    /// * `Err(UsnError::JournalNotActive)` - If the volume has no active journal.
+    /// * `Err(UsnError::JournalDeleted)` - If the journal is being deleted.
+    /// * `Err(UsnError::UnsupportedFilesystem)` - If the volume is not NTFS or ReFS.
+    /// * `Err(UsnError)` - If the query fails otherwise.
+    pub fn query(&self) -> UsnResult<UsnJournalData> {
         match self.query_core() {
             Err(err) => {
-                if err.code() == ERROR_JOURNAL_NOT_ACTIVE.into() && create_if_not_active {
-                    self.create_or_update(
-                        DEFAULT_JOURNAL_MAX_SIZE,
-                        DEFAULT_JOURNAL_ALLOCATION_DELTA,
-                    )?;
-
-                    let journal_data = self.query_core()?;
-                    Ok(journal_data.into())
-                } else {
-                    warn!("Error querying USN journal: {err}");
-                    Err(err.into())
-                }
+                warn!("Error querying USN journal: {err}");
+                Err(journal_error(err))
             }
             Ok(journal_data) => {
                 debug!("USN journal data: {journal_data:#?}");
@@ -183,43 +977,60 @@ impl<'a> UsnJournal<'a> {
         }
     }
 
-    /// Core function to query the USN journal state.
-    fn query_core(&self) -> Result<USN_JOURNAL_DATA_V0, windows::core::Error> {
-        let mut journal_data = USN_JOURNAL_DATA_V0::default();
-        let mut bytes_return = 0u32;
+    /// Query the USN journal state, returning `Ok(None)` if the volume has no active journal.
+    ///
+    /// Like [`UsnJournal::query`], this never creates the journal, but an inactive journal is
+    /// not treated as an error, so callers can check for one without matching on error
+    /// variants.
+    ///
+    /// # Returns
+    /// * `Ok(Some(UsnJournalData))` - The current journal state.
+    /// * `Ok(None)` - If the volume has no active journal.
+    /// * `Err(UsnError::JournalDeleted)` - If the journal is being deleted.
+    /// * `Err(UsnError)` - If the query fails otherwise.
+    pub fn try_query(&self) -> UsnResult<Option<UsnJournalData>> {
+        optional_journal_data(self.query_core())
+    }
 
-        unsafe {
-            // https://learn.microsoft.com/en-us/windows/win32/fileio/using-the-change-journal-identifier
-            // To obtain the identifier of the current change journal on a specified volume,
-            // use the FSCTL_QUERY_USN_JOURNAL control code.
-            //
-            // To perform this and all other change journal operations,
-            // you must have system administrator privileges.
-            // That is, you must be a member of the Administrators group.
-            DeviceIoControl(
-                self.volume.handle(),
-                FSCTL_QUERY_USN_JOURNAL,
-                None,
-                0,
-                Some((&mut journal_data as *mut USN_JOURNAL_DATA_V0).cast()),
-                size_of::<USN_JOURNAL_DATA_V0>() as u32,
-                Some(&mut bytes_return),
-                None,
-            )
-        }?;
+    /// Returns the journal state, creating the journal first if it is not active.
+    ///
+    /// `max_size` and `allocation_delta` are only used when the journal has to be created; an
+    /// active journal is left untouched. Use [`UsnJournal::create_or_update`] to change the
+    /// settings of an existing journal.
+    ///
+    /// # Returns
+    /// * `Ok(UsnJournalData)` - The state of the active journal.
+    /// * `Err(UsnError)` - If the query or creation fails.
+    pub fn ensure_active(&self, max_size: u64, allocation_delta: u64) -> UsnResult<UsnJournalData> {
+        ensure_active_with(
+            || self.query_core(),
+            || self.create_or_update(max_size, allocation_delta),
+        )
+    }
+
+    /// [`UsnJournal::ensure_active`] with the default journal size, used before reading.
+    fn ensure_active_default(&self) -> UsnResult<UsnJournalData> {
+        self.ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)
+    }
 
-        Ok(journal_data)
+    /// Core function to query the USN journal state.
+    fn query_core(&self) -> Result<USN_JOURNAL_DATA_V0, windows::core::Error> {
+        query_journal_data(self.volume.handle())
     }
 
     /// Create or update the USN journal on a volume.
     ///
+    /// Both sizes are rounded up to a multiple of 4 KiB before they are passed on.
+    ///
     /// # Arguments
     /// * `max_size` - Maximum size of the journal in bytes.
     /// * `allocation_delta` - Allocation delta in bytes.
     ///
     /// # Returns
     /// * `Ok(())` on success, or `Err(UsnError)` on failure.
+    /// * `Err(UsnError::OtherError)` - If `allocation_delta` is zero or larger than `max_size`.
     pub fn create_or_update(&self, max_size: u64, allocation_delta: u64) -> UsnResult<()> {
+        let (max_size, allocation_delta) = validate_journal_sizes(max_size, allocation_delta)?;
         let create_data = CREATE_USN_JOURNAL_DATA {
             MaximumSize: max_size,
             AllocationDelta: allocation_delta,
@@ -250,7 +1061,7 @@ impl<'a> UsnJournal<'a> {
     /// # Returns
     /// * `Ok(())` on success, or `Err(UsnError)` on failure.
     pub fn delete(&self) -> UsnResult<()> {
-        let journal_data = self.query(false)?;
+        let journal_data = self.query()?;
         let delete_flags: USN_DELETE_FLAGS = USN_DELETE_FLAG_DELETE | USN_DELETE_FLAG_NOTIFY;
         let delete_data = DELETE_USN_JOURNAL_DATA {
             UsnJournalID: journal_data.journal_id,
@@ -276,93 +1087,610 @@ impl<'a> UsnJournal<'a> {
     }
 }
 
-/// Iterate over USN journal entries.
-///
-/// This iterator yields `Result<UsnEntry, UsnError>` items.
-pub struct UsnJournalIter {
-    handle: Rc<Owned<HANDLE>>,
-    journal_id: u64,
-    buffer: Vec<u8>,
-    bytes_read: u32,
-    offset: u32,
-    next_start_usn: Usn,
-    reason_mask: u32,
-    return_only_on_close: u32,
-    timeout: u64,
-    bytes_to_wait_for: u64,
+// Granularity `create_or_update` rounds journal sizes to: the page size, which is also a
+// multiple of every NTFS cluster size up to 4 KiB.
+const JOURNAL_SIZE_ALIGNMENT: u64 = 4 * 1024;
+
+/// Checks the sizes passed to `FSCTL_CREATE_USN_JOURNAL`, which otherwise fails with a bare
+/// `ERROR_INVALID_PARAMETER`, and rounds them up to [`JOURNAL_SIZE_ALIGNMENT`].
+fn validate_journal_sizes(max_size: u64, allocation_delta: u64) -> UsnResult<(u64, u64)> {
+    if allocation_delta == 0 {
+        return Err(UsnError::OtherError(
+            "USN journal allocation delta must not be zero".to_string(),
+        ));
+    }
+    if max_size < allocation_delta {
+        return Err(UsnError::OtherError(format!(
+            "USN journal maximum size ({max_size} bytes) is smaller than its allocation delta ({allocation_delta} bytes)"
+        )));
+    }
+
+    let align = |size: u64| {
+        size.checked_next_multiple_of(JOURNAL_SIZE_ALIGNMENT)
+            .ok_or_else(|| {
+                UsnError::OtherError(format!("USN journal size {size} bytes is too large"))
+            })
+    };
+    Ok((align(max_size)?, align(allocation_delta)?))
 }
 
-impl UsnJournalIter {
-    /// Read the next chunk of USN journal data into the buffer.
-    ///
-    /// Returns `Ok(true)` if data was read, `Ok(false)` if EOF, or an error.
+/// Converts a journal query result, mapping an inactive journal to `None`.
+fn optional_journal_data(
+    result: windows::core::Result<USN_JOURNAL_DATA_V0>,
+) -> UsnResult<Option<UsnJournalData>> {
+    match result {
+        Ok(journal_data) => Ok(Some(journal_data.into())),
+        Err(err) if err.code() == ERROR_JOURNAL_NOT_ACTIVE.into() => Ok(None),
+        Err(err) => Err(journal_error(err)),
+    }
+}
+
+/// Runs `query`, and if the journal is not active, `create` followed by another `query`.
+fn ensure_active_with(
+    mut query: impl FnMut() -> windows::core::Result<USN_JOURNAL_DATA_V0>,
+    create: impl FnOnce() -> UsnResult<()>,
+) -> UsnResult<UsnJournalData> {
+    match query() {
+        Err(err) if err.code() == ERROR_JOURNAL_NOT_ACTIVE.into() => {
+            debug!("USN journal is not active, creating it");
+            create()?;
+            Ok(query().map_err(journal_error)?.into())
+        }
+        result => Ok(result.map_err(journal_error)?.into()),
+    }
+}
+
+/// Converts a Windows error from a journal operation, mapping journal states to typed errors.
+fn journal_error(err: windows::core::Error) -> UsnError {
+    if err.code() == ERROR_JOURNAL_NOT_ACTIVE.into() {
+        UsnError::JournalNotActive
+    } else if err.code() == ERROR_JOURNAL_DELETE_IN_PROGRESS.into() {
+        UsnError::JournalDeleted
+    } else {
+        errors::fsctl_error(err)
+    }
+}
+
+/// Queries the state of the USN journal on the volume behind `volume_handle`.
+fn query_journal_data(volume_handle: HANDLE) -> Result<USN_JOURNAL_DATA_V0, windows::core::Error> {
+    let mut journal_data = USN_JOURNAL_DATA_V0::default();
+    let mut bytes_return = 0u32;
+
+    unsafe {
+        // https://learn.microsoft.com/en-us/windows/win32/fileio/using-the-change-journal-identifier
+        // To obtain the identifier of the current change journal on a specified volume,
+        // use the FSCTL_QUERY_USN_JOURNAL control code.
+        //
+        // To perform this and all other change journal operations,
+        // you must have system administrator privileges.
+        // That is, you must be a member of the Administrators group.
+        DeviceIoControl(
+            volume_handle,
+            FSCTL_QUERY_USN_JOURNAL,
+            None,
+            0,
+            Some((&mut journal_data as *mut USN_JOURNAL_DATA_V0).cast()),
+            size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            Some(&mut bytes_return),
+            None,
+        )
+    }?;
+
+    Ok(journal_data)
+}
+
+/// An event yielded by [`InitialScanIter`].
+#[derive(Debug, Clone)]
+pub enum JournalEvent {
+    /// A file that already existed when the scan started, read from the MFT.
+    Existing(MftEntry),
+    /// A change recorded in the journal after the scan started.
+    Changed(UsnEntry),
+}
+
+/// Iterator that reports existing files from the MFT before tailing the journal.
+///
+/// Created by [`UsnJournal::iter_with_initial_scan`].
+pub struct InitialScanIter {
+    scan: Option<MftIter>,
+    live: UsnJournalIter,
+}
+
+impl Iterator for InitialScanIter {
+    type Item = UsnResult<JournalEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(scan) = &mut self.scan {
+            match scan.next() {
+                Some(result) => return Some(result.map(JournalEvent::Existing)),
+                None => self.scan = None,
+            }
+        }
+
+        self.live
+            .next()
+            .map(|result| result.map(JournalEvent::Changed))
+    }
+}
+
+/// Iterate over USN journal entries.
+///
+/// This iterator yields `Result<UsnEntry, UsnError>` items. When a read fails the journal is
+/// queried, and `UsnError::JournalIdChanged` or `UsnError::JournalWrapped` is yielded if it was
+/// recreated or overwrote records that had not been read yet, so callers can re-sync.
+pub struct UsnJournalIter {
+    handle: Arc<VolumeHandle>,
+    // Reported in cursors; read whenever a cursor is built unless already known from the
+    // cursor the iterator was resumed from.
+    volume_serial: Option<u32>,
+    journal_id: u64,
+    buffer: AlignedBuffer,
+    bytes_read: u32,
+    offset: u32,
+    next_start_usn: Usn,
+    reason_mask: u32,
+    return_only_on_close: u32,
+    timeout: u64,
+    bytes_to_wait_for: u64,
+    unknown_version_policy: UnknownVersionPolicy,
+    unprivileged: bool,
+    latency_budget: Duration,
+    max_bytes_to_wait_for: u64,
+    stats: ReadStats,
+    recover_from_gaps: bool,
+    // Lowest valid USN seen by the last journal check, to report in `JournalWrapped`.
+    lowest_valid_usn: Usn,
+    // Set once a malformed record was found; iteration cannot continue past it.
+    corrupted: bool,
+    start_time: Option<SystemTime>,
+    end_time: Option<SystemTime>,
+    stop_at_end_time: bool,
+    // Set once a record past `end_time` was read with `stop_at_end_time`.
+    past_end_time: bool,
+    extensions: ExtensionFilter,
+    current_record: Range<usize>,
+    // Set for iterators created by `UsnJournal::iter_cancellable`.
+    cancellable: Option<CancellableRead>,
+}
+
+impl UsnJournalIter {
+    /// Pairs each entry with its resolved path, reporting failed resolutions through a callback.
+    ///
+    /// Entries that cannot be resolved are still yielded with a `None` path; `on_unresolved`
+    /// receives the entry and the resolution error, so tools can log or count failures
+    /// without handling them in the main loop.
+    pub fn resolve_paths<'r, 'v, F, S>(
+        self,
+        resolver: &'r mut PathResolver<'v, S>,
+        on_unresolved: F,
+    ) -> ResolvePaths<'r, 'v, Self, F, S>
+    where
+        F: FnMut(&UsnEntry, UsnError),
+        S: BuildHasher,
+    {
+        ResolvePaths::new(self, resolver, on_unresolved)
+    }
+
+    /// Pairs each entry with its resolved path, or `None` if it cannot be resolved.
+    ///
+    /// Shorthand for [`UsnJournalIter::resolve_paths`] when resolution failures need no
+    /// handling. The iterator itself yields entries without paths.
+    pub fn with_paths<'r, 'v, S: BuildHasher>(
+        self,
+        resolver: &'r mut PathResolver<'v, S>,
+    ) -> WithPaths<'r, 'v, Self, UsnEntry, S> {
+        WithPaths::without_callback(self, resolver)
+    }
+
+    /// Pairs each entry with the entry previously yielded for the same file, if any.
+    ///
+    /// At most `capacity` files are remembered; see [`WithPrevious`] for how memory is bounded.
+    pub fn with_previous(self, capacity: NonZeroUsize) -> WithPrevious<Self> {
+        WithPrevious::new(self, capacity)
+    }
+
+    /// Groups entries into batches of up to `size` entries per item.
+    ///
+    /// Useful for high-volume consumers that process entries in bulk; see [`Chunks`] for how
+    /// errors are surfaced.
+    pub fn chunks(self, size: NonZeroUsize) -> Chunks<Self> {
+        Chunks::new(self, size)
+    }
+
+    /// Yields only `FILE_CREATE` records for files and directories created below `root_fid`.
+    ///
+    /// Directories created inside the subtree extend the watched scope; see
+    /// [`CreationsUnder`] for how membership is decided. Returns an error if the path of
+    /// `root_fid` cannot be resolved.
+    pub fn creations_under<'r, 'v, S: BuildHasher>(
+        self,
+        root_fid: FileId,
+        resolver: &'r mut PathResolver<'v, S>,
+    ) -> UsnResult<CreationsUnder<'r, 'v, Self, S>> {
+        CreationsUnder::new(self, resolver, root_fid)
+    }
+
+    /// Drops entries inside the directory subtrees rooted at `roots`.
+    ///
+    /// Useful for skipping noisy locations such as `C:\Windows\Temp`; see
+    /// [`IgnoreSubtrees`] for how membership is decided. Returns an error if the path of one
+    /// of the roots cannot be resolved.
+    pub fn ignore_subtrees<'r, 'v, S: BuildHasher>(
+        self,
+        roots: &[FileId],
+        resolver: &'r mut PathResolver<'v, S>,
+    ) -> UsnResult<IgnoreSubtrees<'r, 'v, Self, S>> {
+        IgnoreSubtrees::new(self, resolver, roots)
+    }
+
+    /// Coalesces editor-style atomic saves (write a temporary copy, rename it over the
+    /// original) into single [`SaveEvent::AtomicSave`](crate::adapters::SaveEvent) events.
+    ///
+    /// `options` controls which names count as temporary or backup copies; see
+    /// [`AtomicSaves`] for the recognised sequence.
+    pub fn atomic_saves(self, options: AtomicSaveOptions) -> AtomicSaves<Self> {
+        AtomicSaves::new(self, options)
+    }
+
+    /// Pairs the `RENAME_OLD_NAME` and `RENAME_NEW_NAME` records of each rename into a single
+    /// [`UsnEvent::Renamed`](crate::adapters::UsnEvent) event.
+    ///
+    /// See [`CoalesceRenames`] for how unpaired records are reported.
+    pub fn coalesce_renames(self) -> CoalesceRenames<Self> {
+        CoalesceRenames::new(self)
+    }
+
+    /// Groups runs of transacted (TxF) changes into single
+    /// [`TransactionEvent::Transaction`](crate::adapters::TransactionEvent) events.
+    ///
+    /// Consecutive `TRANSACTED_CHANGE` records at most `max_gap` apart are assumed to belong
+    /// to the same transaction; see [`Transactions`] for the limits of this heuristic.
+    pub fn transactions(self, max_gap: Duration) -> Transactions<Self> {
+        Transactions::new(self, max_gap)
+    }
+
+    /// Drops directory records whose only reasons are metadata or indexing changes.
+    ///
+    /// Directories collect frequent `BASIC_INFO_CHANGE` and `INDEXABLE_CHANGE` records as
+    /// their contents change; consumers interested in content changes rarely need them.
+    /// Directory creations, deletions and renames are kept.
+    pub fn exclude_directory_metadata(self) -> ExcludeDirectoryMetadata<Self> {
+        ExcludeDirectoryMetadata::new(self)
+    }
+
+    /// Verifies that entries arrive in strictly increasing USN order.
+    ///
+    /// An out-of-order entry is reported as `UsnError::NonMonotonicUsn` instead of being
+    /// yielded; see [`Checked`] for details.
+    pub fn checked(self) -> Checked<Self> {
+        Checked::new(self)
+    }
+
+    /// Yields only changes to the immediate children of the directory `dir_fid`, plus renames
+    /// and the deletion of the directory itself.
+    ///
+    /// See [`WatchDirectoryShallow`] for how events are selected.
+    pub fn watch_directory_shallow(self, dir_fid: FileId) -> WatchDirectoryShallow<Self> {
+        WatchDirectoryShallow::new(self, dir_fid)
+    }
+
+    /// Converts each entry into a [`FileRecord`](crate::adapters::FileRecord), the type shared
+    /// with MFT enumeration, so one pipeline can consume both sources.
+    pub fn file_records(self) -> FileRecords<Self> {
+        FileRecords::new(self)
+    }
+
+    /// Yields at most `max_per_window` entries per file within each `window`, dropping the
+    /// rest.
+    ///
+    /// Keeps a single rapidly changing file from dominating a watcher. Windows are measured
+    /// on the entries' `time`; see [`RateLimitPerFile`] for details.
+    pub fn rate_limit_per_file(
+        self,
+        max_per_window: usize,
+        window: Duration,
+    ) -> RateLimitPerFile<Self> {
+        RateLimitPerFile::new(self, max_per_window, window)
+    }
+
+    /// Returns the raw bytes of the record behind the most recently yielded entry.
+    ///
+    /// The slice covers the whole `USN_RECORD`, including fields and versions this crate does
+    /// not parse, so callers can decode them themselves. It borrows the iterator's read
+    /// buffer and is therefore only valid until the next call to `next()`, which may refill
+    /// that buffer. The slice is empty before the first entry and after an error or the end
+    /// of iteration.
+    pub fn current_record_bytes(&self) -> &[u8] {
+        &self.buffer[self.current_record.clone()]
+    }
+
+    /// Returns the USN to resume from to continue iterating after the last yielded entry.
+    ///
+    /// If entries from the most recent read are still buffered, this is the USN of the next
+    /// buffered entry; otherwise it is the USN the next read will start at. Pass the value to
+    /// [`UsnJournal::resume_at`] to continue later without gaps or duplicates.
+    pub fn next_usn(&self) -> Usn {
+        if self.offset < self.bytes_read
+            && let Ok((header, _)) = usn_record::parse_usn_record_header(
+                &self.buffer,
+                self.offset,
+                self.bytes_read,
+                "USN record",
+            )
+        {
+            return header.usn;
+        }
+
+        self.next_start_usn
+    }
+
+    /// Returns a cursor to persist for resuming after the last yielded entry.
+    ///
+    /// Pass it to [`UsnJournal::iter_from_cursor`] to continue later; see
+    /// [`UsnJournalIter::next_usn`].
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            // Zero marks the volume as unknown if its serial number cannot be read.
+            volume_serial: self
+                .volume_serial
+                .or_else(|| volume::serial_number_of(**self.handle).ok())
+                .unwrap_or_default(),
+            journal_id: self.journal_id,
+            usn: self.next_usn(),
+        }
+    }
+
+    /// Returns the read counters collected so far.
+    pub fn stats(&self) -> ReadStats {
+        self.stats
+    }
+
+    /// Counts a read and, with a latency budget, adapts the batch size to stay within it.
+    fn record_read_latency(&mut self, latency: Duration) {
+        self.stats.reads += 1;
+        self.stats.total_latency += latency;
+
+        if self.latency_budget.is_zero() || self.bytes_to_wait_for == 0 {
+            return;
+        }
+        if latency > self.latency_budget {
+            // The volume is quieter than assumed; ask for less so reads return sooner.
+            self.bytes_to_wait_for = (self.bytes_to_wait_for / 2).max(1);
+        } else if latency < self.latency_budget / 2 {
+            self.bytes_to_wait_for = (self.bytes_to_wait_for * 2).min(self.max_bytes_to_wait_for);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellable
+            .as_ref()
+            .is_some_and(CancellableRead::is_cancelled)
+    }
+
+    /// Read the next chunk of USN journal data into the buffer.
+    ///
+    /// Returns `Ok(true)` if data was read, `Ok(false)` if EOF, or an error.
     fn get_data(&mut self) -> windows::core::Result<bool> {
-        let read_data = READ_USN_JOURNAL_DATA_V0 {
+        let read_data = READ_USN_JOURNAL_DATA_V1 {
             StartUsn: self.next_start_usn,
             ReasonMask: self.reason_mask,
             ReturnOnlyOnClose: self.return_only_on_close,
             Timeout: self.timeout,
             BytesToWaitFor: self.bytes_to_wait_for,
             UsnJournalID: self.journal_id,
+            MinMajorVersion: usn_record::MIN_MAJOR_VERSION,
+            MaxMajorVersion: usn_record::MAX_MAJOR_VERSION,
         };
 
-        if let Err(err) = unsafe {
-            DeviceIoControl(
-                **self.handle,
-                FSCTL_READ_USN_JOURNAL,
-                Some(&read_data as *const _ as *mut _),
-                size_of::<READ_USN_JOURNAL_DATA_V0>() as u32,
-                Some(self.buffer.as_mut_ptr() as *mut c_void),
-                self.buffer.len() as u32,
-                Some(&mut self.bytes_read),
-                None,
-            )
-        } {
+        let control_code = if self.unprivileged {
+            FSCTL_READ_UNPRIVILEGED_USN_JOURNAL
+        } else {
+            FSCTL_READ_USN_JOURNAL
+        };
+
+        let started = Instant::now();
+        let result = match &self.cancellable {
+            Some(read) => read.device_io_control(
+                control_code,
+                &read_data,
+                &mut self.buffer,
+                &mut self.bytes_read,
+            ),
+            None => unsafe {
+                DeviceIoControl(
+                    **self.handle,
+                    control_code,
+                    Some(&read_data as *const _ as *mut _),
+                    size_of::<READ_USN_JOURNAL_DATA_V1>() as u32,
+                    Some(self.buffer.as_mut_ptr() as *mut c_void),
+                    self.buffer.len() as u32,
+                    Some(&mut self.bytes_read),
+                    None,
+                )
+            },
+        };
+        self.record_read_latency(started.elapsed());
+
+        if let Err(err) = result {
             if err.code() == ERROR_HANDLE_EOF.into() {
                 return Ok(false);
             }
+            if err.code() == ERROR_OPERATION_ABORTED.into() && self.is_cancelled() {
+                debug!("USN journal read cancelled");
+                return Ok(false);
+            }
 
             warn!("Error reading USN data: {err}");
             return Err(err);
         }
 
+        self.stats.bytes += u64::from(self.bytes_read);
         Ok(true)
     }
 
+    /// Checks that the journal was neither recreated nor wrapped past the read position.
+    ///
+    /// Called with the current journal state after a read failed. Returns
+    /// `UsnError::JournalIdChanged` if the journal was recreated, and `UsnError::JournalWrapped`
+    /// (or, when recovering from gaps, `UsnError::JournalGap`) if records not read yet have been
+    /// overwritten. A start USN of 0 means the beginning of the journal and never wraps.
+    fn check_journal_state(&mut self, journal_data: &UsnJournalData) -> UsnResult<()> {
+        if journal_data.journal_id != self.journal_id {
+            return Err(UsnError::JournalIdChanged {
+                expected: self.journal_id,
+                actual: journal_data.journal_id,
+            });
+        }
+
+        let old_lowest_valid_usn =
+            std::mem::replace(&mut self.lowest_valid_usn, journal_data.lowest_valid_usn);
+        if self.next_start_usn == 0 || journal_data.lowest_valid_usn <= self.next_start_usn {
+            return Ok(());
+        }
+        if self.recover_from_gaps {
+            return Err(self.skip_to_lowest_valid_usn(journal_data));
+        }
+        Err(UsnError::JournalWrapped {
+            read_usn: self.next_start_usn,
+            old_lowest_valid_usn,
+            new_lowest_valid_usn: journal_data.lowest_valid_usn,
+        })
+    }
+
+    /// Moves the read position forward to the oldest record still in the journal.
+    ///
+    /// Called after the records at `next_start_usn` were overwritten; returns the
+    /// `UsnError::JournalGap` to report, or `UsnError::JournalIdChanged` if the journal was
+    /// recreated, in which case the position is left unchanged.
+    fn skip_to_lowest_valid_usn(&mut self, journal_data: &UsnJournalData) -> UsnError {
+        if journal_data.journal_id != self.journal_id {
+            return UsnError::JournalIdChanged {
+                expected: self.journal_id,
+                actual: journal_data.journal_id,
+            };
+        }
+
+        let requested = self.next_start_usn;
+        self.next_start_usn = requested.max(journal_data.lowest_valid_usn);
+        self.offset = 0;
+        self.bytes_read = 0;
+        warn!(
+            "USN journal records from {requested} to {} were overwritten before they were read",
+            self.next_start_usn
+        );
+        UsnError::JournalGap {
+            requested,
+            resumed_at: self.next_start_usn,
+        }
+    }
+
     /// Find the next USN record in the buffer, reading more data if needed.
     ///
     /// Returns `Ok(Some(UsnEntry))` if a record is found, `Ok(None)` if EOF, or an error.
     fn find_next_entry(&mut self) -> UsnResult<Option<UsnEntry>> {
-        if self.offset < self.bytes_read {
-            let (entry, record_len) =
-                parse_usn_record_v2(&self.buffer, self.offset, self.bytes_read)?;
-            self.offset += record_len;
-            return Ok(Some(entry));
-        }
-
-        // We need to read more data
-        if self.get_data()? {
-            // https://learn.microsoft.com/en-us/windows/win32/fileio/walking-a-buffer-of-change-journal-records
-            // The USN returned as the first item in the output buffer is the USN of the next record number to be retrieved.
-            // Use this value to continue reading records from the end boundary forward.
-            self.next_start_usn = usn_record::read_unaligned_from::<Usn>(&self.buffer, 0)
-                .ok_or_else(|| {
-                    UsnError::OtherError(
-                        "USN data buffer missing next-start USN header".to_string(),
-                    )
-                })?;
-            self.offset = size_of::<Usn>() as u32;
+        if self.corrupted || self.past_end_time || self.is_cancelled() {
+            return Ok(None);
+        }
+        loop {
+            if self.offset >= self.bytes_read {
+                // We need to read more data
+                let has_data = match self.get_data() {
+                    Ok(has_data) => has_data,
+                    Err(err) if err.code() == ERROR_INSUFFICIENT_BUFFER.into() => {
+                        self.buffer.grow_to(self.buffer.len().saturating_mul(2))?;
+                        continue;
+                    }
+                    Err(err) => {
+                        // A read fails once the journal was recreated or overwrote the records
+                        // we asked for, so only then is its state worth querying.
+                        let journal_data = query_journal_data(**self.handle)
+                            .map_err(journal_error)?
+                            .into();
+                        if self.recover_from_gaps
+                            && err.code() == ERROR_JOURNAL_ENTRY_DELETED.into()
+                        {
+                            return Err(self.skip_to_lowest_valid_usn(&journal_data));
+                        }
+                        self.check_journal_state(&journal_data)?;
+                        return Err(journal_error(err));
+                    }
+                };
+                if !has_data {
+                    // EOF, no more data to read
+                    return Ok(None);
+                }
 
-            if self.offset < self.bytes_read {
-                let (entry, record_len) =
-                    parse_usn_record_v2(&self.buffer, self.offset, self.bytes_read)?;
-                self.offset += record_len;
+                // A record longer than the buffer comes back cut off; read it again with
+                // enough room.
+                if let Some(needed) = usn_record::oversized_first_record(
+                    &self.buffer,
+                    self.bytes_read,
+                    size_of::<Usn>(),
+                ) {
+                    self.buffer.grow_to(needed)?;
+                    self.bytes_read = 0;
+                    continue;
+                }
+
+                // https://learn.microsoft.com/en-us/windows/win32/fileio/walking-a-buffer-of-change-journal-records
+                // The USN returned as the first item in the output buffer is the USN of the next record number to be retrieved.
+                // Use this value to continue reading records from the end boundary forward.
+                self.next_start_usn = usn_record::read_unaligned_from::<Usn>(&self.buffer, 0)
+                    .ok_or_else(|| {
+                        UsnError::OtherError(
+                            "USN data buffer missing next-start USN header".to_string(),
+                        )
+                    })?;
+                self.offset = size_of::<Usn>() as u32;
+
+                if self.offset >= self.bytes_read {
+                    return Ok(None);
+                }
+            }
+
+            if let Some(entry) = self.parse_next_record()? {
+                if self.end_time.is_some_and(|end| entry.time > end) {
+                    if self.stop_at_end_time {
+                        self.past_end_time = true;
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                if self.start_time.is_some_and(|start| entry.time < start) {
+                    continue;
+                }
                 return Ok(Some(entry));
             }
         }
+    }
 
-        // EOF, no more data to read
-        Ok(None)
+    /// Parses the record at the current offset and advances past it.
+    ///
+    /// Returns `Ok(None)` if the record was skipped because of its version or name.
+    fn parse_next_record(&mut self) -> UsnResult<Option<UsnEntry>> {
+        let base = self.offset as usize;
+        // A malformed length leaves no way to find the next record, so the error ends the
+        // iteration instead of being reported again on every call.
+        let (header, record_len) = usn_record::parse_usn_record_header(
+            &self.buffer,
+            self.offset,
+            self.bytes_read,
+            "USN record",
+        )
+        .inspect_err(|_| self.corrupted = true)?;
+        // Advance before checking the version so an unsupported record never stalls iteration.
+        self.offset += record_len;
+
+        if !usn_record::check_record_version(&header, self.unknown_version_policy)? {
+            return Ok(None);
+        }
+        let name = usn_record::usn_record_name_bytes(&self.buffer, base, &header, "USN record")?;
+        if !self.extensions.matches(name) {
+            return Ok(None);
+        }
+        let entry = parse_usn_record(&self.buffer, base, &header)?;
+        self.current_record = base..self.offset as usize;
+        Ok(Some(entry))
     }
 }
 
@@ -370,6 +1698,7 @@ impl Iterator for UsnJournalIter {
     type Item = UsnResult<UsnEntry>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.current_record = 0..0;
         match self.find_next_entry() {
             Ok(Some(entry)) => Some(Ok(entry)),
             Ok(None) => None,
@@ -381,15 +1710,177 @@ impl Iterator for UsnJournalIter {
     }
 }
 
-fn parse_usn_record_v2(
-    buffer: &[u8],
+/// Reads the record NTFS keeps for the open file `file` with `FSCTL_READ_FILE_USN_DATA`.
+///
+/// The entry always carries the file's current name, parent and attributes; its `usn` is
+/// zero if the file has not changed since the journal was created.
+pub(crate) fn read_file_usn_data(file: HANDLE) -> UsnResult<UsnEntry> {
+    const RECORD_BUFFER_SIZE: usize = 1024;
+
+    let versions = READ_FILE_USN_DATA {
+        MinMajorVersion: 2,
+        MaxMajorVersion: 3,
+    };
+    let mut buffer = AlignedBuffer::new(RECORD_BUFFER_SIZE);
+    let mut bytes_read = 0u32;
+    unsafe {
+        DeviceIoControl(
+            file,
+            FSCTL_READ_FILE_USN_DATA,
+            Some(&versions as *const _ as _),
+            size_of::<READ_FILE_USN_DATA>() as u32,
+            Some(buffer.as_mut_ptr() as _),
+            buffer.len() as u32,
+            Some(&mut bytes_read),
+            None,
+        )
+    }
+    .map_err(journal_error)?;
+
+    let (header, _) = usn_record::parse_usn_record_header(&buffer, 0, bytes_read, "USN record")?;
+    if !usn_record::check_record_version(&header, UnknownVersionPolicy::Error)? {
+        return Err(UsnError::UnsupportedRecordVersion(header.major_version));
+    }
+    parse_usn_record(&buffer, 0, &header)
+}
+
+/// Parses the entries out of a buffer captured from an earlier `FSCTL_READ_USN_JOURNAL` call.
+///
+/// `buffer` must hold the data exactly as the call returned it: the next USN to read,
+/// followed by the records. No volume is accessed, which makes captured data replayable in
+/// tests and offline analysis; see [`Replay`] for how errors are reported.
+///
+/// # Example
+/// ```rust,no_run
+/// use usn_journal_rs::journal;
+///
+/// let captured = std::fs::read("journal.bin")?;
+/// for entry in journal::replay(&captured) {
+///     println!("{:?}", entry?);
+/// }
+/// # Ok::<(), usn_journal_rs::UsnError>(())
+/// ```
+pub fn replay(buffer: &[u8]) -> Replay<'_> {
+    Replay {
+        buffer,
+        offset: 0,
+        corrupted: false,
+    }
+}
+
+/// Iterator over the entries of a captured journal buffer.
+///
+/// Created by [`replay`]. Records of unsupported versions are reported as
+/// `UsnError::UnsupportedRecordVersion` and skipped; `USN_RECORD_V4` range records are
+/// skipped silently. A malformed record, or a buffer too
+/// short to hold the leading USN, is reported once and ends the iteration, since the
+/// following records cannot be located.
+pub struct Replay<'a> {
+    buffer: &'a [u8],
     offset: u32,
-    bytes_read: u32,
-) -> Result<(UsnEntry, u32), UsnError> {
-    let base = offset as usize;
-    let (header, record_len) =
-        usn_record::parse_usn_record_v2_header(buffer, offset, bytes_read, "USN record")?;
-    let file_name = usn_record::parse_usn_record_v2_name(buffer, base, &header, "USN record")?;
+    corrupted: bool,
+}
+
+impl Replay<'_> {
+    /// Returns the USN the captured read would have continued from, stored at the start of
+    /// the buffer.
+    pub fn next_usn(&self) -> Option<Usn> {
+        usn_record::read_unaligned_from::<Usn>(self.buffer, 0)
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = UsnResult<UsnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.corrupted {
+            return None;
+        }
+        if self.offset == 0 {
+            if self.next_usn().is_none() {
+                self.corrupted = true;
+                return Some(Err(UsnError::OtherError(
+                    "journal buffer missing next-USN header".to_string(),
+                )));
+            }
+            self.offset = size_of::<Usn>() as u32;
+        }
+
+        let bytes_read = u32::try_from(self.buffer.len()).unwrap_or(u32::MAX);
+        loop {
+            if self.offset >= bytes_read {
+                return None;
+            }
+
+            let base = self.offset as usize;
+            let parsed = usn_record::parse_usn_record_header(
+                self.buffer,
+                self.offset,
+                bytes_read,
+                "USN record",
+            )
+            .and_then(|(header, record_len)| {
+                self.offset += record_len;
+                if !usn_record::check_record_version(&header, UnknownVersionPolicy::Error)? {
+                    return Ok(None);
+                }
+                parse_usn_record(self.buffer, base, &header).map(Some)
+            });
+            if parsed.is_err() && self.offset as usize == base {
+                self.corrupted = true;
+            }
+            if let Some(parsed) = parsed.transpose() {
+                return Some(parsed);
+            }
+        }
+    }
+}
+
+/// Counts each named reason flag over `entries` up to, but excluding, USN `to`.
+fn tally_reasons(
+    entries: impl Iterator<Item = UsnResult<UsnEntry>>,
+    to: Usn,
+) -> UsnResult<HashMap<&'static str, u64>> {
+    let mut histogram = HashMap::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.usn >= to {
+            break;
+        }
+        for (name, _) in entry.reason.iter_names() {
+            *histogram.entry(name).or_insert(0) += 1;
+        }
+    }
+    Ok(histogram)
+}
+
+/// Counts the distinct MFT records changed by `entries` up to, but excluding, USN `to`.
+fn count_distinct_files(
+    entries: impl Iterator<Item = UsnResult<UsnEntry>>,
+    to: Usn,
+) -> UsnResult<usize> {
+    let mut files = HashSet::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.usn >= to {
+            break;
+        }
+        // 128-bit ReFS IDs have no sequence number to strip.
+        let record = match entry.fid.as_u64() {
+            Some(fid) => FileId::from(fid & RECORD_NUMBER_MASK),
+            None => entry.fid,
+        };
+        files.insert(record);
+    }
+    Ok(files.len())
+}
+
+fn parse_usn_record(
+    buffer: &[u8],
+    base: usize,
+    header: &usn_record::UsnRecordHeader,
+) -> Result<UsnEntry, UsnError> {
+    let file_name = usn_record::parse_usn_record_name(buffer, base, header, "USN record")?;
 
     let time = time::filetime_to_systemtime(header.timestamp).unwrap_or_else(|e| {
         warn!(
@@ -398,130 +1889,254 @@ fn parse_usn_record_v2(
         SystemTime::now()
     });
 
-    Ok((
-        UsnEntry {
-            usn: header.usn,
-            time,
-            fid: header.file_reference_number,
-            parent_fid: header.parent_file_reference_number,
-            reason: header.reason,
-            source_info: header.source_info,
-            file_name,
-            file_attributes: header.file_attributes,
-        },
-        record_len,
-    ))
+    Ok(UsnEntry {
+        usn: header.usn,
+        time,
+        fid: header.file_reference_number,
+        parent_fid: header.parent_file_reference_number,
+        reason: UsnReason::from(header.reason),
+        source_info: header.source_info,
+        file_name,
+        file_attributes: header.file_attributes,
+    })
 }
 
+/// High-level classification of a journal entry, derived from its reason flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChangeKind {
+    /// The file or directory was created.
+    Created,
+    /// The file or directory was deleted.
+    Deleted,
+    /// The file or directory was renamed; the entry carries the old name.
+    RenamedFrom,
+    /// The file or directory was renamed; the entry carries the new name.
+    RenamedTo,
+    /// The contents of the file, or one of its streams, changed.
+    Modified,
+    /// Attributes, timestamps, security or other metadata changed.
+    MetadataChanged,
+    /// Any other record, such as a bare close.
+    Other,
+}
+
+/// A classified journal change with its resolved path.
+///
+/// Returned by [`UsnJournal::read_changes`].
+#[derive(Debug, Clone)]
+pub struct ResolvedChange {
+    pub kind: ChangeKind,
+    pub path: Option<PathBuf>,
+    pub time: SystemTime,
+    pub fid: FileId,
+}
+
+/// Reasons reporting a change to the contents of a data stream.
+pub(crate) const DATA_CHANGES: UsnReason = UsnReason::DATA_OVERWRITE
+    .union(UsnReason::DATA_EXTEND)
+    .union(UsnReason::DATA_TRUNCATION)
+    .union(UsnReason::NAMED_DATA_OVERWRITE)
+    .union(UsnReason::NAMED_DATA_EXTEND)
+    .union(UsnReason::NAMED_DATA_TRUNCATION)
+    .union(UsnReason::STREAM_CHANGE);
+
 /// Represents a USN entry in the USN journal.
-#[derive(Debug)]
+///
+/// With the `serde` feature, `time` is serialized as an RFC 3339 timestamp and `file_name` as
+/// a UTF-8 string, in which invalid UTF-16 is replaced with U+FFFD.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UsnEntry {
     pub usn: Usn,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
     pub time: SystemTime,
-    pub fid: u64,
-    pub parent_fid: u64,
-    pub reason: u32,
+    pub fid: FileId,
+    pub parent_fid: FileId,
+    pub reason: UsnReason,
     pub source_info: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::lossy_os_string"))]
     pub file_name: OsString,
     pub file_attributes: u32,
 }
 
 impl UsnEntry {
+    /// Parses an entry from the bytes of a single `USN_RECORD_V2` or `USN_RECORD_V3`.
+    ///
+    /// Useful with [`UsnJournalIter::current_record_bytes`] or records persisted elsewhere.
+    ///
+    /// # Errors
+    /// Returns an error if the record is truncated, malformed, or of another version.
+    pub fn new(record: &[u8]) -> UsnResult<Self> {
+        let (header, _) = usn_record::parse_usn_record_header(
+            record,
+            0,
+            u32::try_from(record.len()).unwrap_or(u32::MAX),
+            "USN record",
+        )?;
+        if !usn_record::check_record_version(&header, UnknownVersionPolicy::Error)? {
+            return Err(UsnError::UnsupportedRecordVersion(header.major_version));
+        }
+        parse_usn_record(record, 0, &header)
+    }
+
     /// Returns true if this entry represents a directory.
     pub fn is_dir(&self) -> bool {
         let attributes = FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes);
         attributes.contains(FILE_ATTRIBUTE_DIRECTORY)
     }
 
+    /// Returns the file name for display, handling invalid UTF-16 according to `policy`.
+    ///
+    /// `file_name` always keeps the raw name; see [`NameEncodingPolicy`] for the options.
+    pub fn display_name(&self, policy: NameEncodingPolicy) -> Option<Cow<'_, OsStr>> {
+        policy.apply(&self.file_name)
+    }
+
     /// Returns true if this entry represents a hidden file or directory.
     pub fn is_hidden(&self) -> bool {
         let attributes = FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes);
         attributes.contains(FILE_ATTRIBUTE_HIDDEN)
     }
 
-    /// Converts a USN reason bitfield to a human-readable string using Windows constants.
-    pub fn get_reason_string(&self) -> String {
-        let reason = self.reason;
-        let mut reasons = Vec::new();
-        if reason & USN_REASON_DATA_OVERWRITE != 0 {
-            reasons.push("DATA_OVERWRITE");
-        }
-        if reason & USN_REASON_DATA_EXTEND != 0 {
-            reasons.push("DATA_EXTEND");
-        }
-        if reason & USN_REASON_DATA_TRUNCATION != 0 {
-            reasons.push("DATA_TRUNCATION");
-        }
-        if reason & USN_REASON_NAMED_DATA_OVERWRITE != 0 {
-            reasons.push("NAMED_DATA_OVERWRITE");
-        }
-        if reason & USN_REASON_NAMED_DATA_EXTEND != 0 {
-            reasons.push("NAMED_DATA_EXTEND");
-        }
-        if reason & USN_REASON_NAMED_DATA_TRUNCATION != 0 {
-            reasons.push("NAMED_DATA_TRUNCATION");
-        }
-        if reason & USN_REASON_FILE_CREATE != 0 {
-            reasons.push("FILE_CREATE");
-        }
-        if reason & USN_REASON_FILE_DELETE != 0 {
-            reasons.push("FILE_DELETE");
-        }
-        if reason & USN_REASON_EA_CHANGE != 0 {
-            reasons.push("EA_CHANGE");
-        }
-        if reason & USN_REASON_SECURITY_CHANGE != 0 {
-            reasons.push("SECURITY_CHANGE");
-        }
-        if reason & USN_REASON_RENAME_OLD_NAME != 0 {
-            reasons.push("RENAME_OLD_NAME");
-        }
-        if reason & USN_REASON_RENAME_NEW_NAME != 0 {
-            reasons.push("RENAME_NEW_NAME");
-        }
-        if reason & USN_REASON_INDEXABLE_CHANGE != 0 {
-            reasons.push("INDEXABLE_CHANGE");
-        }
-        if reason & USN_REASON_BASIC_INFO_CHANGE != 0 {
-            reasons.push("BASIC_INFO_CHANGE");
-        }
-        if reason & USN_REASON_HARD_LINK_CHANGE != 0 {
-            reasons.push("HARD_LINK_CHANGE");
-        }
-        if reason & USN_REASON_COMPRESSION_CHANGE != 0 {
-            reasons.push("COMPRESSION_CHANGE");
-        }
-        if reason & USN_REASON_ENCRYPTION_CHANGE != 0 {
-            reasons.push("ENCRYPTION_CHANGE");
-        }
-        if reason & USN_REASON_OBJECT_ID_CHANGE != 0 {
-            reasons.push("OBJECT_ID_CHANGE");
-        }
-        if reason & USN_REASON_REPARSE_POINT_CHANGE != 0 {
-            reasons.push("REPARSE_POINT_CHANGE");
-        }
-        if reason & USN_REASON_STREAM_CHANGE != 0 {
-            reasons.push("STREAM_CHANGE");
-        }
-        if reason & USN_REASON_TRANSACTED_CHANGE != 0 {
-            reasons.push("TRANSACTED_CHANGE");
-        }
-        if reason & USN_REASON_INTEGRITY_CHANGE != 0 {
-            reasons.push("INTEGRITY_CHANGE");
-        }
-        if reason & USN_REASON_DESIRED_STORAGE_CLASS_CHANGE != 0 {
-            reasons.push("DESIRED_STORAGE_CLASS_CHANGE");
-        }
-        if reason & USN_REASON_CLOSE != 0 {
-            reasons.push("CLOSE");
-        }
-        if reasons.is_empty() {
-            reasons.push("UNKNOWN");
-        }
-        reasons.join(" | ")
+    /// Returns true if this entry represents a read-only file or directory.
+    pub fn is_readonly(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_READONLY)
     }
 
-    /// Formats the USN entry into a human-readable string.
+    /// Returns true if this entry represents a file or directory the operating system uses.
+    pub fn is_system(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_SYSTEM)
+    }
+
+    /// Returns true if this entry represents a file or directory marked for backup or removal.
+    pub fn is_archive(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_ARCHIVE)
+    }
+
+    /// Returns true if this entry represents a reparse point, such as a symbolic link or junction.
+    pub fn is_reparse_point(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_REPARSE_POINT)
+    }
+
+    /// Returns true if this entry represents a compressed file or directory.
+    pub fn is_compressed(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_COMPRESSED)
+    }
+
+    /// Returns true if this entry represents an encrypted file or directory.
+    pub fn is_encrypted(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_ENCRYPTED)
+    }
+
+    /// Returns true if this entry represents a sparse file.
+    pub fn is_sparse(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_SPARSE_FILE)
+    }
+
+    /// Returns true if this entry represents a file whose data has been moved to offline storage.
+    pub fn is_offline(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_OFFLINE)
+    }
+
+    /// Returns the entry's attributes as `FILE_FLAGS_AND_ATTRIBUTES`, for checking flags
+    /// that have no predicate of their own.
+    pub fn attributes(&self) -> FILE_FLAGS_AND_ATTRIBUTES {
+        FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes)
+    }
+
+    /// Converts the entry into the [`MftEntry`] a [`DirectoryTree`] indexes.
+    ///
+    /// Timestamps are left `None`, as journal records do not carry them.
+    ///
+    /// [`DirectoryTree`]: crate::tree::DirectoryTree
+    pub fn to_mft_entry(&self) -> MftEntry {
+        MftEntry {
+            usn: self.usn,
+            fid: self.fid,
+            parent_fid: self.parent_fid,
+            file_name: self.file_name.clone(),
+            file_attributes: self.file_attributes,
+            timestamps: None,
+            sizes: None,
+        }
+    }
+
+    /// Returns true if the file or directory was created.
+    pub fn is_create(&self) -> bool {
+        self.reason.contains(UsnReason::FILE_CREATE)
+    }
+
+    /// Returns true if the file or directory was deleted.
+    pub fn is_delete(&self) -> bool {
+        self.reason.contains(UsnReason::FILE_DELETE)
+    }
+
+    /// Returns true if this is the record of a rename carrying the old name and parent.
+    pub fn is_rename_old(&self) -> bool {
+        self.reason.contains(UsnReason::RENAME_OLD_NAME)
+    }
+
+    /// Returns true if this is the record of a rename carrying the new name and parent.
+    pub fn is_rename_new(&self) -> bool {
+        self.reason.contains(UsnReason::RENAME_NEW_NAME)
+    }
+
+    /// Returns true if the contents of the file's default or a named data stream changed,
+    /// including streams being added or removed.
+    pub fn is_data_change(&self) -> bool {
+        self.reason.intersects(DATA_CHANGES)
+    }
+
+    /// Returns true if the file was closed, ending the run of changes this record summarises.
+    pub fn is_close(&self) -> bool {
+        self.reason.contains(UsnReason::CLOSE)
+    }
+
+    /// Classifies the entry's reason flags into a single high-level [`ChangeKind`].
+    ///
+    /// A record often carries several reasons; the most significant one wins, in the order
+    /// deleted, created, renamed, modified, then metadata changed.
+    pub fn change_kind(&self) -> ChangeKind {
+        const METADATA_CHANGES: UsnReason = UsnReason::BASIC_INFO_CHANGE
+            .union(UsnReason::COMPRESSION_CHANGE)
+            .union(UsnReason::DESIRED_STORAGE_CLASS_CHANGE)
+            .union(UsnReason::EA_CHANGE)
+            .union(UsnReason::ENCRYPTION_CHANGE)
+            .union(UsnReason::HARD_LINK_CHANGE)
+            .union(UsnReason::INDEXABLE_CHANGE)
+            .union(UsnReason::INTEGRITY_CHANGE)
+            .union(UsnReason::OBJECT_ID_CHANGE)
+            .union(UsnReason::REPARSE_POINT_CHANGE)
+            .union(UsnReason::SECURITY_CHANGE)
+            .union(UsnReason::TRANSACTED_CHANGE);
+
+        let reason = self.reason;
+        if reason.contains(UsnReason::FILE_DELETE) {
+            ChangeKind::Deleted
+        } else if reason.contains(UsnReason::FILE_CREATE) {
+            ChangeKind::Created
+        } else if reason.contains(UsnReason::RENAME_NEW_NAME) {
+            ChangeKind::RenamedTo
+        } else if reason.contains(UsnReason::RENAME_OLD_NAME) {
+            ChangeKind::RenamedFrom
+        } else if reason.intersects(DATA_CHANGES) {
+            ChangeKind::Modified
+        } else if reason.intersects(METADATA_CHANGES) {
+            ChangeKind::MetadataChanged
+        } else {
+            ChangeKind::Other
+        }
+    }
+
+    /// Converts a USN reason bitfield to a human-readable string using Windows constants.
+    ///
+    /// Equivalent to formatting [`UsnEntry::reason`] with `Display`.
+    pub fn get_reason_string(&self) -> String {
+        self.reason.to_string()
+    }
+
+    /// Formats the USN entry into a human-readable string.
     pub fn pretty_format<P>(&self, full_path_opt: Option<P>) -> String
     where
         P: AsRef<Path>,
@@ -567,7 +2182,16 @@ impl UsnEntry {
 mod tests {
     use super::*;
     use std::{ffi::OsString, mem::offset_of, ptr};
-    use windows::Win32::System::Ioctl::{USN_JOURNAL_DATA_V0, USN_RECORD_V2};
+    use windows::Win32::{
+        Foundation::{ERROR_ACCESS_DENIED, HANDLE},
+        Storage::FileSystem::FILE_ID_128,
+        System::Ioctl::{
+            USN_JOURNAL_DATA_V0, USN_REASON_BASIC_INFO_CHANGE, USN_REASON_CLOSE,
+            USN_REASON_DATA_EXTEND, USN_REASON_DATA_OVERWRITE, USN_REASON_FILE_CREATE,
+            USN_REASON_FILE_DELETE, USN_REASON_RENAME_NEW_NAME, USN_REASON_RENAME_OLD_NAME,
+            USN_REASON_SECURITY_CHANGE, USN_RECORD_V2, USN_RECORD_V3, USN_RECORD_V4,
+        },
+    };
 
     // Mock data generators
     fn create_mock_usn_journal_data() -> USN_JOURNAL_DATA_V0 {
@@ -638,10 +2262,109 @@ mod tests {
         buffer
     }
 
+    fn create_mock_usn_record_v3(
+        usn: i64,
+        fid: FileId,
+        parent_fid: FileId,
+        reason: u32,
+        file_name: &str,
+        file_attributes: u32,
+    ) -> Vec<u8> {
+        let file_name_utf16: Vec<u16> = file_name.encode_utf16().collect();
+        let file_name_len = file_name_utf16.len() * size_of::<u16>();
+        let file_name_offset = offset_of!(USN_RECORD_V3, FileName);
+        let aligned_size = (file_name_offset + file_name_len + 7) & !7; // 8-byte align
+
+        let mut buffer = vec![0u8; aligned_size];
+
+        let record = USN_RECORD_V3 {
+            RecordLength: aligned_size as u32,
+            MajorVersion: 3,
+            MinorVersion: 0,
+            FileReferenceNumber: FILE_ID_128 {
+                Identifier: fid.to_le_bytes(),
+            },
+            ParentFileReferenceNumber: FILE_ID_128 {
+                Identifier: parent_fid.to_le_bytes(),
+            },
+            Usn: usn,
+            TimeStamp: 0x12345678ABCDEF01i64,
+            Reason: reason,
+            SourceInfo: 0,
+            SecurityId: 0,
+            FileAttributes: file_attributes,
+            FileNameLength: file_name_len as u16,
+            FileNameOffset: file_name_offset as u16,
+            FileName: [0; 1],
+        };
+
+        // Copy the fixed part of the record, then the name at FileNameOffset
+        unsafe {
+            ptr::copy_nonoverlapping(
+                &record as *const USN_RECORD_V3 as *const u8,
+                buffer.as_mut_ptr(),
+                file_name_offset,
+            );
+            ptr::copy_nonoverlapping(
+                file_name_utf16.as_ptr() as *const u8,
+                buffer.as_mut_ptr().add(file_name_offset),
+                file_name_len,
+            );
+        }
+
+        buffer
+    }
+
+    fn create_mock_read_buffer(next_usn: Usn, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut buffer = next_usn.to_le_bytes().to_vec();
+        for record in records {
+            buffer.extend_from_slice(record);
+        }
+        buffer
+    }
+
+    // Builds an iterator whose buffer already holds the result of one FSCTL_READ_USN_JOURNAL call.
+    fn create_mock_journal_iter(read_buffer: Vec<u8>) -> UsnJournalIter {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        UsnJournalIter {
+            handle: volume.shared_handle(),
+            volume_serial: Some(0x1A2B_3C4D),
+            journal_id: 0x123456789ABCDEF0,
+            next_start_usn: usn_record::read_unaligned_from::<Usn>(&read_buffer, 0).unwrap(),
+            bytes_read: read_buffer.len() as u32,
+            buffer: AlignedBuffer::from(read_buffer.as_slice()),
+            offset: size_of::<Usn>() as u32,
+            reason_mask: USN_REASON_MASK_ALL,
+            return_only_on_close: 0,
+            timeout: 0,
+            bytes_to_wait_for: 0,
+            unknown_version_policy: UnknownVersionPolicy::default(),
+            unprivileged: false,
+            latency_budget: Duration::ZERO,
+            max_bytes_to_wait_for: 0,
+            stats: ReadStats::default(),
+            recover_from_gaps: false,
+            lowest_valid_usn: 0,
+            corrupted: false,
+            start_time: None,
+            end_time: None,
+            stop_at_end_time: false,
+            past_end_time: false,
+            extensions: ExtensionFilter::default(),
+            current_record: 0..0,
+            cancellable: None,
+        }
+    }
+
     fn parse_mock_usn_entry(record_data: &[u8]) -> UsnEntry {
-        parse_usn_record_v2(record_data, 0, record_data.len() as u32)
-            .unwrap()
-            .0
+        let (header, _) = usn_record::parse_usn_record_header(
+            record_data,
+            0,
+            record_data.len() as u32,
+            "USN record",
+        )
+        .unwrap();
+        parse_usn_record(record_data, 0, &header).unwrap()
     }
 
     #[test]
@@ -658,6 +2381,127 @@ mod tests {
         assert_eq!(journal_data.allocation_delta, 8 * 1024 * 1024);
     }
 
+    #[test]
+    fn test_journal_usage_from_mock_journal_data() {
+        let journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+
+        assert_eq!(journal_data.used_bytes(), 0x4000);
+        assert_eq!(
+            journal_data.usage_ratio(),
+            0x4000 as f64 / (32 * 1024 * 1024) as f64
+        );
+        assert!(!journal_data.is_near_wrap(0.9));
+        assert!(journal_data.is_near_wrap(0.0));
+
+        let nearly_full = UsnJournalData {
+            next_usn: journal_data.first_usn + (30 * 1024 * 1024),
+            ..journal_data.clone()
+        };
+        assert!(nearly_full.is_near_wrap(0.9));
+        assert!(!nearly_full.is_near_wrap(0.95));
+
+        let no_maximum = UsnJournalData {
+            maximum_size: 0,
+            ..journal_data
+        };
+        assert_eq!(no_maximum.usage_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_usn_to_offset_within_valid_range() {
+        let journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+
+        assert_eq!(journal_data.usn_to_offset(0x1000), Some(0));
+        assert_eq!(journal_data.usn_to_offset(0x1234), Some(0x234));
+        assert_eq!(journal_data.usn_to_offset(0x5000), Some(0x4000));
+        assert_eq!(journal_data.usn_to_offset(0xFFF), None);
+        assert_eq!(journal_data.usn_to_offset(0x5001), None);
+    }
+
+    #[test]
+    fn test_bytes_written_between_snapshots() {
+        let earlier = UsnJournalData::from(create_mock_usn_journal_data());
+        let later = UsnJournalData {
+            next_usn: earlier.next_usn + 0x2000,
+            ..earlier.clone()
+        };
+
+        assert_eq!(later.bytes_written_since(&earlier), 0x2000);
+        assert_eq!(earlier.bytes_written_since(&later), 0);
+        assert_eq!(
+            later.write_rate_since(&earlier, Duration::from_secs(2)),
+            4096.0
+        );
+        assert_eq!(later.write_rate_since(&earlier, Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn test_bytes_written_ignores_recreated_journal() {
+        let earlier = UsnJournalData::from(create_mock_usn_journal_data());
+        let recreated = UsnJournalData {
+            journal_id: earlier.journal_id + 1,
+            next_usn: earlier.next_usn + 0x2000,
+            ..earlier.clone()
+        };
+
+        assert_eq!(recreated.bytes_written_since(&earlier), 0);
+    }
+
+    #[test]
+    fn test_recommend_max_size_covers_retention_window() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let journal = UsnJournal::new(&volume);
+        const MIB: u64 = 1024 * 1024;
+
+        // 1 MiB/s for an hour
+        assert_eq!(
+            journal.recommend_max_size(MIB, Duration::from_secs(3600)),
+            3600 * MIB
+        );
+        // 100 KiB/s for 10 minutes rounds up to whole MiB
+        assert_eq!(
+            journal.recommend_max_size(100 * 1024, Duration::from_secs(600)),
+            59 * MIB
+        );
+        // Sub-second retention windows still count
+        assert_eq!(
+            journal.recommend_max_size(64 * MIB, Duration::from_millis(1500)),
+            96 * MIB
+        );
+    }
+
+    #[test]
+    fn test_recommend_max_size_bounds() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let journal = UsnJournal::new(&volume);
+
+        assert_eq!(
+            journal.recommend_max_size(0, Duration::from_secs(3600)),
+            DEFAULT_JOURNAL_MAX_SIZE
+        );
+        assert_eq!(
+            journal.recommend_max_size(1024, Duration::from_secs(1)),
+            DEFAULT_JOURNAL_MAX_SIZE
+        );
+        assert_eq!(
+            journal.recommend_max_size(u64::MAX, Duration::MAX),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_offset_to_usn_round_trips() {
+        let journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+
+        assert_eq!(journal_data.offset_to_usn(0x234), Some(0x1234));
+        assert_eq!(
+            journal_data.offset_to_usn(journal_data.usn_to_offset(0x4321).unwrap()),
+            Some(0x4321)
+        );
+        assert_eq!(journal_data.offset_to_usn(0x4001), None);
+        assert_eq!(journal_data.offset_to_usn(u64::MAX), None);
+    }
+
     #[test]
     fn test_usn_entry_creation() {
         let record_data = create_mock_usn_record(
@@ -673,12 +2517,266 @@ mod tests {
         assert_eq!(entry.usn, 0x2000);
         assert_eq!(entry.fid, 0x123456);
         assert_eq!(entry.parent_fid, 0x654321);
-        assert_eq!(entry.reason, USN_REASON_FILE_CREATE);
+        assert_eq!(entry.reason.raw(), USN_REASON_FILE_CREATE);
         assert_eq!(entry.file_name, OsString::from("test.txt"));
         assert!(!entry.is_dir());
         assert!(!entry.is_hidden());
     }
 
+    #[test]
+    fn test_usn_entry_from_v3_record() {
+        let fid = FileId(0x0000_0000_0000_0001_0000_0000_0012_3456);
+        let parent_fid = FileId(0x0000_0000_0000_0002_0000_0000_0065_4321);
+        let record_data = create_mock_usn_record_v3(
+            0x2000,
+            fid,
+            parent_fid,
+            USN_REASON_FILE_CREATE,
+            "refs.txt",
+            0,
+        );
+
+        let entry = parse_mock_usn_entry(&record_data);
+        assert_eq!(entry.usn, 0x2000);
+        assert_eq!(entry.fid, fid);
+        assert_eq!(entry.parent_fid, parent_fid);
+        assert_eq!(entry.fid.as_u64(), None);
+        assert_eq!(entry.reason.raw(), USN_REASON_FILE_CREATE);
+        assert_eq!(entry.file_name, OsString::from("refs.txt"));
+    }
+
+    #[test]
+    fn test_malformed_record_length_ends_iteration() {
+        let mut zero_length =
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "zero.txt", 0);
+        zero_length[..4].copy_from_slice(&0u32.to_le_bytes());
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[zero_length]));
+
+        assert!(matches!(
+            iter.next(),
+            Some(Err(UsnError::OtherError(message)))
+                if message == "USN record at offset 8 contains invalid zero RecordLength"
+        ));
+        assert!(iter.next().is_none());
+
+        let mut too_long =
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "long.txt", 0);
+        too_long[..4].copy_from_slice(&0x1000u32.to_le_bytes());
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[too_long]));
+
+        assert!(matches!(
+            iter.next(),
+            Some(Err(UsnError::OtherError(message))) if message.contains("extends past buffer bounds")
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_with_paths_attaches_resolved_paths() {
+        let records = [
+            create_mock_usn_record(0x100, 0x11, 0x10, USN_REASON_FILE_CREATE, "a.txt", 0x20),
+            create_mock_usn_record(0x200, 0x12, 0x10, USN_REASON_FILE_CREATE, "b.txt", 0x20),
+        ];
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        resolver.cache_directory(FileId(0x10), PathBuf::from(r"T:\Docs"));
+
+        let iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+        let paths = iter
+            .with_paths(&mut resolver)
+            .take(2)
+            .map(|result| result.map(|(entry, path)| (entry.usn, path)))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                (0x100, Some(PathBuf::from(r"T:\Docs\a.txt"))),
+                (0x200, Some(PathBuf::from(r"T:\Docs\b.txt"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checked_rejects_out_of_order_usn() {
+        let records = [
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "a.txt", 0),
+            create_mock_usn_record(0x300, 0x11, 0x5, USN_REASON_FILE_CREATE, "b.txt", 0),
+            create_mock_usn_record(0x200, 0x12, 0x5, USN_REASON_FILE_CREATE, "c.txt", 0),
+            create_mock_usn_record(0x400, 0x13, 0x5, USN_REASON_FILE_CREATE, "d.txt", 0),
+        ];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x500, &records)).checked();
+
+        assert_eq!(iter.next().unwrap().unwrap().usn, 0x100);
+        assert_eq!(iter.next().unwrap().unwrap().usn, 0x300);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(UsnError::NonMonotonicUsn {
+                prev: 0x300,
+                current: 0x200
+            }))
+        ));
+        assert_eq!(iter.next().unwrap().unwrap().usn, 0x400);
+    }
+
+    #[test]
+    fn test_journal_iter_reads_mixed_record_versions() {
+        let records = [
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "v2.txt", 0),
+            create_mock_usn_record_v3(
+                0x200,
+                FileId::from_u64(0x11),
+                FileId::from_u64(0x5),
+                USN_REASON_FILE_CREATE,
+                "v3.txt",
+                0,
+            ),
+        ];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+
+        let first = iter.next().unwrap().unwrap();
+        assert_eq!(first.fid, 0x10);
+        assert_eq!(first.file_name, "v2.txt");
+        let second = iter.next().unwrap().unwrap();
+        assert_eq!(second.usn, 0x200);
+        assert_eq!(u64::try_from(second.fid), Ok(0x11));
+        assert_eq!(second.parent_fid, 0x5);
+        assert_eq!(second.file_name, "v3.txt");
+    }
+
+    #[test]
+    fn test_gap_recovery_resumes_at_lowest_valid_usn() {
+        let records = [create_mock_usn_record(
+            0x100,
+            0x10,
+            0x5,
+            USN_REASON_FILE_CREATE,
+            "old.txt",
+            0,
+        )];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &records));
+        iter.recover_from_gaps = true;
+        assert_eq!(iter.next().unwrap().unwrap().usn, 0x100);
+        assert_eq!(iter.next_usn(), 0x200);
+
+        // The journal wrapped past 0x200 (lowest_valid_usn is 0x800) before the next read.
+        let journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+        let gap = iter.skip_to_lowest_valid_usn(&journal_data);
+
+        assert!(matches!(
+            gap,
+            UsnError::JournalGap {
+                requested: 0x200,
+                resumed_at: 0x800
+            }
+        ));
+        assert_eq!(iter.next_usn(), 0x800);
+        assert_eq!(iter.cursor().usn, 0x800);
+        assert!(iter.current_record_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_journal_check_detects_recreated_and_wrapped_journal() {
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[]));
+        let mut journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+        journal_data.lowest_valid_usn = 0x100;
+        assert!(iter.check_journal_state(&journal_data).is_ok());
+
+        // The journal was deleted and recreated between two reads.
+        let recreated = UsnJournalData {
+            journal_id: 0x42,
+            ..journal_data.clone()
+        };
+        assert!(matches!(
+            iter.check_journal_state(&recreated),
+            Err(UsnError::JournalIdChanged {
+                expected: 0x123456789ABCDEF0,
+                actual: 0x42
+            })
+        ));
+
+        // The journal wrapped past the next record to read.
+        journal_data.lowest_valid_usn = 0x800;
+        assert!(matches!(
+            iter.check_journal_state(&journal_data),
+            Err(UsnError::JournalWrapped {
+                read_usn: 0x200,
+                old_lowest_valid_usn: 0x100,
+                new_lowest_valid_usn: 0x800
+            })
+        ));
+        assert_eq!(iter.next_usn(), 0x200);
+
+        // With recovery enabled the same state skips ahead instead.
+        iter.recover_from_gaps = true;
+        assert!(matches!(
+            iter.check_journal_state(&journal_data),
+            Err(UsnError::JournalGap {
+                requested: 0x200,
+                resumed_at: 0x800
+            })
+        ));
+        assert!(iter.check_journal_state(&journal_data).is_ok());
+    }
+
+    #[test]
+    fn test_gap_recovery_rejects_recreated_journal() {
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[]));
+        let mut journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+        journal_data.journal_id += 1;
+
+        let err = iter.skip_to_lowest_valid_usn(&journal_data);
+
+        assert!(matches!(err, UsnError::JournalIdChanged { .. }));
+        assert_eq!(iter.next_usn(), 0x200);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_usn_entry_serde_round_trip() {
+        let entry = UsnEntry {
+            usn: 0x2000,
+            time: SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_700),
+            fid: FileId(0x1_0000_0000_0000_0042),
+            parent_fid: FileId(0x5),
+            reason: UsnReason::RENAME_NEW_NAME | UsnReason::CLOSE,
+            source_info: 0x2,
+            file_name: OsString::from("résumé.txt"),
+            file_attributes: FILE_ATTRIBUTE_HIDDEN.0,
+        };
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["time"], "2023-11-14T22:13:20.123456700Z");
+        assert_eq!(json["file_name"], "résumé.txt");
+        assert_eq!(
+            json["reason"],
+            serde_json::json!({ "bits": 0x8000_2000u32, "flags": ["RENAME_NEW_NAME", "CLOSE"] })
+        );
+
+        let parsed: UsnEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.usn, entry.usn);
+        assert_eq!(parsed.time, entry.time);
+        assert_eq!(parsed.fid, entry.fid);
+        assert_eq!(parsed.parent_fid, entry.parent_fid);
+        assert_eq!(parsed.reason, entry.reason);
+        assert_eq!(parsed.source_info, entry.source_info);
+        assert_eq!(parsed.file_name, entry.file_name);
+        assert_eq!(parsed.file_attributes, entry.file_attributes);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_enum_options_deserialize_fills_defaults() {
+        let options: EnumOptions =
+            serde_json::from_str(r#"{ "start_usn": 4096, "wait_for_more": true }"#).unwrap();
+
+        assert_eq!(options.start_usn, 4096);
+        assert!(options.wait_for_more);
+        assert_eq!(options.reason_mask, USN_REASON_MASK_ALL);
+        assert_eq!(options.buffer_size, DEFAULT_BUFFER_SIZE);
+    }
+
     #[test]
     fn test_usn_entry_directory_detection() {
         let record_data = create_mock_usn_record(
@@ -711,6 +2809,90 @@ mod tests {
         assert!(entry.is_hidden());
     }
 
+    #[test]
+    fn test_usn_entry_reason_predicates() {
+        let entry_with = |reason: u32| {
+            parse_mock_usn_entry(&create_mock_usn_record(
+                0x4000,
+                0x10,
+                0x5,
+                reason,
+                "report.docx",
+                0x20,
+            ))
+        };
+
+        let created =
+            entry_with(USN_REASON_FILE_CREATE | USN_REASON_DATA_EXTEND | USN_REASON_CLOSE);
+        assert!(created.is_create());
+        assert!(created.is_data_change());
+        assert!(created.is_close());
+        assert!(!created.is_delete());
+        assert!(!created.is_rename_old());
+        assert!(!created.is_rename_new());
+
+        let renamed = entry_with(USN_REASON_RENAME_NEW_NAME | USN_REASON_BASIC_INFO_CHANGE);
+        assert!(renamed.is_rename_new());
+        assert!(!renamed.is_rename_old());
+        assert!(!renamed.is_data_change());
+        assert!(!renamed.is_close());
+
+        let rename_old = entry_with(USN_REASON_RENAME_OLD_NAME);
+        assert!(rename_old.is_rename_old());
+        assert!(!rename_old.is_rename_new());
+
+        let deleted = entry_with(
+            crate::reasons::NAMED_DATA_OVERWRITE | USN_REASON_FILE_DELETE | USN_REASON_CLOSE,
+        );
+        assert!(deleted.is_delete());
+        assert!(deleted.is_data_change());
+        assert!(deleted.is_close());
+        assert!(!deleted.is_create());
+
+        let metadata_only = entry_with(USN_REASON_SECURITY_CHANGE);
+        assert!(!metadata_only.is_create());
+        assert!(!metadata_only.is_delete());
+        assert!(!metadata_only.is_rename_old());
+        assert!(!metadata_only.is_rename_new());
+        assert!(!metadata_only.is_data_change());
+        assert!(!metadata_only.is_close());
+    }
+
+    #[test]
+    fn test_usn_entry_attribute_predicates() {
+        type Predicate = fn(&UsnEntry) -> bool;
+        let predicates: [(FILE_FLAGS_AND_ATTRIBUTES, Predicate); 8] = [
+            (FILE_ATTRIBUTE_READONLY, UsnEntry::is_readonly),
+            (FILE_ATTRIBUTE_SYSTEM, UsnEntry::is_system),
+            (FILE_ATTRIBUTE_ARCHIVE, UsnEntry::is_archive),
+            (FILE_ATTRIBUTE_REPARSE_POINT, UsnEntry::is_reparse_point),
+            (FILE_ATTRIBUTE_COMPRESSED, UsnEntry::is_compressed),
+            (FILE_ATTRIBUTE_ENCRYPTED, UsnEntry::is_encrypted),
+            (FILE_ATTRIBUTE_SPARSE_FILE, UsnEntry::is_sparse),
+            (FILE_ATTRIBUTE_OFFLINE, UsnEntry::is_offline),
+        ];
+
+        for (attribute, predicate) in predicates {
+            let entry = parse_mock_usn_entry(&create_mock_usn_record(
+                0x4000,
+                0x10,
+                0x5,
+                USN_REASON_BASIC_INFO_CHANGE,
+                "attributes.bin",
+                attribute.0,
+            ));
+            assert!(predicate(&entry), "{attribute:?} not detected");
+            assert_eq!(entry.attributes(), attribute);
+            // Every other predicate stays false.
+            let others = predicates
+                .iter()
+                .filter(|(other, _)| *other != attribute)
+                .filter(|(_, other)| other(&entry))
+                .count();
+            assert_eq!(others, 0, "{attribute:?} matched other predicates");
+        }
+    }
+
     #[test]
     fn test_usn_entry_reason_string_conversion() {
         let record_data = create_mock_usn_record(
@@ -743,6 +2925,37 @@ mod tests {
         assert_eq!(reason_string, "UNKNOWN");
     }
 
+    #[test]
+    fn test_usn_entry_change_kind_classification() {
+        let cases = [
+            (
+                USN_REASON_FILE_CREATE | USN_REASON_DATA_EXTEND | USN_REASON_CLOSE,
+                ChangeKind::Created,
+            ),
+            (
+                USN_REASON_FILE_CREATE | USN_REASON_FILE_DELETE | USN_REASON_CLOSE,
+                ChangeKind::Deleted,
+            ),
+            (USN_REASON_RENAME_OLD_NAME, ChangeKind::RenamedFrom),
+            (
+                USN_REASON_RENAME_NEW_NAME | USN_REASON_CLOSE,
+                ChangeKind::RenamedTo,
+            ),
+            (
+                USN_REASON_DATA_OVERWRITE | USN_REASON_BASIC_INFO_CHANGE,
+                ChangeKind::Modified,
+            ),
+            (USN_REASON_SECURITY_CHANGE, ChangeKind::MetadataChanged),
+            (USN_REASON_CLOSE, ChangeKind::Other),
+        ];
+
+        for (reason, expected) in cases {
+            let record_data = create_mock_usn_record(0x5000, 0x1, 0x5, reason, "test.txt", 0);
+            let entry = parse_mock_usn_entry(&record_data);
+            assert_eq!(entry.change_kind(), expected, "reason 0x{reason:x}");
+        }
+    }
+
     #[test]
     fn test_usn_entry_pretty_format_with_path() {
         let record_data = create_mock_usn_record(
@@ -795,4 +3008,1575 @@ mod tests {
         assert!(formatted.contains("Path"));
         assert!(formatted.contains("deleted.txt"));
     }
+
+    #[test]
+    fn test_next_usn_points_at_next_buffered_entry() {
+        let records = vec![
+            create_mock_usn_record(0x100, 0x1, 0x5, USN_REASON_FILE_CREATE, "a.txt", 0),
+            create_mock_usn_record(0x200, 0x2, 0x5, USN_REASON_FILE_CREATE, "b.txt", 0),
+        ];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+
+        assert_eq!(iter.next_usn(), 0x100);
+        iter.next().unwrap().unwrap();
+        assert_eq!(iter.next_usn(), 0x200);
+        iter.next().unwrap().unwrap();
+        assert_eq!(iter.next_usn(), 0x300);
+    }
+
+    #[test]
+    fn test_latency_budget_grows_bytes_to_wait_for() {
+        let with_budget = |millis| EnumOptions {
+            wait_for_more: true,
+            latency_budget: Duration::from_millis(millis),
+            ..Default::default()
+        };
+
+        let (_, immediate) = with_budget(0).read_wait();
+        let (short_timeout, short) = with_budget(100).read_wait();
+        let (long_timeout, long) = with_budget(2_000).read_wait();
+        let (_, capped) = with_budget(3_600_000).read_wait();
+
+        assert_eq!(immediate, 1);
+        assert!(short > immediate);
+        assert!(long > short);
+        assert_eq!((short_timeout, long_timeout), (1, 2));
+        assert_eq!(capped, (DEFAULT_BUFFER_SIZE - size_of::<Usn>()) as u64);
+
+        let no_wait = EnumOptions {
+            latency_budget: Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert_eq!(no_wait.read_wait().1, 0);
+    }
+
+    #[test]
+    fn test_read_latency_adapts_batch_size_and_averages() {
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x100, &[]));
+        iter.latency_budget = Duration::from_millis(100);
+        iter.max_bytes_to_wait_for = 4096;
+        iter.bytes_to_wait_for = 4096;
+
+        iter.record_read_latency(Duration::from_millis(300));
+        assert_eq!(iter.bytes_to_wait_for, 2048);
+        iter.record_read_latency(Duration::from_millis(10));
+        iter.record_read_latency(Duration::from_millis(10));
+        assert_eq!(iter.bytes_to_wait_for, 4096);
+
+        let stats = iter.stats();
+        assert_eq!(stats.reads, 3);
+        assert_eq!(stats.average_latency(), Duration::from_millis(320) / 3);
+        assert_eq!(ReadStats::default().average_latency(), Duration::ZERO);
+    }
+
+    fn create_mock_records_with_unknown_version() -> Vec<Vec<u8>> {
+        let mut future_record =
+            create_mock_usn_record(0x100, 0x1, 0x5, USN_REASON_FILE_CREATE, "future.txt", 0);
+        future_record[offset_of!(USN_RECORD_V2, MajorVersion)..][..2]
+            .copy_from_slice(&9u16.to_le_bytes());
+        vec![
+            future_record,
+            create_mock_usn_record(0x200, 0x2, 0x5, USN_REASON_FILE_CREATE, "valid.txt", 0),
+        ]
+    }
+
+    /// Builds a `USN_RECORD_V4` with one extent, as written when range tracking is enabled.
+    fn create_mock_range_record(usn: i64, fid: u64) -> Vec<u8> {
+        let len = size_of::<USN_RECORD_V4>();
+        let mut record = vec![0u8; len];
+        record[offset_of!(USN_RECORD_V4, Header)..][..4]
+            .copy_from_slice(&(len as u32).to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, Header) + 4..][..2].copy_from_slice(&4u16.to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, FileReferenceNumber)..][..8]
+            .copy_from_slice(&fid.to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, Usn)..][..8].copy_from_slice(&usn.to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, Reason)..][..4]
+            .copy_from_slice(&USN_REASON_DATA_OVERWRITE.to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, NumberOfExtents)..][..2]
+            .copy_from_slice(&1u16.to_le_bytes());
+        record[offset_of!(USN_RECORD_V4, ExtentSize)..][..2].copy_from_slice(&16u16.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn test_range_records_are_skipped() {
+        let records = vec![
+            create_mock_usn_record(0x100, 0x1, 0x5, USN_REASON_FILE_CREATE, "first.txt", 0),
+            create_mock_range_record(0x180, 0x1),
+            create_mock_usn_record(0x200, 0x1, 0x5, USN_REASON_DATA_OVERWRITE, "first.txt", 0),
+        ];
+        let buffer = create_mock_read_buffer(0x300, &records);
+
+        // The iterator would read the (mock) volume once the buffer is used up.
+        let usns = create_mock_journal_iter(buffer.clone())
+            .take(2)
+            .map(|entry| entry.unwrap().usn)
+            .collect::<Vec<_>>();
+        assert_eq!(usns, vec![0x100, 0x200]);
+
+        let replayed = replay(&buffer)
+            .map(|entry| entry.unwrap().usn)
+            .collect::<Vec<_>>();
+        assert_eq!(replayed, vec![0x100, 0x200]);
+        assert!(matches!(
+            UsnEntry::new(&records[1]),
+            Err(UsnError::UnsupportedRecordVersion(4))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_record_version_errors_then_continues() {
+        let records = create_mock_records_with_unknown_version();
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+
+        assert!(matches!(
+            iter.next(),
+            Some(Err(UsnError::UnsupportedRecordVersion(9)))
+        ));
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.usn, 0x200);
+        assert_eq!(entry.file_name, "valid.txt");
+    }
+
+    #[test]
+    fn test_unknown_record_version_is_skipped_silently() {
+        let records = create_mock_records_with_unknown_version();
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+        iter.unknown_version_policy = UnknownVersionPolicy::Skip;
+
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.usn, 0x200);
+        assert_eq!(entry.file_name, "valid.txt");
+    }
+
+    #[test]
+    fn test_time_range_filters_records() {
+        // FILETIME of the Unix epoch, plus one second per step.
+        let filetime = |seconds: i64| 116_444_736_000_000_000 + seconds * 10_000_000;
+        let at = |seconds: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        let records = [0, 10, 20, 5, 30, 40, 25]
+            .into_iter()
+            .enumerate()
+            .map(|(i, seconds)| {
+                let mut record = create_mock_usn_record(
+                    0x100 * (i as i64 + 1),
+                    0x10 + i as u64,
+                    0x5,
+                    USN_REASON_DATA_EXTEND,
+                    "file.txt",
+                    0,
+                );
+                // TimeStamp follows RecordLength, the versions, both file references and Usn.
+                record[32..40].copy_from_slice(&filetime(seconds).to_le_bytes());
+                record
+            })
+            .collect::<Vec<_>>();
+        let buffer = create_mock_read_buffer(0x800, &records);
+
+        // The out-of-order record at 5s is dropped; the one at 25s after the end is kept.
+        let mut iter = create_mock_journal_iter(buffer.clone());
+        iter.start_time = Some(at(10));
+        iter.end_time = Some(at(30));
+        let times = iter
+            .by_ref()
+            .take(4)
+            .map(|entry| entry.unwrap().time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, vec![at(10), at(20), at(30), at(25)]);
+
+        let mut iter = create_mock_journal_iter(buffer);
+        iter.start_time = Some(at(10));
+        iter.end_time = Some(at(30));
+        iter.stop_at_end_time = true;
+        let usns = iter.map(|entry| entry.unwrap().usn).collect::<Vec<_>>();
+        assert_eq!(usns, vec![0x200, 0x300, 0x500]);
+    }
+
+    #[test]
+    fn test_extension_filter_skips_other_names() {
+        let records = ["a.txt", "b.log", "c.txt.bak", "D.Txt"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                create_mock_usn_record(
+                    0x100 * (i as i64 + 1),
+                    0x10 + i as u64,
+                    0x5,
+                    USN_REASON_FILE_CREATE,
+                    name,
+                    0,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x500, &records));
+        iter.extensions = ExtensionFilter::new(&[".txt".to_string()]);
+
+        let names = iter
+            .by_ref()
+            .take(2)
+            .map(|entry| entry.unwrap().file_name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a.txt", "D.Txt"]);
+        assert_eq!(iter.current_record_bytes().len(), records[3].len());
+    }
+
+    #[test]
+    fn test_tally_reasons_counts_each_flag_in_window() {
+        let records = vec![
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "a.txt", 0),
+            create_mock_usn_record(
+                0x200,
+                0x10,
+                0x5,
+                USN_REASON_DATA_EXTEND | USN_REASON_CLOSE,
+                "a.txt",
+                0,
+            ),
+            create_mock_usn_record(
+                0x300,
+                0x10,
+                0x5,
+                USN_REASON_FILE_DELETE | USN_REASON_CLOSE,
+                "a.txt",
+                0,
+            ),
+            create_mock_usn_record(0x400, 0x11, 0x5, USN_REASON_FILE_CREATE, "b.txt", 0),
+        ];
+        let iter = create_mock_journal_iter(create_mock_read_buffer(0x500, &records));
+
+        let histogram = tally_reasons(iter, 0x400).unwrap();
+
+        assert_eq!(
+            histogram,
+            HashMap::from([
+                ("FILE_CREATE", 1),
+                ("DATA_EXTEND", 1),
+                ("FILE_DELETE", 1),
+                ("CLOSE", 2),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_count_distinct_files_ignores_sequence_numbers() {
+        const REUSED: u64 = 0x0002_0000_0000_0010;
+        let records = vec![
+            create_mock_usn_record(0x100, 0x10, 0x5, USN_REASON_FILE_CREATE, "a.txt", 0),
+            create_mock_usn_record(0x200, 0x10, 0x5, USN_REASON_DATA_EXTEND, "a.txt", 0),
+            create_mock_usn_record(0x300, 0x11, 0x5, USN_REASON_FILE_CREATE, "b.txt", 0),
+            create_mock_usn_record(0x400, REUSED, 0x5, USN_REASON_FILE_CREATE, "c.txt", 0),
+            create_mock_usn_record(0x500, 0x12, 0x5, USN_REASON_FILE_CREATE, "d.txt", 0),
+        ];
+        let iter = create_mock_journal_iter(create_mock_read_buffer(0x600, &records));
+
+        assert_eq!(count_distinct_files(iter, 0x500).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_current_record_bytes_match_yielded_record() {
+        let records = vec![
+            create_mock_usn_record(0x100, 0x1, 0x5, USN_REASON_FILE_CREATE, "first.txt", 0),
+            create_mock_usn_record(0x200, 0x2, 0x5, USN_REASON_FILE_CREATE, "second.txt", 0),
+        ];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x300, &records));
+        assert!(iter.current_record_bytes().is_empty());
+
+        for record in &records {
+            assert!(iter.next().unwrap().is_ok());
+            assert_eq!(iter.current_record_bytes(), record.as_slice());
+        }
+
+        // The buffer is exhausted, so the next call hits the (invalid) mock volume handle.
+        assert!(matches!(iter.next(), Some(Err(_))));
+        assert!(iter.current_record_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_journal_iter_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Volume>();
+        assert_send::<UsnJournalIter>();
+        assert_send::<InitialScanIter>();
+    }
+
+    #[test]
+    fn test_journal_iter_can_move_to_worker_thread() {
+        let records = vec![create_mock_usn_record(
+            0x100,
+            0x1,
+            0x5,
+            USN_REASON_FILE_CREATE,
+            "a.txt",
+            0,
+        )];
+        let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &records));
+
+        let usn = std::thread::spawn(move || iter.next().unwrap().unwrap().usn)
+            .join()
+            .unwrap();
+
+        assert_eq!(usn, 0x100);
+    }
+
+    #[test]
+    fn test_cursor_detects_journal_id_mismatch() {
+        let journal_data = UsnJournalData::from(create_mock_usn_journal_data());
+        let records = vec![create_mock_usn_record(
+            0x100,
+            0x1,
+            0x5,
+            USN_REASON_FILE_CREATE,
+            "a.txt",
+            0,
+        )];
+        let iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &records));
+
+        let cursor = iter.cursor();
+        assert_eq!(cursor.journal_id, journal_data.journal_id);
+        assert_eq!(cursor.usn, 0x100);
+        assert!(cursor.ensure_matches(&journal_data).is_ok());
+
+        let recreated = UsnJournalData {
+            journal_id: 0x42,
+            ..journal_data
+        };
+        assert!(matches!(
+            cursor.ensure_matches(&recreated),
+            Err(UsnError::JournalIdChanged {
+                expected: 0x123456789ABCDEF0,
+                actual: 0x42
+            })
+        ));
+    }
+
+    #[test]
+    fn test_cursor_rejects_other_volume() {
+        let cursor = Cursor {
+            volume_serial: 0x1A2B_3C4D,
+            journal_id: 0x123456789ABCDEF0,
+            usn: 0x100,
+        };
+        assert!(cursor.ensure_volume(0x1A2B_3C4D).is_ok());
+        assert!(matches!(
+            cursor.ensure_volume(0xBEEF),
+            Err(UsnError::VolumeMismatch {
+                expected: 0x1A2B_3C4D,
+                actual: 0xBEEF
+            })
+        ));
+
+        let unknown = Cursor {
+            volume_serial: 0,
+            ..cursor
+        };
+        assert!(unknown.ensure_volume(0xBEEF).is_ok());
+    }
+
+    #[test]
+    fn test_cursor_token_round_trips() {
+        let cursors = [
+            Cursor {
+                volume_serial: 0x1A2B_3C4D,
+                journal_id: 0x123456789ABCDEF0,
+                usn: 0x100,
+            },
+            Cursor {
+                volume_serial: 0,
+                journal_id: u64::MAX,
+                usn: Usn::MAX,
+            },
+        ];
+        for cursor in cursors {
+            let token = cursor.to_token();
+            assert!(
+                token
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+            );
+            assert_eq!(Cursor::from_token(&token).unwrap(), cursor);
+        }
+
+        let iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[]));
+        let cursor = iter.cursor();
+        assert_eq!(cursor.volume_serial, 0x1A2B_3C4D);
+        assert_eq!(Cursor::from_token(&cursor.to_token()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn test_cursor_token_rejects_tampering() {
+        let token = Cursor {
+            volume_serial: 0x1A2B_3C4D,
+            journal_id: 0x123456789ABCDEF0,
+            usn: 0x100,
+        }
+        .to_token();
+
+        // Flip one character in the middle of the payload.
+        let mut tampered = token.clone().into_bytes();
+        tampered[10] = if tampered[10] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+
+        let mut wrong_version = token::decode_base64(&token).unwrap();
+        wrong_version[0] = 2;
+        let wrong_version = token::encode_base64(&wrong_version);
+
+        for (bad, reason) in [
+            (tampered.as_str(), "checksum mismatch"),
+            (&token[..token.len() - 4], "wrong length"),
+            ("not a token!", "not base64"),
+            (wrong_version.as_str(), "unsupported version 2"),
+        ] {
+            assert!(
+                matches!(Cursor::from_token(bad), Err(UsnError::InvalidCursorToken(r)) if r == reason),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resume_from_next_usn_has_no_gaps_or_duplicates() {
+        let journal: Vec<(Usn, Vec<u8>)> = (1..=5)
+            .map(|i| {
+                let usn = i * 0x100;
+                let name = format!("file{i}.txt");
+                let record = create_mock_usn_record(usn, i as u64, 0x5, USN_REASON_CLOSE, &name, 0);
+                (usn, record)
+            })
+            .collect();
+
+        // Mimics FSCTL_READ_USN_JOURNAL: return every record at or after `start_usn`.
+        let read_from = |start_usn: Usn| {
+            let records = journal
+                .iter()
+                .filter(|(usn, _)| *usn >= start_usn)
+                .map(|(_, record)| record.clone())
+                .collect::<Vec<_>>();
+            (create_mock_read_buffer(0x600, &records), records.len())
+        };
+
+        let (buffer, _) = read_from(0);
+        let mut first_tail = create_mock_journal_iter(buffer);
+        let mut seen = first_tail
+            .by_ref()
+            .take(2)
+            .map(|entry| entry.unwrap().usn)
+            .collect::<Vec<_>>();
+        let cursor = first_tail.next_usn();
+        drop(first_tail);
+
+        let (buffer, remaining) = read_from(cursor);
+        let resumed_tail = create_mock_journal_iter(buffer);
+        seen.extend(resumed_tail.take(remaining).map(|entry| entry.unwrap().usn));
+
+        assert_eq!(seen, vec![0x100, 0x200, 0x300, 0x400, 0x500]);
+    }
+
+    #[test]
+    fn test_ensure_active_creates_only_missing_journal() {
+        let created = std::cell::Cell::new(0);
+        let create = || {
+            created.set(created.get() + 1);
+            Ok(())
+        };
+
+        let journal_data =
+            ensure_active_with(|| Ok(create_mock_usn_journal_data()), create).unwrap();
+        assert_eq!(journal_data.journal_id, 0x123456789ABCDEF0);
+        assert_eq!(created.get(), 0);
+
+        let journal_data = ensure_active_with(
+            || {
+                if created.get() == 0 {
+                    Err(windows::core::Error::from(ERROR_JOURNAL_NOT_ACTIVE))
+                } else {
+                    Ok(create_mock_usn_journal_data())
+                }
+            },
+            create,
+        )
+        .unwrap();
+        assert_eq!(journal_data.journal_id, 0x123456789ABCDEF0);
+        assert_eq!(created.get(), 1);
+    }
+
+    #[test]
+    fn test_journal_error_maps_journal_states() {
+        use windows::core::Error;
+
+        assert!(matches!(
+            journal_error(Error::from(ERROR_JOURNAL_NOT_ACTIVE)),
+            UsnError::JournalNotActive
+        ));
+        assert!(matches!(
+            journal_error(Error::from(ERROR_JOURNAL_DELETE_IN_PROGRESS)),
+            UsnError::JournalDeleted
+        ));
+        assert!(matches!(
+            journal_error(Error::from(ERROR_ACCESS_DENIED)),
+            UsnError::WinApiError(err) if err.code() == ERROR_ACCESS_DENIED.into()
+        ));
+    }
+
+    #[test]
+    fn test_validate_journal_sizes_rejects_zero_allocation_delta() {
+        assert!(matches!(
+            validate_journal_sizes(DEFAULT_JOURNAL_MAX_SIZE, 0),
+            Err(UsnError::OtherError(message)) if message.contains("must not be zero")
+        ));
+    }
+
+    #[test]
+    fn test_validate_journal_sizes_rejects_delta_above_max_size() {
+        assert!(matches!(
+            validate_journal_sizes(1024 * 1024, 8 * 1024 * 1024),
+            Err(UsnError::OtherError(message))
+                if message.contains("smaller than its allocation delta")
+        ));
+    }
+
+    #[test]
+    fn test_validate_journal_sizes_rejects_sizes_that_cannot_be_aligned() {
+        assert!(matches!(
+            validate_journal_sizes(u64::MAX, 4096),
+            Err(UsnError::OtherError(message)) if message.contains("too large")
+        ));
+    }
+
+    #[test]
+    fn test_validate_journal_sizes_aligns_and_passes_valid_sizes() {
+        assert_eq!(
+            validate_journal_sizes(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)
+                .unwrap(),
+            (DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)
+        );
+        assert_eq!(
+            validate_journal_sizes(1_000_000, 100_000).unwrap(),
+            (1_003_520, 102_400)
+        );
+    }
+
+    #[test]
+    fn test_optional_journal_data_maps_inactive_journal_to_none() {
+        use windows::core::Error;
+
+        let journal_data = optional_journal_data(Ok(create_mock_usn_journal_data())).unwrap();
+        assert_eq!(journal_data.unwrap().journal_id, 0x123456789ABCDEF0);
+        assert!(
+            optional_journal_data(Err(Error::from(ERROR_JOURNAL_NOT_ACTIVE)))
+                .unwrap()
+                .is_none()
+        );
+        assert!(matches!(
+            optional_journal_data(Err(Error::from(ERROR_JOURNAL_DELETE_IN_PROGRESS))),
+            Err(UsnError::JournalDeleted)
+        ));
+        assert!(matches!(
+            optional_journal_data(Err(Error::from(ERROR_ACCESS_DENIED))),
+            Err(UsnError::WinApiError(err)) if err.code() == ERROR_ACCESS_DENIED.into()
+        ));
+    }
+
+    #[test]
+    fn test_ensure_active_passes_other_errors_through() {
+        let result = ensure_active_with(
+            || Err(windows::core::Error::from(ERROR_ACCESS_DENIED)),
+            || panic!("must not create the journal after an unrelated error"),
+        );
+
+        assert!(
+            matches!(result, Err(UsnError::WinApiError(err)) if err.code() == ERROR_ACCESS_DENIED.into())
+        );
+    }
+
+    #[test]
+    fn test_replay_parses_captured_buffer() {
+        let records = vec![
+            create_mock_usn_record(0x100, 0x10, 0x5, 0x100, "created.txt", 0x20),
+            create_mock_usn_record_v3(0x200, FileId(0x11), FileId(0x5), 0x2, "extended.txt", 0x20),
+            create_mock_usn_record(0x300, 0x12, 0x5, 0x200, "gone", 0x10),
+        ];
+        let captured = create_mock_read_buffer(0x400, &records);
+
+        let replayed = replay(&captured);
+        assert_eq!(replayed.next_usn(), Some(0x400));
+        let entries = replayed.collect::<UsnResult<Vec<_>>>().unwrap();
+
+        let expected = records
+            .iter()
+            .map(|record| UsnEntry::new(record).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), expected.len());
+        for (entry, expected) in entries.iter().zip(&expected) {
+            assert_eq!(entry.usn, expected.usn);
+            assert_eq!(entry.fid, expected.fid);
+            assert_eq!(entry.reason, expected.reason);
+            assert_eq!(entry.file_name, expected.file_name);
+        }
+        assert!(entries[2].is_dir());
+
+        // A truncated capture reports the broken record once, then stops.
+        let truncated = &captured[..captured.len() - 4];
+        let results = replay(truncated).collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+        assert!(replay(&[0u8; 4]).next().unwrap().is_err());
+    }
+
+    mod mocked_tests {
+        use super::*;
+        use injectorpp::interface::injector::*;
+        use windows::Win32::Storage::FileSystem::GetVolumeInformationByHandleW;
+
+        /// Answers a mocked `GetVolumeInformationByHandleW` with one serial number for the
+        /// mock volume's null handle and another for any file.
+        fn write_mock_serial(handle: HANDLE, serial_number: Option<*mut u32>) {
+            if let Some(serial_number) = serial_number {
+                let serial = if handle.0.is_null() {
+                    0x1111_1111
+                } else {
+                    0x2222_2222
+                };
+                unsafe { *serial_number = serial };
+            }
+        }
+
+        // Control codes seen by a mocked `DeviceIoControl` that records them.
+        static RECEIVED_CONTROL_CODES: std::sync::Mutex<Vec<u32>> =
+            std::sync::Mutex::new(Vec::new());
+
+        fn record_control_code(control_code: u32) {
+            RECEIVED_CONTROL_CODES.lock().unwrap().push(control_code);
+        }
+
+        fn mock_volume_serials(injector: &mut InjectorPP) {
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (GetVolumeInformationByHandleW)(
+                        HANDLE,
+                        Option<&mut [u16]>,
+                        Option<*mut u32>,
+                        Option<*mut u32>,
+                        Option<*mut u32>,
+                        Option<&mut [u16]>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _name: Option<&mut [u16]>,
+                        _serial_number: Option<*mut u32>,
+                        _max_component_length: Option<*mut u32>,
+                        _flags: Option<*mut u32>,
+                        _file_system_name: Option<&mut [u16]>
+                    ) -> windows::core::Result<()>,
+                    assign: { write_mock_serial(_handle, _serial_number) },
+                    returns: Ok(())
+                ));
+        }
+
+        #[test]
+        fn test_read_file_record_rejects_file_on_another_volume() {
+            let mut injector = InjectorPP::new();
+            mock_volume_serials(&mut injector);
+
+            let path = std::env::temp_dir().join("usn-journal-rs-read-file-record.txt");
+            std::fs::write(&path, b"other volume").unwrap();
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let result = UsnJournal::new(&volume).read_file_record(&path);
+            let _ = std::fs::remove_file(&path);
+
+            assert!(matches!(
+                result,
+                Err(UsnError::VolumeMismatch {
+                    expected: 0x1111_1111,
+                    actual: 0x2222_2222
+                })
+            ));
+        }
+
+        #[test]
+        fn test_write_close_record_rejects_file_on_another_volume() {
+            let mut injector = InjectorPP::new();
+            mock_volume_serials(&mut injector);
+
+            let path = std::env::temp_dir().join("usn-journal-rs-write-close-record.txt");
+            let file = std::fs::File::create(&path).unwrap();
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let result = UsnJournal::new(&volume).write_close_record(&file);
+            drop(file);
+            let _ = std::fs::remove_file(&path);
+
+            assert!(matches!(
+                result,
+                Err(UsnError::VolumeMismatch {
+                    expected: 0x1111_1111,
+                    actual: 0x2222_2222
+                })
+            ));
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_query_reports_inactive_journal_without_creating_it() {
+            let mut injector = InjectorPP::new();
+            RECEIVED_CONTROL_CODES.lock().unwrap().clear();
+
+            // Every FSCTL fails as if the volume had no journal, so a creating query would
+            // also fail; the error must be the one from the query itself.
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    assign: { record_control_code(_control_code) },
+                    returns: Err(windows::core::Error::from(ERROR_JOURNAL_NOT_ACTIVE))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let result = UsnJournal::new(&volume).query();
+
+            assert!(matches!(result, Err(UsnError::JournalNotActive)));
+            let received = RECEIVED_CONTROL_CODES.lock().unwrap().clone();
+            assert_eq!(received, vec![FSCTL_QUERY_USN_JOURNAL]);
+            assert!(!received.contains(&FSCTL_CREATE_USN_JOURNAL));
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_refill_reads_without_querying_journal() {
+            let mut injector = InjectorPP::new();
+            RECEIVED_CONTROL_CODES.lock().unwrap().clear();
+
+            // The refill reaches the end of the journal straight away.
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    assign: { record_control_code(_control_code) },
+                    returns: Err(windows::core::Error::from(ERROR_HANDLE_EOF))
+                ));
+
+            let mut iter = create_mock_journal_iter(create_mock_read_buffer(0x200, &[]));
+            assert!(iter.next().is_none());
+
+            let received = RECEIVED_CONTROL_CODES.lock().unwrap().clone();
+            assert_eq!(received, vec![FSCTL_READ_USN_JOURNAL]);
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_try_query_returns_none_for_inactive_journal() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to report that the volume has no journal
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Err(windows::core::Error::from(ERROR_JOURNAL_NOT_ACTIVE))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let result = UsnJournal::new(&volume).try_query();
+
+            assert!(matches!(result, Ok(None)));
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_query_reports_journal_being_deleted() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to report a journal deletion in progress
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Err(windows::core::Error::from(ERROR_JOURNAL_DELETE_IN_PROGRESS))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let result = UsnJournal::new(&volume).query();
+
+            assert!(matches!(result, Err(UsnError::JournalDeleted)));
+        }
+    }
+
+    // Integration tests that require actual filesystem access
+    mod integration_tests {
+        use super::*;
+        use crate::adapters::DirectoryEvent;
+        use std::io::Write;
+
+        #[test]
+        fn test_try_query_returns_active_journal() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let active = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?;
+
+            let queried = journal.try_query()?.expect("journal should be active");
+            assert_eq!(queried.journal_id, active.journal_id);
+            Ok(())
+        }
+
+        #[test]
+        fn test_watch_directory_shallow_reports_immediate_children() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let dir = std::env::temp_dir().join("usn_journal_rs_watch_shallow");
+            let subdir = dir.join("nested");
+            std::fs::create_dir_all(&subdir)?;
+            let dir_fid = FileId::from(path::file_id_from_path(&dir)?);
+            let journal = volume.journal();
+            // Same as `UsnJournal::watch_directory_shallow`, but the iterator ends after ten
+            // seconds instead of blocking the test forever if an expected record never comes.
+            let (mut iter, _canceller) = journal.iter_cancellable(EnumOptions {
+                start_usn: journal.query()?.next_usn,
+                wait_for_more: true,
+                ..Default::default()
+            })?;
+            if let Some(read) = &mut iter.cancellable {
+                read.set_deadline(Instant::now() + Duration::from_secs(10));
+            }
+            let mut events = iter.watch_directory_shallow(dir_fid);
+
+            // Write the nested file first: once the last child shows up, every earlier record
+            // has been seen.
+            std::fs::write(subdir.join("grandchild.txt"), b"nested")?;
+            std::fs::write(dir.join("child.txt"), b"child")?;
+            std::fs::write(dir.join("last_child.txt"), b"last")?;
+
+            let mut names = Vec::new();
+            for event in events.by_ref() {
+                match event? {
+                    DirectoryEvent::Child(entry) => {
+                        let done = entry.file_name == "last_child.txt";
+                        names.push(entry.file_name);
+                        if done {
+                            break;
+                        }
+                    }
+                    other => panic!("watched directory changed unexpectedly: {other:?}"),
+                }
+            }
+            std::fs::remove_dir_all(&dir)?;
+
+            assert!(names.iter().any(|name| name == "child.txt"));
+            assert!(!names.iter().any(|name| name == "grandchild.txt"));
+            Ok(())
+        }
+
+        #[test]
+        fn test_time_span_brackets_new_file() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_time_span.txt");
+            let mut file = std::fs::File::create(&file_path)?;
+            file.write_all(b"time span")?;
+            drop(file);
+            let created = std::fs::metadata(&file_path)?.created()?;
+
+            let (oldest, newest) = volume.journal().time_span()?;
+            std::fs::remove_file(&file_path)?;
+
+            assert!(oldest <= created, "oldest record should predate the file");
+            assert!(newest >= created, "newest record should include the file");
+            Ok(())
+        }
+
+        #[test]
+        fn test_iter_from_resumes_at_saved_usn() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let journal_id = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .journal_id;
+            let start_usn = journal.cursor()?.usn;
+
+            for i in 0..3 {
+                let file_path = std::env::temp_dir().join(format!("usn_journal_rs_resume_{i}.txt"));
+                std::fs::write(&file_path, b"resume")?;
+                std::fs::remove_file(&file_path)?;
+            }
+
+            let mut first_run = journal.iter_from(journal_id, start_usn)?;
+            let read = first_run
+                .by_ref()
+                .take(2)
+                .map(|entry| entry.map(|entry| entry.usn))
+                .collect::<UsnResult<Vec<_>>>()?;
+            let saved_usn = first_run.next_usn();
+            drop(first_run);
+
+            let mut resumed = journal.iter_from(journal_id, saved_usn)?;
+            let next = resumed.next().expect("more entries after the saved USN")?;
+            assert_eq!(next.usn, saved_usn);
+            assert!(read.iter().all(|&usn| usn < next.usn));
+
+            assert!(matches!(
+                journal.iter_from(journal_id ^ 1, saved_usn),
+                Err(UsnError::JournalIdChanged { .. })
+            ));
+            Ok(())
+        }
+
+        // Needs no elevation, so this also runs from a standard user's process.
+        #[test]
+        fn test_unprivileged_read_reports_new_file() -> Result<(), UsnError> {
+            let volume = Volume::from_drive_letter_unprivileged('C')?;
+            let journal = volume.journal();
+            let start_usn = journal.cursor()?.usn;
+
+            let file_name = "usn_journal_rs_unprivileged.txt";
+            let file_path = std::env::temp_dir().join(file_name);
+            std::fs::write(&file_path, b"unprivileged")?;
+            std::fs::remove_file(&file_path)?;
+
+            let options = EnumOptions {
+                start_usn,
+                unprivileged: true,
+                ..Default::default()
+            };
+            let mut found = false;
+            for entry in journal.iter_with_options(options)? {
+                if entry?.file_name == file_name {
+                    found = true;
+                    break;
+                }
+            }
+
+            assert!(found, "unprivileged read should report the new file");
+            Ok(())
+        }
+
+        #[test]
+        fn test_initial_scan_reports_existing_files_before_live_changes() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let existing_path = std::env::temp_dir().join("usn_journal_rs_scan_existing.txt");
+            let live_path = std::env::temp_dir().join("usn_journal_rs_scan_live.txt");
+            std::fs::write(&existing_path, b"existing")?;
+
+            let events = volume
+                .journal()
+                .iter_with_initial_scan(EnumOptions::default())?;
+            // Created after the journal position was captured, before the scan finishes.
+            std::fs::write(&live_path, b"live")?;
+
+            let mut saw_changed = false;
+            let mut existing_seen_before_live = false;
+            let mut live_change_seen = false;
+            for event in events {
+                match event? {
+                    JournalEvent::Existing(entry) => {
+                        assert!(!saw_changed, "existing files must precede live changes");
+                        if entry.file_name == "usn_journal_rs_scan_existing.txt" {
+                            existing_seen_before_live = true;
+                        }
+                    }
+                    JournalEvent::Changed(entry) => {
+                        saw_changed = true;
+                        if entry.file_name == "usn_journal_rs_scan_live.txt" {
+                            live_change_seen = true;
+                        }
+                    }
+                }
+            }
+
+            std::fs::remove_file(&existing_path)?;
+            std::fs::remove_file(&live_path)?;
+
+            assert!(existing_seen_before_live, "pre-existing file not reported");
+            assert!(
+                live_change_seen,
+                "change made during the handoff was missed"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_creations_under_reports_only_watched_directory() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+
+            let watched = std::env::temp_dir().join("usn_journal_rs_watched");
+            let outside = std::env::temp_dir().join("usn_journal_rs_unwatched");
+            // Start from empty directories so every file below is a fresh creation.
+            let _ = std::fs::remove_dir_all(&watched);
+            let _ = std::fs::remove_dir_all(&outside);
+            std::fs::create_dir_all(&watched)?;
+            std::fs::create_dir_all(&outside)?;
+            let watched_fid = {
+                let start_usn = journal
+                    .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                    .next_usn;
+                // Touch the watched directory so its file ID shows up in the journal.
+                std::fs::write(watched.join("usn_journal_rs_probe.txt"), b"probe")?;
+                journal
+                    .iter_with_options(EnumOptions {
+                        start_usn,
+                        ..Default::default()
+                    })?
+                    .filter_map(Result::ok)
+                    .find(|entry| entry.file_name == "usn_journal_rs_probe.txt")
+                    .map(|entry| entry.parent_fid)
+                    .expect("probe file should appear in the journal")
+            };
+
+            let start_usn = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .next_usn;
+            std::fs::write(watched.join("inside.txt"), b"inside")?;
+            std::fs::create_dir_all(watched.join("nested"))?;
+            std::fs::write(watched.join("nested").join("nested.txt"), b"nested")?;
+            std::fs::write(outside.join("outside.txt"), b"outside")?;
+
+            let mut resolver = volume.path_resolver();
+            let names = journal
+                .iter_with_options(EnumOptions {
+                    start_usn,
+                    ..Default::default()
+                })?
+                .creations_under(watched_fid, &mut resolver)?
+                .filter_map(Result::ok)
+                .map(|(entry, _)| entry.file_name)
+                .collect::<Vec<_>>();
+
+            std::fs::remove_dir_all(&watched)?;
+            std::fs::remove_dir_all(&outside)?;
+
+            assert!(names.iter().any(|name| name == "inside.txt"), "{names:?}");
+            assert!(names.iter().any(|name| name == "nested"), "{names:?}");
+            assert!(names.iter().any(|name| name == "nested.txt"), "{names:?}");
+            assert!(!names.iter().any(|name| name == "outside.txt"), "{names:?}");
+            Ok(())
+        }
+
+        #[test]
+        fn test_wait_for_path_returns_change_and_delete() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_wait_for_path.txt");
+            std::fs::write(&file_path, b"before")?;
+
+            let writer_path = file_path.clone();
+            let writer = std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&writer_path)
+                    .and_then(|mut file| file.write_all(b" and after"))
+            });
+
+            let started = Instant::now();
+            let changed = journal.wait_for_path(&file_path, Duration::from_secs(10))?;
+            writer.join().unwrap()?;
+            let changed = changed.expect("modification should be reported");
+            assert!(started.elapsed() < Duration::from_secs(5));
+            assert_eq!(changed.file_name, "usn_journal_rs_wait_for_path.txt");
+            assert_eq!(changed.change_kind(), ChangeKind::Modified);
+
+            let delete_path = file_path.clone();
+            let deleter = std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                std::fs::remove_file(&delete_path)
+            });
+
+            let deleted = journal.wait_for_path(&file_path, Duration::from_secs(10))?;
+            deleter.join().unwrap()?;
+            let deleted = deleted.expect("deletion should be reported");
+            assert_eq!(deleted.change_kind(), ChangeKind::Deleted);
+            Ok(())
+        }
+
+        #[test]
+        fn test_atomic_saves_coalesces_editor_save() -> Result<(), UsnError> {
+            use crate::adapters::SaveEvent;
+
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+
+            let dir = std::env::temp_dir();
+            let target = dir.join("usn_journal_rs_atomic.txt");
+            let temp = dir.join("usn_journal_rs_atomic.txt.tmp");
+            let backup = dir.join("usn_journal_rs_atomic.txt~");
+            std::fs::write(&target, b"original")?;
+
+            let start_usn = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .next_usn;
+            std::fs::write(&temp, b"saved")?;
+            std::fs::rename(&target, &backup)?;
+            std::fs::rename(&temp, &target)?;
+            std::fs::remove_file(&backup)?;
+
+            let events = journal
+                .iter_with_options(EnumOptions {
+                    start_usn,
+                    ..Default::default()
+                })?
+                .atomic_saves(Default::default())
+                .collect::<UsnResult<Vec<_>>>()?;
+            std::fs::remove_file(&target)?;
+
+            let ours = |name: &OsString| {
+                name.to_string_lossy()
+                    .starts_with("usn_journal_rs_atomic.txt")
+            };
+            let saves = events
+                .iter()
+                .filter_map(|event| match event {
+                    SaveEvent::AtomicSave(save) if ours(&save.file_name) => Some(save),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(saves.len(), 1, "events: {events:?}");
+            assert_eq!(saves[0].file_name, "usn_journal_rs_atomic.txt");
+
+            // The temporary and backup copies must not leak out as separate events.
+            let leaked = events
+                .iter()
+                .filter(|event| {
+                    matches!(event, SaveEvent::Entry(entry)
+                        if ours(&entry.file_name) && entry.file_name != "usn_journal_rs_atomic.txt")
+                })
+                .collect::<Vec<_>>();
+            assert!(leaked.is_empty(), "leaked: {leaked:?}");
+            Ok(())
+        }
+
+        #[test]
+        fn test_read_file_record_returns_latest_change() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let before = journal.query()?.next_usn;
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_read_file_record.txt");
+            std::fs::write(&file_path, b"record")?;
+            let fid = path::file_id_from_path(&file_path)?;
+            let entry = journal.read_file_record(&file_path);
+            std::fs::remove_file(&file_path)?;
+            let entry = entry?;
+
+            assert_eq!(entry.fid, FileId::from(fid));
+            assert_eq!(entry.file_name, "usn_journal_rs_read_file_record.txt");
+            assert!(entry.usn >= before);
+            assert!(!entry.is_dir());
+            Ok(())
+        }
+
+        #[test]
+        fn test_write_close_record_is_found_at_returned_usn() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_close_record.txt");
+            let mut file = std::fs::File::create(&file_path)?;
+            file.write_all(b"closing")?;
+            let fid = FileId::from(path::file_id_from_path(&file_path)?);
+            let usn = journal.write_close_record(&file);
+            drop(file);
+            std::fs::remove_file(&file_path)?;
+            let usn = usn?;
+
+            let entry = journal
+                .iter_with_options(EnumOptions {
+                    start_usn: usn,
+                    ..Default::default()
+                })?
+                .next()
+                .expect("the close record should be readable")?;
+            assert_eq!(entry.usn, usn);
+            assert_eq!(entry.fid, fid);
+            assert!(entry.reason.contains(UsnReason::CLOSE));
+            assert!(entry.reason.contains(UsnReason::DATA_EXTEND));
+            Ok(())
+        }
+
+        #[test]
+        fn test_wait_for_path_times_out_without_changes() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_wait_for_path_idle.txt");
+            std::fs::write(&file_path, b"idle")?;
+            let started = Instant::now();
+            let result = volume
+                .journal()
+                .wait_for_path(&file_path, Duration::from_millis(200));
+            let elapsed = started.elapsed();
+            std::fs::remove_file(&file_path)?;
+
+            assert!(result?.is_none());
+            assert!(
+                elapsed < Duration::from_secs(5),
+                "waited {elapsed:?} past the timeout"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_resolve_path_extended_opens_deeply_nested_file() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let start_usn = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .next_usn;
+
+            // Nest well past MAX_PATH; only the extended form can create and open the file.
+            let root = crate::path::to_extended_length_path(
+                &std::env::temp_dir().join("usn_journal_rs_long_path"),
+            );
+            let mut dir = root.clone();
+            for depth in 0..6 {
+                dir.push(format!("{depth}_{}", "d".repeat(60)));
+            }
+            std::fs::create_dir_all(&dir)?;
+            let file_name = "usn_journal_rs_long_path.txt";
+            std::fs::write(dir.join(file_name), b"deep")?;
+
+            let entry = journal
+                .iter_with_options(EnumOptions {
+                    start_usn,
+                    ..Default::default()
+                })?
+                .filter_map(Result::ok)
+                .find(|entry| entry.file_name == file_name);
+
+            let mut resolver = volume.path_resolver();
+            let extended = entry.and_then(|entry| resolver.resolve_path_extended(&entry));
+            let contents = extended.as_ref().map(std::fs::read).transpose();
+            std::fs::remove_dir_all(&root)?;
+
+            let extended = extended.expect("created file should resolve");
+            assert!(extended.as_os_str().len() > 260);
+            assert!(extended.to_string_lossy().starts_with(r"\\?\"));
+            assert_eq!(contents?.as_deref(), Some(&b"deep"[..]));
+            Ok(())
+        }
+
+        #[test]
+        fn test_reason_histogram_counts_file_activity() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let from = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .next_usn;
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_reason_histogram.txt");
+            std::fs::write(&file_path, b"created")?;
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(&file_path)?
+                .write_all(b" and modified")?;
+            std::fs::remove_file(&file_path)?;
+
+            let to = journal.query()?.next_usn;
+            let histogram = journal.reason_histogram(from, to)?;
+
+            for reason in ["FILE_CREATE", "DATA_EXTEND", "FILE_DELETE", "CLOSE"] {
+                assert!(
+                    histogram.get(reason).is_some_and(|&count| count > 0),
+                    "{reason} missing from {histogram:?}"
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_distinct_files_changed_counts_each_file_once() -> Result<(), UsnError> {
+            const FILES: usize = 3;
+
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+
+            let dir = std::env::temp_dir().join("usn_journal_rs_distinct_files");
+            std::fs::create_dir_all(&dir)?;
+            let paths = (0..FILES)
+                .map(|i| dir.join(format!("file{i}.txt")))
+                .collect::<Vec<_>>();
+            for path in &paths {
+                std::fs::write(path, b"created")?;
+            }
+
+            let from = journal.query()?.next_usn;
+            for _ in 0..4 {
+                for path in &paths {
+                    std::fs::OpenOptions::new()
+                        .append(true)
+                        .open(path)?
+                        .write_all(b" and again")?;
+                }
+            }
+            let to = journal.query()?.next_usn;
+            let changed = journal.distinct_files_changed(from, to);
+            let fids = paths
+                .iter()
+                .map(|path| path::file_id_from_path(path).map(FileId::from))
+                .collect::<UsnResult<HashSet<_>>>();
+            std::fs::remove_dir_all(&dir)?;
+            let (changed, fids) = (changed?, fids?);
+
+            // Other processes write to C: too; every other file they touched counts once.
+            let others = journal
+                .iter_with_options(EnumOptions {
+                    start_usn: from,
+                    ..Default::default()
+                })?
+                .map_while(|entry| entry.ok().filter(|entry| entry.usn < to))
+                .filter(|entry| !fids.contains(&entry.fid))
+                .map(|entry| entry.fid.as_u64().map(|fid| fid & RECORD_NUMBER_MASK))
+                .collect::<HashSet<_>>();
+            assert_eq!(changed, FILES + others.len());
+            Ok(())
+        }
+
+        #[test]
+        fn test_read_changes_classifies_and_resolves() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let journal = volume.journal();
+            let start_usn = journal
+                .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?
+                .next_usn;
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_read_changes.txt");
+            std::fs::write(&file_path, b"created")?;
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(&file_path)?
+                .write_all(b" and modified")?;
+            std::fs::remove_file(&file_path)?;
+
+            let mut resolver = volume.path_resolver();
+            let changes = journal.read_changes(
+                EnumOptions {
+                    start_usn,
+                    ..Default::default()
+                },
+                &mut resolver,
+            )?;
+
+            let kinds = changes
+                .iter()
+                .filter(|change| {
+                    change
+                        .path
+                        .as_ref()
+                        .is_some_and(|path| path.ends_with("usn_journal_rs_read_changes.txt"))
+                })
+                .map(|change| change.kind)
+                .collect::<Vec<_>>();
+
+            assert!(kinds.contains(&ChangeKind::Created), "kinds: {kinds:?}");
+            assert!(kinds.contains(&ChangeKind::Modified), "kinds: {kinds:?}");
+            assert!(kinds.contains(&ChangeKind::Deleted), "kinds: {kinds:?}");
+            Ok(())
+        }
+
+        #[test]
+        fn test_measure_read_throughput_reports_rates() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let stats = volume
+                .journal()
+                .measure_read_throughput(EnumOptions::default(), Duration::from_millis(200))?;
+
+            assert!(stats.reads > 0, "should issue at least one read");
+            assert!(stats.records_per_sec >= 0.0);
+            assert!(stats.bytes_per_sec > 0.0);
+            Ok(())
+        }
+    }
 }
@@ -54,26 +54,44 @@
 //!
 //! ## Platform
 //! - Windows NTFS/ReFS volumes
-//! - Requires appropriate privileges to access the USN journal
+//! - Requires appropriate privileges to access the USN journal; use
+//!   [`privilege::current_privileges`] to see which ones the process holds
 //!
 //! ## License
 //! MIT License. See [LICENSE](https://github.com/wangfu91/usn-journal-rs/blob/main/LICENSE).
 
+pub mod adapters;
+pub mod cancel;
 pub mod errors;
+mod file_id;
 pub mod journal;
 pub mod mft;
+#[cfg(feature = "testing")]
+pub mod mock;
 pub mod path;
-mod privilege;
+pub mod privilege;
+pub mod reasons;
+#[cfg(feature = "serde")]
+mod serde_util;
+pub mod sources;
+#[cfg(feature = "tokio")]
+pub mod stream;
+mod token;
+pub mod tree;
 mod usn_record;
 
 // Re-export commonly used types
 pub use errors::UsnError;
+pub use file_id::FileId;
+pub use reasons::UsnReason;
+pub use usn_record::{NameEncodingPolicy, UnknownVersionPolicy};
 
 /// A convenient type alias for Results with UsnError.
 pub type UsnResult<T> = std::result::Result<T, UsnError>;
 
 mod time;
 pub mod volume;
+pub mod watch;
 
 pub type Usn = i64;
 
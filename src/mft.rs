@@ -4,32 +4,161 @@
 //! from the MFT using the Windows FSCTL_ENUM_USN_DATA control code. It manages the buffer and state
 //! required to sequentially retrieve and parse USN records from the volume.
 
-use crate::{DEFAULT_BUFFER_SIZE, Usn, UsnResult, errors::UsnError, usn_record, volume::Volume};
+use crate::{
+    DEFAULT_BUFFER_SIZE, FileId, NameEncodingPolicy, UnknownVersionPolicy, Usn, UsnResult,
+    adapters::FileRecords,
+    errors::{self, UsnError},
+    path::{self, PathIndex, PathResolver, WithPaths},
+    time,
+    tree::DirectoryTree,
+    usn_record::{self, AlignedBuffer, ExtensionFilter},
+    volume::{Volume, VolumeHandle},
+};
+use chrono::{DateTime, Local};
 use log::debug;
-use std::rc::Rc;
-use std::{ffi::OsString, mem::size_of, path::Path};
-use windows::{
-    Win32::{
-        Foundation::{ERROR_HANDLE_EOF, HANDLE},
-        Storage::FileSystem::{
-            FILE_ATTRIBUTE_DIRECTORY, FILE_ATTRIBUTE_HIDDEN, FILE_FLAGS_AND_ATTRIBUTES,
-        },
-        System::{
-            IO::DeviceIoControl,
-            Ioctl::{self},
-        },
+#[cfg(feature = "rayon")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::{HashMap, VecDeque};
+use std::hash::BuildHasher;
+#[cfg(feature = "rayon")]
+use std::num::NonZeroUsize;
+use std::ops::{ControlFlow, Range};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString, c_void},
+    mem::size_of,
+    os::windows::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
+use windows::Win32::{
+    Foundation::{ERROR_HANDLE_EOF, ERROR_INSUFFICIENT_BUFFER, ERROR_NO_MORE_FILES, HANDLE},
+    Storage::FileSystem::{
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_DIRECTORY,
+        FILE_ATTRIBUTE_ENCRYPTED, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_OFFLINE,
+        FILE_ATTRIBUTE_READONLY, FILE_ATTRIBUTE_REPARSE_POINT, FILE_ATTRIBUTE_SPARSE_FILE,
+        FILE_ATTRIBUTE_SYSTEM, FILE_BASIC_INFO, FILE_FLAGS_AND_ATTRIBUTES, FILE_READ_ATTRIBUTES,
+        FILE_STANDARD_INFO, FileBasicInfo, FileStandardInfo, GetFileInformationByHandleEx,
+    },
+    System::{
+        IO::DeviceIoControl,
+        Ioctl::{self},
     },
-    core::Owned,
 };
 
+// Record numbers read by `Mft::estimate_scan_duration` to measure the enumeration rate.
+const SCAN_SAMPLE_RECORDS: u64 = 64 * 1024;
+
+// NTFS stores the volume root directory in MFT record 5.
+pub(crate) const ROOT_RECORD_NUMBER: u64 = 5;
+
+// The low 48 bits of a file reference number hold the MFT record number;
+// the high 16 bits hold the sequence number.
+pub(crate) const RECORD_NUMBER_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+
+/// Returns true if `fid` refers to the NTFS volume root, whatever its sequence number.
+pub(crate) fn is_root_record(fid: FileId) -> bool {
+    fid.as_u64()
+        .is_some_and(|fid| fid & RECORD_NUMBER_MASK == ROOT_RECORD_NUMBER)
+}
+
 /// Represents a single entry in the Master File Table (MFT).
-#[derive(Debug)]
+///
+/// With the `serde` feature, `file_name` is serialized as a UTF-8 string, in which invalid
+/// UTF-16 is replaced with U+FFFD.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MftEntry {
     pub usn: Usn,
-    pub fid: u64,
-    pub parent_fid: u64,
+    pub fid: FileId,
+    pub parent_fid: FileId,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::lossy_os_string"))]
     pub file_name: OsString,
     pub file_attributes: u32,
+    /// Set only when enumerating with [`EnumOptions::with_timestamps`], and left `None` for
+    /// files that could not be opened, e.g. because they were deleted during the scan.
+    pub timestamps: Option<FileTimestamps>,
+    /// Set only when enumerating with [`EnumOptions::with_sizes`], and left `None` for files
+    /// that could not be opened.
+    pub sizes: Option<FileSizes>,
+}
+
+/// Timestamps of a file, as stored in its `$STANDARD_INFORMATION` attribute.
+///
+/// With the `serde` feature, each time is serialized as an RFC 3339 timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileTimestamps {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
+    pub created: SystemTime,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
+    pub last_write: SystemTime,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
+    pub last_access: SystemTime,
+    /// Last change to the file's MFT record, such as a rename or an attribute change.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::rfc3339"))]
+    pub mft_changed: SystemTime,
+}
+
+/// Sizes of a file's unnamed data stream.
+///
+/// Directories report zero for both, as the size of their index is not included. For sparse
+/// and compressed files `allocated_size` counts only the clusters actually allocated, so it
+/// can be far smaller than `file_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileSizes {
+    /// Logical size of the data in bytes, i.e. the end-of-file position.
+    pub file_size: u64,
+    /// Bytes allocated for the data on disk, a whole number of clusters.
+    pub allocated_size: u64,
+}
+
+/// An MFT entry whose file name is borrowed from the enumeration buffer.
+///
+/// Passed to the callback of [`Mft::for_each`] and only valid for the duration of that call.
+/// Use [`BorrowedMftEntry::to_entry`] to keep an entry beyond it.
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedMftEntry<'a> {
+    pub usn: Usn,
+    pub fid: FileId,
+    pub parent_fid: FileId,
+    pub file_attributes: u32,
+    // Raw UTF-16LE bytes; the buffer offers no alignment guarantee for `[u16]`.
+    file_name: &'a [u8],
+}
+
+impl BorrowedMftEntry<'_> {
+    /// Returns the UTF-16 code units of the file name.
+    pub fn file_name_utf16(&self) -> impl Iterator<Item = u16> + '_ {
+        self.file_name
+            .chunks_exact(size_of::<u16>())
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+    }
+
+    /// Returns true if the file name equals `name`, without allocating.
+    pub fn file_name_eq(&self, name: &str) -> bool {
+        self.file_name_utf16().eq(name.encode_utf16())
+    }
+
+    /// Returns true if this entry represents a directory.
+    pub fn is_dir(&self) -> bool {
+        FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes).contains(FILE_ATTRIBUTE_DIRECTORY)
+    }
+
+    /// Copies the entry, including its file name, into an owned [`MftEntry`].
+    pub fn to_entry(&self) -> MftEntry {
+        MftEntry {
+            usn: self.usn,
+            fid: self.fid,
+            parent_fid: self.parent_fid,
+            file_name: OsString::from_wide(&self.file_name_utf16().collect::<Vec<_>>()),
+            file_attributes: self.file_attributes,
+            timestamps: None,
+            sizes: None,
+        }
+    }
 }
 
 impl MftEntry {
@@ -39,12 +168,65 @@ impl MftEntry {
         attributes.contains(FILE_ATTRIBUTE_DIRECTORY)
     }
 
+    /// Returns the file name for display, handling invalid UTF-16 according to `policy`.
+    ///
+    /// `file_name` always keeps the raw name; see [`NameEncodingPolicy`] for the options.
+    pub fn display_name(&self, policy: NameEncodingPolicy) -> Option<Cow<'_, OsStr>> {
+        policy.apply(&self.file_name)
+    }
+
     /// Returns true if this entry represents a hidden file or directory.
     pub fn is_hidden(&self) -> bool {
         let attributes = FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes);
         attributes.contains(FILE_ATTRIBUTE_HIDDEN)
     }
 
+    /// Returns true if this entry represents a read-only file or directory.
+    pub fn is_readonly(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_READONLY)
+    }
+
+    /// Returns true if this entry represents a file or directory the operating system uses.
+    pub fn is_system(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_SYSTEM)
+    }
+
+    /// Returns true if this entry represents a file or directory marked for backup or removal.
+    pub fn is_archive(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_ARCHIVE)
+    }
+
+    /// Returns true if this entry represents a reparse point, such as a symbolic link or junction.
+    pub fn is_reparse_point(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_REPARSE_POINT)
+    }
+
+    /// Returns true if this entry represents a compressed file or directory.
+    pub fn is_compressed(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_COMPRESSED)
+    }
+
+    /// Returns true if this entry represents an encrypted file or directory.
+    pub fn is_encrypted(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_ENCRYPTED)
+    }
+
+    /// Returns true if this entry represents a sparse file.
+    pub fn is_sparse(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_SPARSE_FILE)
+    }
+
+    /// Returns true if this entry represents a file whose data has been moved to offline storage.
+    pub fn is_offline(&self) -> bool {
+        self.attributes().contains(FILE_ATTRIBUTE_OFFLINE)
+    }
+
+    /// Returns the entry's attributes as `FILE_FLAGS_AND_ATTRIBUTES`, for checking flags
+    /// that have no predicate of their own.
+    pub fn attributes(&self) -> FILE_FLAGS_AND_ATTRIBUTES {
+        FILE_FLAGS_AND_ATTRIBUTES(self.file_attributes)
+    }
+
     pub fn pretty_format<P>(&self, full_path_opt: Option<P>) -> String
     where
         P: AsRef<Path>,
@@ -60,6 +242,22 @@ impl MftEntry {
             "Type",
             if self.is_dir() { "Directory" } else { "File" }
         ));
+        if let Some(timestamps) = &self.timestamps {
+            for (label, time) in [
+                ("Created", timestamps.created),
+                ("Modified", timestamps.last_write),
+            ] {
+                let dt_local: DateTime<Local> = DateTime::from(time);
+                output.push_str(&format!(
+                    "{:<20}: {}\n",
+                    label,
+                    dt_local.format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+        }
+        if let Some(sizes) = &self.sizes {
+            output.push_str(&format!("{:<20}: {} bytes\n", "Size", sizes.file_size));
+        }
         if let Some(full_path) = full_path_opt {
             output.push_str(&format!(
                 "{:<20}: {}\n",
@@ -86,6 +284,25 @@ pub struct EnumOptions {
     pub low_usn: Usn,
     pub high_usn: Usn,
     pub buffer_size: usize,
+    pub unknown_version_policy: UnknownVersionPolicy,
+    /// Fill in [`MftEntry::timestamps`]. This opens every enumerated file, which is far
+    /// slower than the enumeration itself, so it is off by default.
+    pub with_timestamps: bool,
+    /// Fill in [`MftEntry::sizes`]. Like `with_timestamps`, this opens every enumerated
+    /// file, so it is off by default.
+    pub with_sizes: bool,
+    /// Only report entries whose file name ends with one of these extensions, such as `txt`
+    /// or `.log`, ignoring ASCII case. Names are matched before they are decoded, so skipped
+    /// records cost no allocation. Directories are filtered like files. Empty, the default,
+    /// reports every entry.
+    pub extensions: Vec<String>,
+    /// File reference number to start enumerating at. Only its MFT record number (the low
+    /// 48 bits) is significant. Defaults to 0, the start of the MFT.
+    pub start_fid: u64,
+    /// MFT record number at which to stop; records from this one on are not reported.
+    /// Defaults to `u64::MAX`, the end of the MFT. Together with `start_fid` this selects
+    /// the window `[start_fid, end_fid)` that [`Mft::par_iter`] gives each shard.
+    pub end_fid: u64,
 }
 
 impl Default for EnumOptions {
@@ -94,6 +311,12 @@ impl Default for EnumOptions {
             low_usn: 0,
             high_usn: i64::MAX,
             buffer_size: DEFAULT_BUFFER_SIZE,
+            unknown_version_policy: UnknownVersionPolicy::default(),
+            with_timestamps: false,
+            with_sizes: false,
+            extensions: Vec::new(),
+            start_fid: 0,
+            end_fid: u64::MAX,
         }
     }
 }
@@ -119,10 +342,18 @@ impl<'a> Mft<'a> {
             handle: self.volume.shared_handle(),
             low_usn: 0,
             high_usn: i64::MAX,
-            buffer: vec![0u8; DEFAULT_BUFFER_SIZE],
+            buffer: AlignedBuffer::new(DEFAULT_BUFFER_SIZE),
             bytes_read: 0,
             offset: 0,
             next_start_fid: 0,
+            end_record: u64::MAX,
+            finished_cleanly: false,
+            corrupted: false,
+            unknown_version_policy: UnknownVersionPolicy::default(),
+            with_timestamps: false,
+            with_sizes: false,
+            extensions: ExtensionFilter::default(),
+            current_record: 0..0,
         }
     }
 
@@ -131,43 +362,353 @@ impl<'a> Mft<'a> {
     /// The iterator yields `Result<MftEntry, UsnError>` items, allowing callers
     /// to handle individual entry errors gracefully without stopping iteration.
     pub fn iter_with_options(&self, options: EnumOptions) -> MftIter {
-        MftIter {
-            handle: self.volume.shared_handle(),
-            low_usn: options.low_usn,
-            high_usn: options.high_usn,
-            buffer: vec![0u8; options.buffer_size],
-            bytes_read: 0,
-            offset: 0,
-            next_start_fid: 0,
+        MftIter::with_options(self.volume.shared_handle(), options)
+    }
+
+    /// Enumerates the MFT on `num_shards` threads, each reading its own range of records.
+    ///
+    /// The record numbers are split into equal windows using the MFT size reported by
+    /// `FSCTL_GET_NTFS_VOLUME_DATA`, and each shard reads its window through its own volume
+    /// handle, since reads on one handle are serialized. Every record falls into exactly one
+    /// window, so nothing is dropped or reported twice at the shard edges. Collecting the
+    /// returned iterator into a `Vec` keeps the serial, record-number order.
+    ///
+    /// On volumes that do not report an MFT size, such as ReFS, a single shard reads
+    /// everything.
+    ///
+    /// # Errors
+    /// Returns an error if an additional handle to the volume cannot be opened; errors while
+    /// enumerating are yielded by the iterator.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(
+        &self,
+        num_shards: NonZeroUsize,
+    ) -> UsnResult<impl ParallelIterator<Item = UsnResult<MftEntry>> + use<>> {
+        let shards = match mft_record_count(self.volume.handle()) {
+            Ok(record_count) => shard_ranges(record_count, num_shards.get()),
+            Err(err) => {
+                debug!("MFT size unknown, enumerating in one shard: {err}");
+                shard_ranges(0, 1)
+            }
+        };
+
+        let mut iters = Vec::with_capacity(shards.len());
+        for (index, records) in shards.into_iter().enumerate() {
+            let handle = if index == 0 {
+                self.volume.shared_handle()
+            } else {
+                self.volume.reopen()?.shared_handle()
+            };
+            let options = EnumOptions {
+                start_fid: records.start,
+                end_fid: records.end,
+                ..Default::default()
+            };
+            iters.push(MftIter::with_options(handle, options));
+        }
+
+        Ok(iters.into_par_iter().flat_map_iter(|iter| iter))
+    }
+
+    /// Returns the number of records the MFT holds, as reported by
+    /// `FSCTL_GET_NTFS_VOLUME_DATA`.
+    ///
+    /// This counts record slots, including unused ones, so it is an upper bound on the number
+    /// of entries a full enumeration yields.
+    ///
+    /// # Errors
+    /// Returns an error on volumes that do not report an MFT size, such as ReFS.
+    pub fn estimate_entry_count(&self) -> UsnResult<u64> {
+        mft_record_count(self.volume.handle())
+    }
+
+    /// Estimates how long enumerating the whole MFT will take.
+    ///
+    /// Times the enumeration of the first 65,536 records and extrapolates to the size reported
+    /// by [`Mft::estimate_entry_count`]. This is a rough estimate: the records at the start of
+    /// the MFT are not always representative of the rest, the sample may be served from a cold
+    /// or a warm cache, and options such as `with_sizes` make a real scan slower.
+    ///
+    /// # Errors
+    /// Returns an error if the MFT size is unknown, such as on ReFS, or the sample cannot be
+    /// read.
+    pub fn estimate_scan_duration(&self) -> UsnResult<Duration> {
+        let record_count = self.estimate_entry_count()?;
+        let options = EnumOptions {
+            end_fid: SCAN_SAMPLE_RECORDS,
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        for entry in MftIter::with_options(self.volume.shared_handle(), options) {
+            entry?;
+        }
+        Ok(extrapolate_scan_duration(started.elapsed(), record_count))
+    }
+
+    /// Calls `f` with every MFT entry without allocating per entry.
+    ///
+    /// Entries borrow their file name from the read buffer, which makes this the cheapest
+    /// way to scan a whole volume. Return [`ControlFlow::Break`] from `f` to stop early.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while enumerating the MFT.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::ops::ControlFlow;
+    /// use usn_journal_rs::{mft::Mft, volume::Volume};
+    ///
+    /// let volume = Volume::from_drive_letter('C')?;
+    /// let mut directories = 0;
+    /// Mft::new(&volume).for_each(|entry| {
+    ///     if entry.is_dir() {
+    ///         directories += 1;
+    ///     }
+    ///     ControlFlow::Continue(())
+    /// })?;
+    /// # Ok::<(), usn_journal_rs::UsnError>(())
+    /// ```
+    pub fn for_each<F>(&self, f: F) -> UsnResult<()>
+    where
+        F: FnMut(&BorrowedMftEntry<'_>) -> ControlFlow<()>,
+    {
+        self.iter().for_each_borrowed(f)
+    }
+
+    /// Enumerates the whole MFT and yields entries parents-first, each with its resolved path.
+    ///
+    /// Because every directory is yielded before its children, paths are built purely from
+    /// an in-memory cache of directory paths without a single `OpenFileById` call, which is
+    /// dramatically faster than [`PathResolver`](crate::path::PathResolver) for full scans.
+    ///
+    /// # Memory
+    /// The full enumeration runs up front: every `MftEntry` of the volume is held in memory
+    /// until it is yielded, and the path of every directory yielded so far is cached for the
+    /// lifetime of the iterator. Budget roughly a few hundred bytes per file on the volume.
+    ///
+    /// Entries whose parent chain does not reach the volume root (for example files whose
+    /// parent was deleted mid-scan) are yielded last, with a `None` path.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while enumerating the MFT.
+    pub fn iter_with_paths_cached(&self) -> UsnResult<MftPathIter> {
+        let entries = self.iter().collect::<UsnResult<Vec<_>>>()?;
+        Ok(MftPathIter::new(
+            path::volume_root_path(self.volume),
+            entries,
+        ))
+    }
+
+    /// Enumerates the whole MFT into a [`PathIndex`] for resolving paths in memory.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while enumerating the MFT.
+    pub fn path_index(&self) -> UsnResult<PathIndex> {
+        let entries = self.iter().collect::<UsnResult<Vec<_>>>()?;
+        Ok(PathIndex::from_entries(
+            path::volume_root_path(self.volume),
+            entries,
+        ))
+    }
+
+    /// Enumerates the whole MFT into an in-memory [`DirectoryTree`].
+    ///
+    /// # Errors
+    /// Returns the first error encountered while enumerating the MFT.
+    pub fn directory_tree(&self) -> UsnResult<DirectoryTree> {
+        let entries = self.iter().collect::<UsnResult<Vec<_>>>()?;
+        let root_name = path::volume_root_path(self.volume)
+            .to_string_lossy()
+            .into_owned();
+        Ok(DirectoryTree::from_entries(root_name, entries))
+    }
+}
+
+/// Iterator over MFT entries in parents-first order with paths resolved from an in-memory cache.
+///
+/// Created by [`Mft::iter_with_paths_cached`].
+pub struct MftPathIter {
+    ordered: VecDeque<MftEntry>,
+    orphans: VecDeque<MftEntry>,
+    root_path: PathBuf,
+    dir_paths: HashMap<FileId, PathBuf>,
+}
+
+impl MftPathIter {
+    fn new(root_path: PathBuf, entries: Vec<MftEntry>) -> Self {
+        let mut ordered = VecDeque::with_capacity(entries.len());
+        let mut children: HashMap<FileId, Vec<MftEntry>> = HashMap::new();
+        let mut pending_dirs = VecDeque::new();
+
+        for entry in entries {
+            if entry.fid == entry.parent_fid {
+                // The volume root reports itself as its own parent.
+                pending_dirs.push_back(entry.fid);
+                ordered.push_back(entry);
+            } else {
+                children.entry(entry.parent_fid).or_default().push(entry);
+            }
+        }
+
+        // The root may be missing from the enumeration; its record number is fixed on NTFS.
+        let mut dir_paths = HashMap::new();
+        for &parent_fid in children.keys() {
+            if is_root_record(parent_fid) {
+                pending_dirs.push_back(parent_fid);
+                dir_paths.insert(parent_fid, root_path.clone());
+            }
+        }
+
+        // Breadth-first walk from the root so every parent precedes its children.
+        while let Some(dir_fid) = pending_dirs.pop_front() {
+            for child in children.remove(&dir_fid).unwrap_or_default() {
+                if child.is_dir() {
+                    pending_dirs.push_back(child.fid);
+                }
+                ordered.push_back(child);
+            }
+        }
+
+        MftPathIter {
+            ordered,
+            orphans: children.into_values().flatten().collect(),
+            root_path,
+            dir_paths,
         }
     }
 }
 
+impl Iterator for MftPathIter {
+    type Item = (MftEntry, Option<PathBuf>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some(entry) = self.ordered.pop_front() else {
+            return self.orphans.pop_front().map(|entry| (entry, None));
+        };
+
+        let path = if entry.fid == entry.parent_fid {
+            self.root_path.clone()
+        } else {
+            // Parents come first, so the walk stops at the entry's already resolved parent.
+            path::walk_parent_links(
+                entry.fid,
+                |fid| self.dir_paths.get(&fid).cloned(),
+                |fid| (fid == entry.fid).then_some((entry.parent_fid, entry.file_name.as_os_str())),
+            )?
+        };
+
+        if entry.is_dir() {
+            self.dir_paths.insert(entry.fid, path.clone());
+        }
+
+        Some((entry, Some(path)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.ordered.len() + self.orphans.len();
+        (remaining, Some(remaining))
+    }
+}
+
 /// Iterator over MFT entries.
 ///
 /// This iterator yields `Result<MftEntry, UsnError>` items, allowing applications
 /// to handle individual entry errors without stopping the entire iteration process.
 pub struct MftIter {
-    handle: Rc<Owned<HANDLE>>,
+    handle: Arc<VolumeHandle>,
     low_usn: Usn,
     high_usn: Usn,
-    buffer: Vec<u8>,
+    buffer: AlignedBuffer,
     bytes_read: u32,
     offset: u32,
     next_start_fid: u64,
+    // Record number at which the enumeration stops, for shards of a parallel enumeration.
+    end_record: u64,
+    finished_cleanly: bool,
+    // Set once a malformed record was found; the enumeration cannot continue past it.
+    corrupted: bool,
+    unknown_version_policy: UnknownVersionPolicy,
+    with_timestamps: bool,
+    with_sizes: bool,
+    extensions: ExtensionFilter,
+    current_record: Range<usize>,
 }
 
 impl MftIter {
+    fn with_options(handle: Arc<VolumeHandle>, options: EnumOptions) -> Self {
+        MftIter {
+            handle,
+            low_usn: options.low_usn,
+            high_usn: options.high_usn,
+            buffer: AlignedBuffer::new(options.buffer_size),
+            bytes_read: 0,
+            offset: 0,
+            next_start_fid: options.start_fid & RECORD_NUMBER_MASK,
+            end_record: options.end_fid,
+            finished_cleanly: false,
+            corrupted: false,
+            unknown_version_policy: options.unknown_version_policy,
+            with_timestamps: options.with_timestamps,
+            with_sizes: options.with_sizes,
+            extensions: ExtensionFilter::new(&options.extensions),
+            current_record: 0..0,
+        }
+    }
+
+    /// Pairs each entry with its resolved path, or `None` if it cannot be resolved.
+    ///
+    /// Every path is resolved through `resolver`; for whole-volume scans
+    /// [`Mft::iter_with_paths_cached`] avoids the per-directory lookups. The iterator itself
+    /// yields entries without paths.
+    pub fn with_paths<'r, 'v, S: BuildHasher>(
+        self,
+        resolver: &'r mut PathResolver<'v, S>,
+    ) -> WithPaths<'r, 'v, Self, MftEntry, S> {
+        WithPaths::without_callback(self, resolver)
+    }
+
+    /// Returns true once the enumeration has reached the genuine end of the MFT.
+    ///
+    /// Iteration may also stop early because a caller gave up after an error; in that case
+    /// this stays false, which lets callers detect a truncated enumeration.
+    pub fn finished_cleanly(&self) -> bool {
+        self.finished_cleanly
+    }
+
+    /// Converts each entry into a [`FileRecord`](crate::adapters::FileRecord), the type shared
+    /// with journal reads, so one pipeline can consume both sources.
+    pub fn file_records(self) -> FileRecords<Self> {
+        FileRecords::new(self)
+    }
+
+    /// Returns the raw bytes of the record behind the most recently yielded entry.
+    ///
+    /// The slice covers the whole `USN_RECORD`, including fields and versions this crate does
+    /// not parse, so callers can decode them themselves. It borrows the iterator's read
+    /// buffer and is therefore only valid until the next call to `next()`, which may refill
+    /// that buffer. The slice is empty before the first entry and after an error or the end
+    /// of iteration.
+    pub fn current_record_bytes(&self) -> &[u8] {
+        &self.buffer[self.current_record.clone()]
+    }
+
     /// Reads the next chunk of MFT data into the buffer.
     ///
-    /// Returns `Ok(true)` if data was read, `Ok(false)` if EOF, or an error.
+    /// Returns `Ok(true)` if records were read, `Ok(false)` if EOF, or an error.
+    ///
+    /// The end of the MFT is normally reported as `ERROR_HANDLE_EOF`, but some systems and
+    /// filter drivers report `ERROR_NO_MORE_FILES` or succeed with a buffer holding only the
+    /// next-start FID header. All of these are treated as a clean end of enumeration.
     fn get_data(&mut self) -> Result<bool, UsnError> {
         // To enumerate files on a volume, use the FSCTL_ENUM_USN_DATA operation one or more times.
         // On the first call, set the starting point, the StartFileReferenceNumber member of the MFT_ENUM_DATA structure, to (DWORDLONG)0.
-        let mft_enum_data = Ioctl::MFT_ENUM_DATA_V0 {
+        let mft_enum_data = Ioctl::MFT_ENUM_DATA_V1 {
             StartFileReferenceNumber: self.next_start_fid,
             LowUsn: self.low_usn,
             HighUsn: self.high_usn,
+            MinMajorVersion: usn_record::MIN_MAJOR_VERSION,
+            MaxMajorVersion: usn_record::MAX_MAJOR_VERSION,
         };
 
         if let Err(err) = unsafe {
@@ -175,61 +716,200 @@ impl MftIter {
                 **self.handle,
                 Ioctl::FSCTL_ENUM_USN_DATA,
                 Some(&mft_enum_data as *const _ as _),
-                size_of::<Ioctl::MFT_ENUM_DATA_V0>() as u32,
+                size_of::<Ioctl::MFT_ENUM_DATA_V1>() as u32,
                 Some(self.buffer.as_mut_ptr() as _),
                 self.buffer.len() as u32,
                 Some(&mut self.bytes_read),
                 None,
             )
         } {
-            if err.code() == ERROR_HANDLE_EOF.into() {
+            if err.code() == ERROR_HANDLE_EOF.into() || err.code() == ERROR_NO_MORE_FILES.into() {
+                self.finished_cleanly = true;
                 return Ok(false);
             }
-            return Err(UsnError::WinApiError(err));
+            return Err(errors::fsctl_error(err));
+        }
+
+        if self.bytes_read as usize <= size_of::<u64>() {
+            // Nothing beyond the next-start FID header, so there are no records left.
+            self.finished_cleanly = true;
+            return Ok(false);
         }
         Ok(true)
     }
 
-    /// Finds the next USN record in the buffer, reading more data if needed.
+    /// Makes sure the buffer holds an unread record, reading more data if needed.
     ///
-    /// Returns `Ok(Some(MftEntry))` if a record is found, `Ok(None)` if EOF, or an error.
-    fn find_next_entry(&mut self) -> Result<Option<MftEntry>, UsnError> {
+    /// Returns `Ok(false)` once the enumeration is exhausted, or an error.
+    fn fill_buffer(&mut self) -> Result<bool, UsnError> {
+        if self.finished_cleanly || self.corrupted {
+            return Ok(false);
+        }
         if self.offset < self.bytes_read {
-            let (entry, record_len) =
-                parse_mft_usn_record_v2(&self.buffer, self.offset, self.bytes_read)?;
-            self.offset += record_len;
-            return Ok(Some(entry));
+            return Ok(true);
         }
 
         // We need to read more data
-        if self.get_data()? {
-            // Each call to FSCTL_ENUM_USN_DATA retrieves the starting point for the subsequent call as the first entry in the output buffer.
-            self.next_start_fid = usn_record::read_unaligned_from::<u64>(&self.buffer, 0)
-                .ok_or_else(|| {
-                    UsnError::OtherError(
-                        "MFT data buffer missing next-start FID header".to_string(),
-                    )
-                })?;
-            self.offset = size_of::<u64>() as u32;
-            if self.offset < self.bytes_read {
-                let (entry, record_len) =
-                    parse_mft_usn_record_v2(&self.buffer, self.offset, self.bytes_read)?;
-                self.offset += record_len;
-                return Ok(Some(entry));
+        loop {
+            match self.get_data() {
+                Ok(true) => {}
+                // EOF, no more data to read
+                Ok(false) => return Ok(false),
+                Err(UsnError::WinApiError(err))
+                    if err.code() == ERROR_INSUFFICIENT_BUFFER.into() =>
+                {
+                    self.buffer.grow_to(self.buffer.len().saturating_mul(2))?;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+            // A record longer than the buffer comes back cut off; read it again with enough
+            // room.
+            match usn_record::oversized_first_record(
+                &self.buffer,
+                self.bytes_read,
+                size_of::<u64>(),
+            ) {
+                Some(needed) => self.buffer.grow_to(needed)?,
+                None => break,
             }
         }
 
-        // EOF, no more data to read
+        // Each call to FSCTL_ENUM_USN_DATA retrieves the starting point for the subsequent call as the first entry in the output buffer.
+        self.next_start_fid =
+            usn_record::read_unaligned_from::<u64>(&self.buffer, 0).ok_or_else(|| {
+                UsnError::OtherError("MFT data buffer missing next-start FID header".to_string())
+            })?;
+        self.offset = size_of::<u64>() as u32;
+        Ok(true)
+    }
+
+    /// Finds the next USN record in the buffer, reading more data if needed.
+    ///
+    /// Returns `Ok(Some(MftEntry))` if a record is found, `Ok(None)` if EOF, or an error.
+    fn find_next_entry(&mut self) -> Result<Option<MftEntry>, UsnError> {
+        while self.fill_buffer()? {
+            if let Some(entry) = self.parse_next_record()? {
+                return Ok(Some(entry));
+            }
+        }
         Ok(None)
     }
+
+    /// Reads the header of the record at the current offset and advances past it.
+    ///
+    /// Returns the record's offset and header, or `Ok(None)` if the record was skipped
+    /// because of its version.
+    fn next_record_header(
+        &mut self,
+    ) -> Result<Option<(usize, usn_record::UsnRecordHeader)>, UsnError> {
+        let base = self.offset as usize;
+        // A malformed length leaves no way to find the next record, so the error ends the
+        // enumeration instead of being reported again on every call.
+        let (header, record_len) = usn_record::parse_usn_record_header(
+            &self.buffer,
+            self.offset,
+            self.bytes_read,
+            "MFT record",
+        )
+        .inspect_err(|_| self.corrupted = true)?;
+
+        // Records come back in record-number order, so the first one past the end of the
+        // window ends it.
+        if header
+            .file_reference_number
+            .as_u64()
+            .is_some_and(|fid| fid & RECORD_NUMBER_MASK >= self.end_record)
+        {
+            self.finished_cleanly = true;
+            return Ok(None);
+        }
+
+        // Advance before checking the version so an unsupported record never stalls iteration.
+        self.offset += record_len;
+
+        if !usn_record::check_record_version(&header, self.unknown_version_policy)? {
+            return Ok(None);
+        }
+        Ok(Some((base, header)))
+    }
+
+    /// Parses the record at the current offset and advances past it.
+    ///
+    /// Returns `Ok(None)` if the record was skipped because of its version or name.
+    fn parse_next_record(&mut self) -> Result<Option<MftEntry>, UsnError> {
+        let Some((base, header)) = self.next_record_header()? else {
+            return Ok(None);
+        };
+        let name = usn_record::usn_record_name_bytes(&self.buffer, base, &header, "MFT record")?;
+        if !self.extensions.matches(name) {
+            return Ok(None);
+        }
+        let entry = parse_mft_usn_record(&self.buffer, base, &header)?;
+        self.current_record = base..self.offset as usize;
+        Ok(Some(entry))
+    }
+
+    /// Calls `f` with each remaining entry, borrowed from the read buffer.
+    ///
+    /// Stops early when `f` returns [`ControlFlow::Break`]. No memory is allocated per entry.
+    pub(crate) fn for_each_borrowed<F>(&mut self, mut f: F) -> UsnResult<()>
+    where
+        F: FnMut(&BorrowedMftEntry<'_>) -> ControlFlow<()>,
+    {
+        while self.fill_buffer()? {
+            let Some((base, header)) = self.next_record_header()? else {
+                continue;
+            };
+            let entry = BorrowedMftEntry {
+                usn: header.usn,
+                fid: header.file_reference_number,
+                parent_fid: header.parent_file_reference_number,
+                file_attributes: header.file_attributes,
+                file_name: usn_record::usn_record_name_bytes(
+                    &self.buffer,
+                    base,
+                    &header,
+                    "MFT record",
+                )?,
+            };
+            if f(&entry).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Iterator for MftIter {
     type Item = UsnResult<MftEntry>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.current_record = 0..0;
         match self.find_next_entry() {
-            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(Some(mut entry)) => {
+                if self.with_timestamps || self.with_sizes {
+                    // Both queries share one handle, so each file is only opened once.
+                    match path::open_file_by_id(**self.handle, entry.fid, FILE_READ_ATTRIBUTES.0) {
+                        Ok(file) => {
+                            if self.with_timestamps {
+                                entry.timestamps = read_file_timestamps(*file)
+                                    .inspect_err(|err| {
+                                        debug!("No timestamps for {}: {err}", entry.fid)
+                                    })
+                                    .ok();
+                            }
+                            if self.with_sizes {
+                                entry.sizes = read_file_sizes(*file)
+                                    .inspect_err(|err| debug!("No sizes for {}: {err}", entry.fid))
+                                    .ok();
+                            }
+                        }
+                        Err(err) => debug!("No timestamps or sizes for {}: {err}", entry.fid),
+                    }
+                }
+                Some(Ok(entry))
+            }
             Ok(None) => None,
             Err(err) => {
                 debug!("Error finding next MFT entry: {err}");
@@ -239,26 +919,119 @@ impl Iterator for MftIter {
     }
 }
 
-fn parse_mft_usn_record_v2(
+fn parse_mft_usn_record(
     buffer: &[u8],
-    offset: u32,
-    bytes_read: u32,
-) -> Result<(MftEntry, u32), UsnError> {
-    let base = offset as usize;
-    let (header, record_len) =
-        usn_record::parse_usn_record_v2_header(buffer, offset, bytes_read, "MFT record")?;
-    let file_name = usn_record::parse_usn_record_v2_name(buffer, base, &header, "MFT record")?;
+    base: usize,
+    header: &usn_record::UsnRecordHeader,
+) -> Result<MftEntry, UsnError> {
+    let file_name = usn_record::parse_usn_record_name(buffer, base, header, "MFT record")?;
+
+    Ok(MftEntry {
+        usn: header.usn,
+        fid: header.file_reference_number,
+        parent_fid: header.parent_file_reference_number,
+        file_name,
+        file_attributes: header.file_attributes,
+        timestamps: None,
+        sizes: None,
+    })
+}
 
-    Ok((
-        MftEntry {
-            usn: header.usn,
-            fid: header.file_reference_number,
-            parent_fid: header.parent_file_reference_number,
-            file_name,
-            file_attributes: header.file_attributes,
-        },
-        record_len,
-    ))
+/// Returns the number of records the MFT of the NTFS volume behind `volume_handle` holds.
+fn mft_record_count(volume_handle: HANDLE) -> UsnResult<u64> {
+    let mut data = Ioctl::NTFS_VOLUME_DATA_BUFFER::default();
+    let mut bytes_returned = 0u32;
+    unsafe {
+        DeviceIoControl(
+            volume_handle,
+            Ioctl::FSCTL_GET_NTFS_VOLUME_DATA,
+            None,
+            0,
+            Some(&mut data as *mut _ as _),
+            size_of::<Ioctl::NTFS_VOLUME_DATA_BUFFER>() as u32,
+            Some(&mut bytes_returned),
+            None,
+        )?
+    };
+
+    if data.BytesPerFileRecordSegment == 0 {
+        return Err(UsnError::OtherError(
+            "volume reported a zero MFT record size".to_string(),
+        ));
+    }
+    Ok(data.MftValidDataLength.max(0) as u64 / u64::from(data.BytesPerFileRecordSegment))
+}
+
+/// Splits the record numbers of an MFT with `record_count` records into up to `num_shards`
+/// contiguous windows.
+///
+/// The last window is left open-ended so records added while enumerating are still read.
+#[cfg(any(feature = "rayon", test))]
+fn shard_ranges(record_count: u64, num_shards: usize) -> Vec<Range<u64>> {
+    let num_shards = (num_shards as u64).clamp(1, record_count.max(1));
+    let bound =
+        |shard: u64| (u128::from(record_count) * u128::from(shard) / u128::from(num_shards)) as u64;
+    (0..num_shards)
+        .map(|shard| {
+            let end = if shard + 1 == num_shards {
+                u64::MAX
+            } else {
+                bound(shard + 1)
+            };
+            bound(shard)..end
+        })
+        .collect()
+}
+
+/// Scales the time taken to read the first [`SCAN_SAMPLE_RECORDS`] records up to an MFT with
+/// `record_count` records.
+fn extrapolate_scan_duration(sample_elapsed: Duration, record_count: u64) -> Duration {
+    let sampled = record_count.clamp(1, SCAN_SAMPLE_RECORDS);
+    sample_elapsed.mul_f64(record_count.max(1) as f64 / sampled as f64)
+}
+
+/// Reads the timestamps of the open `file`.
+fn read_file_timestamps(file: HANDLE) -> UsnResult<FileTimestamps> {
+    let mut info = FILE_BASIC_INFO::default();
+    unsafe {
+        GetFileInformationByHandleEx(
+            file,
+            FileBasicInfo,
+            &mut info as *mut FILE_BASIC_INFO as *mut c_void,
+            size_of::<FILE_BASIC_INFO>() as u32,
+        )?
+    };
+
+    Ok(FileTimestamps {
+        created: time::filetime_to_systemtime(info.CreationTime)?,
+        last_write: time::filetime_to_systemtime(info.LastWriteTime)?,
+        last_access: time::filetime_to_systemtime(info.LastAccessTime)?,
+        mft_changed: time::filetime_to_systemtime(info.ChangeTime)?,
+    })
+}
+
+/// Reads the sizes of the data of the open `file`.
+fn read_file_sizes(file: HANDLE) -> UsnResult<FileSizes> {
+    let mut info = FILE_STANDARD_INFO::default();
+    unsafe {
+        GetFileInformationByHandleEx(
+            file,
+            FileStandardInfo,
+            &mut info as *mut FILE_STANDARD_INFO as *mut c_void,
+            size_of::<FILE_STANDARD_INFO>() as u32,
+        )?
+    };
+
+    if info.Directory {
+        return Ok(FileSizes {
+            file_size: 0,
+            allocated_size: 0,
+        });
+    }
+    Ok(FileSizes {
+        file_size: info.EndOfFile.max(0) as u64,
+        allocated_size: info.AllocationSize.max(0) as u64,
+    })
 }
 
 #[cfg(test)]
@@ -268,7 +1041,11 @@ mod tests {
     use std::mem::offset_of;
     use windows::Win32::{
         Foundation::{ERROR_INVALID_HANDLE, HANDLE},
-        System::{IO::DeviceIoControl, Ioctl::USN_RECORD_V2},
+        Storage::FileSystem::FILE_ID_128,
+        System::{
+            IO::DeviceIoControl,
+            Ioctl::{USN_RECORD_V2, USN_RECORD_V3},
+        },
     };
 
     // Test data generators
@@ -327,34 +1104,120 @@ mod tests {
         buffer
     }
 
-    fn parse_mock_mft_entry(record_data: &[u8]) -> MftEntry {
-        parse_mft_usn_record_v2(record_data, 0, record_data.len() as u32)
-            .unwrap()
-            .0
-    }
-
-    // Unit tests for MftEntry
-    mod mft_entry_tests {
-        use super::*;
-
-        #[test]
-        fn test_mft_entry_new_basic() {
-            let record_data = create_mock_usn_record(
-                100,   // usn
-                12345, // file_id
-                67890, // parent_file_id
-                "test.txt", 0x20, // FILE_ATTRIBUTE_ARCHIVE
-            );
+    fn create_mock_usn_record_v3(
+        usn: i64,
+        file_id: FileId,
+        parent_file_id: FileId,
+        file_name: &str,
+        file_attributes: u32,
+    ) -> Vec<u8> {
+        let file_name_utf16: Vec<u16> = file_name.encode_utf16().collect();
+        let file_name_len = file_name_utf16.len() * 2; // length in bytes
+        let filename_offset = offset_of!(USN_RECORD_V3, FileName);
+        let record_len = filename_offset + file_name_len;
 
-            let entry = parse_mock_mft_entry(&record_data);
+        let mut buffer = vec![0u8; record_len];
 
-            assert_eq!(entry.usn, 100);
-            assert_eq!(entry.fid, 12345);
-            assert_eq!(entry.parent_fid, 67890);
+        let base_record = USN_RECORD_V3 {
+            RecordLength: record_len as u32,
+            MajorVersion: 3,
+            MinorVersion: 0,
+            FileReferenceNumber: FILE_ID_128 {
+                Identifier: file_id.to_le_bytes(),
+            },
+            ParentFileReferenceNumber: FILE_ID_128 {
+                Identifier: parent_file_id.to_le_bytes(),
+            },
+            Usn: usn,
+            TimeStamp: 0,
+            Reason: 0,
+            SourceInfo: 0,
+            SecurityId: 0,
+            FileAttributes: file_attributes,
+            FileNameLength: file_name_len as u16,
+            FileNameOffset: filename_offset as u16,
+            FileName: [0; 1], // This will be overwritten
+        };
+
+        // Copy the fixed part of the record, then the filename data
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                &base_record as *const USN_RECORD_V3 as *const u8,
+                buffer.as_mut_ptr(),
+                filename_offset,
+            );
+            std::ptr::copy_nonoverlapping(
+                file_name_utf16.as_ptr() as *const u8,
+                buffer.as_mut_ptr().add(filename_offset),
+                file_name_len,
+            );
+        }
+
+        buffer
+    }
+
+    // Builds an iterator whose buffer already holds the result of one FSCTL_ENUM_USN_DATA call.
+    fn create_seeded_mft_iter(volume: &Volume, records: &[Vec<u8>]) -> MftIter {
+        let mut read_buffer = 0x1000u64.to_le_bytes().to_vec();
+        for record in records {
+            read_buffer.extend_from_slice(record);
+        }
+
+        let mut iter = Mft::new(volume).iter();
+        iter.buffer[..read_buffer.len()].copy_from_slice(&read_buffer);
+        iter.bytes_read = read_buffer.len() as u32;
+        iter.offset = size_of::<u64>() as u32;
+        iter
+    }
+
+    fn parse_mock_mft_entry(record_data: &[u8]) -> MftEntry {
+        let (header, _) = usn_record::parse_usn_record_header(
+            record_data,
+            0,
+            record_data.len() as u32,
+            "MFT record",
+        )
+        .unwrap();
+        parse_mft_usn_record(record_data, 0, &header).unwrap()
+    }
+
+    // Unit tests for MftEntry
+    mod mft_entry_tests {
+        use super::*;
+
+        #[test]
+        fn test_mft_entry_new_basic() {
+            let record_data = create_mock_usn_record(
+                100,   // usn
+                12345, // file_id
+                67890, // parent_file_id
+                "test.txt", 0x20, // FILE_ATTRIBUTE_ARCHIVE
+            );
+
+            let entry = parse_mock_mft_entry(&record_data);
+
+            assert_eq!(entry.usn, 100);
+            assert_eq!(entry.fid, 12345);
+            assert_eq!(entry.parent_fid, 67890);
             assert_eq!(entry.file_name.to_string_lossy(), "test.txt");
             assert_eq!(entry.file_attributes, 0x20);
         }
 
+        #[test]
+        fn test_mft_entry_from_v3_record() {
+            let file_id = FileId(0x0000_0000_0000_0001_0000_0000_0000_3039);
+            let record_data =
+                create_mock_usn_record_v3(100, file_id, FileId::from_u64(67890), "refs.txt", 0x10);
+
+            let entry = parse_mock_mft_entry(&record_data);
+
+            assert_eq!(entry.usn, 100);
+            assert_eq!(entry.fid, file_id);
+            assert_eq!(entry.parent_fid, 67890);
+            assert_eq!(entry.file_name.to_string_lossy(), "refs.txt");
+            assert!(entry.is_dir());
+        }
+
         #[test]
         fn test_mft_entry_is_dir_true() {
             let record_data = create_mock_usn_record(
@@ -407,6 +1270,49 @@ mod tests {
             assert!(entry.is_hidden());
         }
 
+        #[test]
+        fn test_mft_entry_attribute_predicates() {
+            let record_data = create_mock_usn_record(
+                100,
+                12345,
+                67890,
+                "pagefile.sys",
+                0x1 | 0x4 | 0x200 | 0x1000, // READONLY | SYSTEM | SPARSE_FILE | OFFLINE
+            );
+
+            let entry = parse_mock_mft_entry(&record_data);
+
+            assert!(entry.is_readonly());
+            assert!(entry.is_system());
+            assert!(entry.is_sparse());
+            assert!(entry.is_offline());
+            assert!(!entry.is_archive());
+            assert!(!entry.is_reparse_point());
+            assert!(!entry.is_compressed());
+            assert!(!entry.is_encrypted());
+            assert!(entry.attributes().contains(FILE_ATTRIBUTE_SYSTEM));
+            assert_eq!(entry.attributes().0, entry.file_attributes);
+
+            let record_data = create_mock_usn_record(
+                100,
+                12345,
+                67890,
+                "link",
+                0x20 | 0x400 | 0x800 | 0x4000, // ARCHIVE | REPARSE_POINT | COMPRESSED | ENCRYPTED
+            );
+
+            let entry = parse_mock_mft_entry(&record_data);
+
+            assert!(entry.is_archive());
+            assert!(entry.is_reparse_point());
+            assert!(entry.is_compressed());
+            assert!(entry.is_encrypted());
+            assert!(!entry.is_readonly());
+            assert!(!entry.is_system());
+            assert!(!entry.is_sparse());
+            assert!(!entry.is_offline());
+        }
+
         #[test]
         fn test_mft_entry_unicode_filename() {
             let record_data = create_mock_usn_record(
@@ -460,6 +1366,36 @@ mod tests {
             assert!(formatted.contains("Parent File ID      : 0x67890"));
             assert!(formatted.contains("Type                : Directory"));
             assert!(formatted.contains("Path                : test.txt"));
+            assert!(!formatted.contains("Created"));
+            assert!(!formatted.contains("Size"));
+        }
+
+        #[test]
+        fn test_mft_entry_pretty_format_with_timestamps_and_size() {
+            let record_data = create_mock_usn_record(100, 0x12345, 0x67890, "test.txt", 0x20);
+            let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+            let last_write = created + Duration::from_secs(3600);
+            let entry = MftEntry {
+                timestamps: Some(FileTimestamps {
+                    created,
+                    last_write,
+                    last_access: last_write,
+                    mft_changed: last_write,
+                }),
+                sizes: Some(FileSizes {
+                    file_size: 1234,
+                    allocated_size: 4096,
+                }),
+                ..parse_mock_mft_entry(&record_data)
+            };
+
+            let formatted = entry.pretty_format(None::<&std::path::Path>);
+
+            let local = |time| DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M:%S");
+            assert!(formatted.contains(&format!("Created             : {}", local(created))));
+            assert!(formatted.contains(&format!("Modified            : {}", local(last_write))));
+            assert!(formatted.contains("Size                : 1234 bytes"));
+            assert!(formatted.contains("Path                : test.txt"));
         }
     }
 
@@ -468,6 +1404,13 @@ mod tests {
         use super::*;
         use windows::Win32::Foundation::HANDLE;
 
+        #[test]
+        fn test_mft_iter_is_send() {
+            fn assert_send<T: Send>() {}
+            assert_send::<MftIter>();
+            assert_send::<MftPathIter>();
+        }
+
         #[test]
         fn test_iter_with_options_starts_from_first_file_reference() {
             let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
@@ -477,6 +1420,7 @@ mod tests {
                 low_usn: 42,
                 high_usn: 2048,
                 buffer_size: 4096,
+                ..Default::default()
             });
 
             assert_eq!(iter.low_usn, 42);
@@ -484,13 +1428,294 @@ mod tests {
             assert_eq!(iter.buffer.len(), 4096);
             assert_eq!(iter.handle, volume.shared_handle());
             assert_eq!(iter.next_start_fid, 0);
+            assert_eq!(iter.end_record, u64::MAX);
+        }
+
+        #[test]
+        fn test_shard_ranges_cover_every_record_once() {
+            let shards = shard_ranges(1_000_003, 4);
+            assert_eq!(shards.len(), 4);
+            assert_eq!(shards[0].start, 0);
+            for pair in shards.windows(2) {
+                assert_eq!(pair[0].end, pair[1].start);
+                assert!(pair[0].start < pair[0].end);
+            }
+            assert_eq!(shards[3].end, u64::MAX);
+
+            // Never more shards than records, and always at least one.
+            assert_eq!(shard_ranges(2, 8), vec![0..1, 1..u64::MAX]);
+            let empty = shard_ranges(0, 8);
+            assert_eq!(empty.len(), 1);
+            assert_eq!((empty[0].start, empty[0].end), (0, u64::MAX));
         }
+
+        #[test]
+        fn test_extrapolate_scan_duration_scales_sample_to_record_count() {
+            let sample = Duration::from_millis(100);
+            assert_eq!(
+                extrapolate_scan_duration(sample, SCAN_SAMPLE_RECORDS * 10),
+                Duration::from_secs(1)
+            );
+
+            // An MFT no larger than the sample was read in full.
+            assert_eq!(
+                extrapolate_scan_duration(sample, SCAN_SAMPLE_RECORDS),
+                sample
+            );
+            assert_eq!(extrapolate_scan_duration(sample, 1_000), sample);
+            assert_eq!(extrapolate_scan_duration(sample, 0), sample);
+        }
+    }
+
+    #[test]
+    fn test_with_paths_attaches_resolved_paths() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [
+            create_mock_usn_record(0x100, 0x11, 0x10, "a.txt", 0x20),
+            create_mock_usn_record(0x100, 0x12, 0x99, "orphan.txt", 0x20),
+        ];
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        iter.end_record = 0x13;
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        resolver.cache_directory(FileId(0x10), PathBuf::from(r"T:\Docs"));
+
+        let paths = iter
+            .with_paths(&mut resolver)
+            .map(|result| result.map(|(entry, path)| (entry.fid, path)))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                (FileId(0x11), Some(PathBuf::from(r"T:\Docs\a.txt"))),
+                (FileId(0x12), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter_stops_at_end_of_shard_window() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [0x10u64, 0x1_0000_0000_0011, 0x20, 0x21]
+            .map(|fid| create_mock_usn_record(0x100, fid, 0x5, "shard.txt", 0x20));
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        iter.end_record = 0x20;
+
+        // The sequence number in the high bits does not count towards the record number.
+        let fids = iter
+            .by_ref()
+            .map(|entry| entry.map(|entry| entry.fid))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(fids, vec![FileId(0x10), FileId(0x1_0000_0000_0011)]);
+        assert!(iter.finished_cleanly());
     }
 
     // Simplified mocked test using Injectorpp
     mod mocked_tests {
         use super::*;
 
+        /// Answers a mocked `FSCTL_ENUM_USN_DATA` with `record`, cut off at the output size
+        /// like a real read into a buffer that is too small.
+        fn write_enum_output(
+            record: &[u8],
+            output: Option<*mut std::ffi::c_void>,
+            output_size: u32,
+            bytes_returned: Option<*mut u32>,
+        ) {
+            let mut data = 0x1000u64.to_le_bytes().to_vec();
+            data.extend_from_slice(record);
+            let len = data.len().min(output_size as usize);
+            unsafe {
+                std::ptr::copy_nonoverlapping(data.as_ptr(), output.unwrap().cast::<u8>(), len);
+                *bytes_returned.unwrap() = len as u32;
+            }
+        }
+
+        // Number of files opened by the mocked `OpenFileById` below.
+        static FILE_OPENS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        fn count_file_open() {
+            FILE_OPENS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        #[test]
+        fn test_next_opens_file_once_for_timestamps_and_sizes() {
+            use windows::Win32::Storage::FileSystem::{
+                FILE_FLAGS_AND_ATTRIBUTES, FILE_ID_DESCRIPTOR, FILE_INFO_BY_HANDLE_CLASS,
+                FILE_SHARE_MODE, OpenFileById,
+            };
+
+            let mut injector = InjectorPP::new();
+            FILE_OPENS.store(0, std::sync::atomic::Ordering::SeqCst);
+
+            // Every file opens as the never-closed pseudo handle and has zeroed metadata.
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (OpenFileById)(
+                        HANDLE,
+                        *const FILE_ID_DESCRIPTOR,
+                        u32,
+                        FILE_SHARE_MODE,
+                        Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<HANDLE>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _volume: HANDLE,
+                        _file_id: *const FILE_ID_DESCRIPTOR,
+                        _access: u32,
+                        _share_mode: FILE_SHARE_MODE,
+                        _security: Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        _flags: FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<HANDLE>,
+                    assign: { count_file_open() },
+                    returns: Ok(HANDLE(-1isize as *mut std::ffi::c_void))
+                ));
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (GetFileInformationByHandleEx)(
+                        HANDLE,
+                        FILE_INFO_BY_HANDLE_CLASS,
+                        *mut std::ffi::c_void,
+                        u32
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _file: HANDLE,
+                        _class: FILE_INFO_BY_HANDLE_CLASS,
+                        _info: *mut std::ffi::c_void,
+                        _size: u32
+                    ) -> windows::core::Result<()>,
+                    returns: Ok(())
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let records = vec![create_mock_usn_record(0x100, 0x20, 0x5, "file.txt", 0x20)];
+            let mut iter = create_seeded_mft_iter(&volume, &records);
+            iter.with_timestamps = true;
+            iter.with_sizes = true;
+
+            let entry = iter.next().unwrap().unwrap();
+            assert_eq!(FILE_OPENS.load(std::sync::atomic::Ordering::SeqCst), 1);
+            assert_eq!(
+                entry.sizes,
+                Some(FileSizes {
+                    file_size: 0,
+                    allocated_size: 0
+                })
+            );
+        }
+
+        fn long_name_record() -> Vec<u8> {
+            create_mock_usn_record(100, 0x20, 0x5, &"long-name-".repeat(20), 0x20)
+        }
+
+        fn corrupt_length_record() -> Vec<u8> {
+            let mut record = create_mock_usn_record(100, 0x20, 0x5, "short.txt", 0x20);
+            record[..4].copy_from_slice(&4096u32.to_le_bytes());
+            record
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_iter_grows_buffer_for_cut_off_record() {
+            let mut injector = InjectorPP::new();
+
+            // Every read returns the same long record, cut off when the buffer is too small
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    assign: {
+                        write_enum_output(&long_name_record(), _output, _output_size, _bytes_returned)
+                    },
+                    returns: Ok(())
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let mut iter = Mft::new(&volume).iter_with_options(EnumOptions {
+                buffer_size: 128,
+                ..Default::default()
+            });
+
+            let entry = iter.next().unwrap().unwrap();
+            assert_eq!(entry.file_name, "long-name-".repeat(20).as_str());
+            assert!(iter.buffer.len() >= size_of::<u64>() + long_name_record().len());
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_iter_reports_corrupt_record_length_without_growing() {
+            let mut injector = InjectorPP::new();
+
+            // The record claims to run past the bytes read, although the buffer is not full
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    assign: {
+                        write_enum_output(&corrupt_length_record(), _output, _output_size, _bytes_returned)
+                    },
+                    returns: Ok(())
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let mut iter = Mft::new(&volume).iter();
+
+            match iter.next() {
+                Some(Err(UsnError::OtherError(message))) => {
+                    assert!(!message.contains("does not fit"), "{message}")
+                }
+                other => panic!("Expected a malformed record error, got {other:?}"),
+            }
+            assert_eq!(iter.buffer.len(), DEFAULT_BUFFER_SIZE);
+            assert!(iter.next().is_none());
+        }
+
         #[allow(clippy::too_many_arguments)]
         #[test]
         fn test_device_io_control_error_handling() {
@@ -538,5 +1763,531 @@ mod tests {
                 _ => panic!("Expected WinApiError"),
             }
         }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_finished_cleanly_after_eof() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to report the end of the MFT
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Err(windows::core::Error::from(ERROR_HANDLE_EOF))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let mft = Mft::new(&volume);
+
+            let mut iter = mft.iter();
+            assert!(!iter.finished_cleanly());
+            assert!(iter.next().is_none());
+            assert!(iter.finished_cleanly());
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_not_finished_cleanly_after_error() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to fail mid-enumeration
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Err(windows::core::Error::from(ERROR_INVALID_HANDLE))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let mft = Mft::new(&volume);
+
+            let mut iter = mft.iter();
+            assert!(matches!(iter.next(), Some(Err(UsnError::WinApiError(_)))));
+            assert!(!iter.finished_cleanly());
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_for_each_visits_every_entry_until_eof() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to report the end of the MFT
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Err(windows::core::Error::from(ERROR_HANDLE_EOF))
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let records = (0..4)
+                .map(|i| create_mock_usn_record(0x100 + i, 0x10 + i as u64, 0x5, "f.txt", 0x20))
+                .collect::<Vec<_>>();
+            let mut iter = create_seeded_mft_iter(&volume, &records);
+
+            let mut count = 0;
+            let result = iter.for_each_borrowed(|_| {
+                count += 1;
+                ControlFlow::Continue(())
+            });
+
+            assert!(result.is_ok());
+            assert_eq!(count, 4);
+            assert!(iter.finished_cleanly());
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[test]
+        fn test_header_only_buffer_finishes_cleanly() {
+            let mut injector = InjectorPP::new();
+
+            // Mock DeviceIoControl to succeed without touching the output, so the iterator
+            // sees the header-only buffer it was seeded with below
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (DeviceIoControl)(
+                        HANDLE,
+                        u32,
+                        Option<*const std::ffi::c_void>,
+                        u32,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        Option<*mut u32>,
+                        Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _handle: HANDLE,
+                        _control_code: u32,
+                        _input: Option<*const std::ffi::c_void>,
+                        _input_size: u32,
+                        _output: Option<*mut std::ffi::c_void>,
+                        _output_size: u32,
+                        _bytes_returned: Option<*mut u32>,
+                        _overlapped: Option<*mut windows::Win32::System::IO::OVERLAPPED>
+                    ) -> windows::core::Result<()>,
+                    returns: Ok(())
+                ));
+
+            let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+            let mft = Mft::new(&volume);
+
+            let mut iter = mft.iter();
+            iter.buffer[..size_of::<u64>()].copy_from_slice(&0x1234u64.to_le_bytes());
+            iter.bytes_read = size_of::<u64>() as u32;
+            iter.offset = iter.bytes_read;
+
+            assert!(iter.next().is_none());
+            assert!(iter.finished_cleanly());
+            assert!(iter.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_timestamps_are_optional_per_entry() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [create_mock_usn_record(0x100, 0x10, 0x5, "first.txt", 0x20)];
+
+        let entry = create_seeded_mft_iter(&volume, &records)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.timestamps, None);
+
+        // A file that cannot be opened still yields its entry, just without timestamps.
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        iter.with_timestamps = true;
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.file_name, "first.txt");
+        assert_eq!(entry.timestamps, None);
+
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        iter.with_sizes = true;
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.sizes, None);
+    }
+
+    #[test]
+    fn test_extension_filter_skips_other_names() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [
+            create_mock_usn_record(0x100, 0x10, 0x5, "notes.txt", 0x20),
+            create_mock_usn_record(0x200, 0x11, 0x5, "app.log", 0x20),
+            create_mock_usn_record(0x300, 0x12, 0x5, "Docs", 0x10),
+            create_mock_usn_record(0x400, 0x13, 0x5, "README.TXT", 0x20),
+        ];
+
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        iter.extensions = ExtensionFilter::new(&["txt".to_string()]);
+        let names = iter
+            .take(2)
+            .map(|entry| entry.unwrap().file_name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["notes.txt", "README.TXT"]);
+    }
+
+    #[test]
+    fn test_zero_length_record_ends_enumeration() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let mut corrupt = create_mock_usn_record(0x200, 0x11, 0x5, "corrupt.txt", 0x20);
+        corrupt[..4].copy_from_slice(&0u32.to_le_bytes());
+        let records = [
+            create_mock_usn_record(0x100, 0x10, 0x5, "first.txt", 0x20),
+            corrupt,
+        ];
+
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        assert_eq!(iter.next().unwrap().unwrap().file_name, "first.txt");
+        assert!(matches!(
+            iter.next(),
+            Some(Err(UsnError::OtherError(message))) if message.contains("zero RecordLength")
+        ));
+        // Without a valid length there is no next record to move to; iteration stops.
+        assert!(iter.next().is_none());
+        assert!(!iter.finished_cleanly());
+    }
+
+    #[test]
+    fn test_current_record_bytes_match_yielded_record() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [
+            create_mock_usn_record(0x100, 0x10, 0x5, "first.txt", 0x20),
+            create_mock_usn_record(0x200, 0x11, 0x5, "second.txt", 0x20),
+        ];
+
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+        assert!(iter.current_record_bytes().is_empty());
+
+        for record in &records {
+            assert!(iter.next().unwrap().is_ok());
+            assert_eq!(iter.current_record_bytes(), record.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_for_each_stops_on_break() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = (0..5)
+            .map(|i| create_mock_usn_record(0x100 + i, 0x10 + i as u64, 0x5, "file.txt", 0x20))
+            .collect::<Vec<_>>();
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+
+        let mut visited = 0;
+        let mut names_match = true;
+        let result = iter.for_each_borrowed(|entry| {
+            visited += 1;
+            names_match &= entry.file_name_eq("file.txt");
+            if visited == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(visited, 3);
+        assert!(names_match);
+    }
+
+    #[test]
+    fn test_borrowed_entry_to_entry_copies_fields() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let records = [create_mock_usn_record(0x100, 0x10, 0x5, "Docs", 0x10)];
+        let mut iter = create_seeded_mft_iter(&volume, &records);
+
+        let mut owned = None;
+        iter.for_each_borrowed(|entry| {
+            owned = Some(entry.to_entry());
+            ControlFlow::Break(())
+        })
+        .unwrap();
+
+        let owned = owned.unwrap();
+        assert_eq!(owned.fid, 0x10);
+        assert_eq!(owned.parent_fid, 0x5);
+        assert_eq!(owned.file_name, "Docs");
+        assert!(owned.is_dir());
+    }
+
+    // Unit tests for cache-only path resolution
+    mod path_iter_tests {
+        use super::*;
+
+        fn mock_entry(fid: u64, parent_fid: u64, name: &str, is_dir: bool) -> MftEntry {
+            MftEntry {
+                usn: 0,
+                fid: fid.into(),
+                parent_fid: parent_fid.into(),
+                file_name: OsString::from(name),
+                file_attributes: if is_dir { 0x10 } else { 0x20 },
+                timestamps: None,
+                sizes: None,
+            }
+        }
+
+        #[test]
+        fn test_path_iter_yields_parents_first_with_paths() {
+            let root = 0x0005_0000_0000_0005;
+            // Deliberately list children before their parents.
+            let entries = vec![
+                mock_entry(0x30, 0x20, "deep.txt", false),
+                mock_entry(0x20, 0x10, "Sub", true),
+                mock_entry(0x11, root, "top.txt", false),
+                mock_entry(0x10, root, "Docs", true),
+                mock_entry(root, root, ".", true),
+                mock_entry(0x40, 0x99, "orphan.txt", false),
+            ];
+
+            let results = MftPathIter::new(PathBuf::from(r"C:\"), entries)
+                .map(|(entry, path)| (entry.fid, path))
+                .collect::<Vec<_>>();
+
+            let position = |fid| results.iter().position(|(f, _)| *f == fid).unwrap();
+            assert!(position(root) < position(0x10));
+            assert!(position(0x10) < position(0x20));
+            assert!(position(0x20) < position(0x30));
+            assert_eq!(position(0x40), results.len() - 1);
+
+            let path_of = |fid| results[position(fid)].1.clone();
+            assert_eq!(path_of(root), Some(PathBuf::from(r"C:\")));
+            assert_eq!(path_of(0x11), Some(PathBuf::from(r"C:\top.txt")));
+            assert_eq!(path_of(0x30), Some(PathBuf::from(r"C:\Docs\Sub\deep.txt")));
+            assert_eq!(path_of(0x40), None);
+        }
+
+        #[test]
+        fn test_path_iter_without_root_entry_uses_root_record_number() {
+            let root = 0x0005_0000_0000_0005;
+            let entries = vec![
+                mock_entry(0x20, 0x10, "file.txt", false),
+                mock_entry(0x10, root, "Docs", true),
+            ];
+
+            let results = MftPathIter::new(PathBuf::from(r"D:\"), entries).collect::<Vec<_>>();
+
+            assert_eq!(results[0].1, Some(PathBuf::from(r"D:\Docs")));
+            assert_eq!(results[1].1, Some(PathBuf::from(r"D:\Docs\file.txt")));
+        }
+    }
+
+    // Integration tests that require actual filesystem access
+    mod integration_tests {
+        use super::*;
+
+        #[test]
+        #[ignore = "timing depends on cache state and machine load"]
+        fn test_estimate_scan_duration_is_close_to_a_timed_scan() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let mft = volume.mft();
+
+            let estimate = mft.estimate_scan_duration()?;
+            let started = Instant::now();
+            for entry in mft.iter() {
+                entry?;
+            }
+            let actual = started.elapsed();
+
+            assert!(estimate > Duration::ZERO);
+            let ratio = estimate.as_secs_f64() / actual.as_secs_f64();
+            assert!(
+                (0.1..=10.0).contains(&ratio),
+                "estimate {estimate:?} vs actual {actual:?}"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_read_file_sizes_of_known_file() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_mft_sizes.txt");
+            // Large enough to be stored outside the MFT record.
+            std::fs::write(&file_path, vec![b'x'; 10_000])?;
+            let fid = FileId::from(path::file_id_from_path(&file_path)?);
+            let dir_fid = FileId::from(path::file_id_from_path(file_path.parent().unwrap())?);
+
+            let sizes = path::open_file_by_id(volume.handle(), fid, FILE_READ_ATTRIBUTES.0)
+                .map_err(UsnError::from)
+                .and_then(|file| read_file_sizes(*file));
+            std::fs::remove_file(&file_path)?;
+            let sizes = sizes?;
+
+            assert_eq!(sizes.file_size, 10_000);
+            assert!(sizes.allocated_size >= sizes.file_size);
+            let dir = path::open_file_by_id(volume.handle(), dir_fid, FILE_READ_ATTRIBUTES.0)?;
+            let dir_sizes = read_file_sizes(*dir)?;
+            assert_eq!(dir_sizes.file_size, 0);
+            Ok(())
+        }
+
+        #[cfg(feature = "rayon")]
+        #[test]
+        fn test_par_iter_matches_serial_enumeration() -> Result<(), UsnError> {
+            use std::collections::HashSet;
+
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let mft = Mft::new(&volume);
+
+            let serial = mft.iter().collect::<UsnResult<Vec<_>>>()?;
+            let parallel = mft
+                .par_iter(NonZeroUsize::new(4).unwrap())?
+                .collect::<UsnResult<Vec<_>>>()?;
+
+            // A shard edge must never report a record twice.
+            let unique = parallel
+                .iter()
+                .map(|entry| entry.fid)
+                .collect::<HashSet<_>>();
+            assert_eq!(unique.len(), parallel.len());
+            // The live volume changes between the two passes, so allow a little drift.
+            let drift = serial.len().abs_diff(parallel.len());
+            assert!(
+                drift <= 100 + serial.len() / 1000,
+                "serial {} vs parallel {} entries",
+                serial.len(),
+                parallel.len()
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn test_read_file_timestamps_match_metadata() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let file_path = std::env::temp_dir().join("usn_journal_rs_mft_timestamps.txt");
+            std::fs::write(&file_path, b"timestamps")?;
+            let metadata = std::fs::metadata(&file_path)?;
+            let fid = FileId::from(path::file_id_from_path(&file_path)?);
+
+            let timestamps = path::open_file_by_id(volume.handle(), fid, FILE_READ_ATTRIBUTES.0)
+                .map_err(UsnError::from)
+                .and_then(|file| read_file_timestamps(*file));
+            std::fs::remove_file(&file_path)?;
+            let timestamps = timestamps?;
+
+            assert_eq!(timestamps.created, metadata.created()?);
+            assert_eq!(timestamps.last_write, metadata.modified()?);
+            assert!(timestamps.mft_changed >= timestamps.created);
+            Ok(())
+        }
+
+        #[test]
+        fn test_iter_with_paths_cached_matches_path_resolver() -> Result<(), UsnError> {
+            let volume = match Volume::from_drive_letter('C') {
+                Ok(volume) => volume,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            let mut resolver = volume.path_resolver();
+
+            let mut compared = 0;
+            for (entry, cached_path) in volume.mft().iter_with_paths_cached()? {
+                if compared == 500 {
+                    break;
+                }
+                if let (Some(cached_path), Some(resolved_path)) =
+                    (cached_path, resolver.resolve_path(&entry))
+                {
+                    assert_eq!(cached_path, resolved_path);
+                    compared += 1;
+                }
+            }
+
+            assert!(compared > 0, "Expected to compare at least one path");
+            Ok(())
+        }
     }
 }
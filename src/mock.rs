@@ -0,0 +1,240 @@
+//! Builders for mock journal data, for testing code that consumes this crate.
+//!
+//! Available with the `testing` feature. The buffers produced here have the same layout as
+//! the data Windows returns, so they can be parsed with [`UsnEntry::new`] without a real
+//! volume.
+//!
+//! [`UsnEntry::new`]: crate::journal::UsnEntry::new
+
+use crate::{FileId, Usn};
+use std::mem::offset_of;
+use windows::Win32::System::Ioctl::{USN_RECORD_V2, USN_RECORD_V3};
+
+/// Builds the bytes of a single `USN_RECORD_V2` or `USN_RECORD_V3`.
+///
+/// `RecordLength` is padded to a multiple of 8 bytes, as it is in real journal data, so
+/// records built here can be concatenated into a read buffer with [`read_buffer`].
+///
+/// # Example
+/// ```rust
+/// use usn_journal_rs::{journal::UsnEntry, mock::UsnRecordBuilder, reasons};
+///
+/// let record = UsnRecordBuilder::new("report.txt")
+///     .usn(0x100)
+///     .fid(0x42)
+///     .parent_fid(0x5)
+///     .reason(reasons::FILE_CREATE)
+///     .build();
+/// let entry = UsnEntry::new(&record)?;
+/// assert_eq!(entry.fid, 0x42);
+/// # Ok::<(), usn_journal_rs::UsnError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct UsnRecordBuilder {
+    major_version: u16,
+    usn: Usn,
+    fid: FileId,
+    parent_fid: FileId,
+    timestamp: i64,
+    reason: u32,
+    source_info: u32,
+    security_id: u32,
+    file_attributes: u32,
+    file_name: Vec<u16>,
+}
+
+impl UsnRecordBuilder {
+    /// Starts a version 2 record for `file_name` with all other fields zeroed.
+    pub fn new(file_name: &str) -> Self {
+        UsnRecordBuilder {
+            major_version: 2,
+            usn: 0,
+            fid: FileId(0),
+            parent_fid: FileId(0),
+            timestamp: 0,
+            reason: 0,
+            source_info: 0,
+            security_id: 0,
+            file_attributes: 0,
+            file_name: file_name.encode_utf16().collect(),
+        }
+    }
+
+    /// Builds a `USN_RECORD_V3` instead, whose file IDs are 128 bits wide.
+    ///
+    /// Version 2 records only hold the low 64 bits of the IDs set with [`fid`](Self::fid)
+    /// and [`parent_fid`](Self::parent_fid).
+    pub fn v3(mut self) -> Self {
+        self.major_version = 3;
+        self
+    }
+
+    pub fn usn(mut self, usn: Usn) -> Self {
+        self.usn = usn;
+        self
+    }
+
+    pub fn fid(mut self, fid: impl Into<FileId>) -> Self {
+        self.fid = fid.into();
+        self
+    }
+
+    pub fn parent_fid(mut self, parent_fid: impl Into<FileId>) -> Self {
+        self.parent_fid = parent_fid.into();
+        self
+    }
+
+    /// Sets the record time as a FILETIME (100-nanosecond intervals since 1601-01-01 UTC).
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn reason(mut self, reason: u32) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    pub fn source_info(mut self, source_info: u32) -> Self {
+        self.source_info = source_info;
+        self
+    }
+
+    pub fn security_id(mut self, security_id: u32) -> Self {
+        self.security_id = security_id;
+        self
+    }
+
+    pub fn file_attributes(mut self, file_attributes: u32) -> Self {
+        self.file_attributes = file_attributes;
+        self
+    }
+
+    /// Sets the file name from raw UTF-16 code units, which need not be valid UTF-16.
+    pub fn file_name_wide(mut self, file_name: &[u16]) -> Self {
+        self.file_name = file_name.to_vec();
+        self
+    }
+
+    /// Returns the encoded record.
+    pub fn build(&self) -> Vec<u8> {
+        let file_name_offset = if self.major_version == 3 {
+            offset_of!(USN_RECORD_V3, FileName)
+        } else {
+            offset_of!(USN_RECORD_V2, FileName)
+        };
+        let file_name_len = self.file_name.len() * size_of::<u16>();
+        let record_len = (file_name_offset + file_name_len).next_multiple_of(8);
+
+        let mut record = Vec::with_capacity(record_len);
+        record.extend_from_slice(&(record_len as u32).to_le_bytes());
+        record.extend_from_slice(&self.major_version.to_le_bytes());
+        record.extend_from_slice(&0u16.to_le_bytes()); // MinorVersion
+        if self.major_version == 3 {
+            record.extend_from_slice(&self.fid.to_le_bytes());
+            record.extend_from_slice(&self.parent_fid.to_le_bytes());
+        } else {
+            record.extend_from_slice(&(self.fid.0 as u64).to_le_bytes());
+            record.extend_from_slice(&(self.parent_fid.0 as u64).to_le_bytes());
+        }
+        record.extend_from_slice(&self.usn.to_le_bytes());
+        record.extend_from_slice(&self.timestamp.to_le_bytes());
+        record.extend_from_slice(&self.reason.to_le_bytes());
+        record.extend_from_slice(&self.source_info.to_le_bytes());
+        record.extend_from_slice(&self.security_id.to_le_bytes());
+        record.extend_from_slice(&self.file_attributes.to_le_bytes());
+        record.extend_from_slice(&(file_name_len as u16).to_le_bytes());
+        record.extend_from_slice(&(file_name_offset as u16).to_le_bytes());
+        debug_assert_eq!(record.len(), file_name_offset);
+
+        for unit in &self.file_name {
+            record.extend_from_slice(&unit.to_le_bytes());
+        }
+        record.resize(record_len, 0);
+        record
+    }
+}
+
+/// Lays out records the way `FSCTL_READ_USN_JOURNAL` returns them: the next USN to read,
+/// followed by the records back to back.
+pub fn read_buffer(next_usn: Usn, records: &[Vec<u8>]) -> Vec<u8> {
+    let mut buffer = next_usn.to_le_bytes().to_vec();
+    for record in records {
+        buffer.extend_from_slice(record);
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{UsnReason, journal::UsnEntry, reasons};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_builder_record_parses_with_usn_entry_new() {
+        // 2024-01-01T00:00:00Z as a FILETIME.
+        let timestamp = 133_485_408_000_000_000;
+        let record = UsnRecordBuilder::new("report.txt")
+            .usn(0x100)
+            .fid(0x0001_0000_0000_0042)
+            .parent_fid(0x5)
+            .timestamp(timestamp)
+            .reason(reasons::FILE_CREATE | reasons::CLOSE)
+            .source_info(0x2)
+            .file_attributes(0x20)
+            .build();
+
+        assert_eq!(record.len() % 8, 0);
+        let entry = UsnEntry::new(&record).unwrap();
+        assert_eq!(entry.usn, 0x100);
+        assert_eq!(entry.fid, 0x0001_0000_0000_0042);
+        assert_eq!(entry.parent_fid, 0x5);
+        assert_eq!(entry.reason, UsnReason::FILE_CREATE | UsnReason::CLOSE);
+        assert_eq!(entry.source_info, 0x2);
+        assert_eq!(entry.file_attributes, 0x20);
+        assert_eq!(entry.file_name, "report.txt");
+        assert_eq!(
+            entry.time,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200)
+        );
+    }
+
+    #[test]
+    fn test_builder_v3_record_layout() {
+        let fid = FileId(0x0123_4567_89AB_CDEF_0000_0000_0000_0042);
+        let record = UsnRecordBuilder::new("a.txt")
+            .v3()
+            .fid(fid)
+            .parent_fid(0x5)
+            .usn(0x100)
+            .build();
+
+        assert_eq!(u16::from_le_bytes([record[4], record[5]]), 3);
+        assert_eq!(record[8..24], fid.to_le_bytes());
+        let name_offset = offset_of!(USN_RECORD_V3, FileName);
+        assert_eq!(
+            u16::from_le_bytes([record[name_offset - 2], record[name_offset - 1]]) as usize,
+            name_offset
+        );
+
+        let entry = UsnEntry::new(&record).unwrap();
+        assert_eq!(entry.fid, fid);
+        assert_eq!(entry.parent_fid, 0x5);
+        assert_eq!(entry.usn, 0x100);
+        assert_eq!(entry.file_name, "a.txt");
+    }
+
+    #[test]
+    fn test_read_buffer_prefixes_next_usn() {
+        let records = [
+            UsnRecordBuilder::new("a.txt").build(),
+            UsnRecordBuilder::new("b.txt").build(),
+        ];
+
+        let buffer = read_buffer(0x300, &records);
+
+        assert_eq!(&buffer[..8], &0x300i64.to_le_bytes());
+        assert_eq!(buffer.len(), 8 + records[0].len() + records[1].len());
+    }
+}
@@ -2,13 +2,23 @@
 //!
 //! Provides types and logic to resolve full file paths from file IDs using MFT or USN journal data.
 
-use crate::{journal::UsnEntry, mft::MftEntry, volume::Volume};
+use crate::{
+    FileId, NameEncodingPolicy, UsnReason, UsnResult,
+    errors::UsnError,
+    journal::{self, UsnEntry},
+    mft::{MftEntry, is_root_record},
+    volume::Volume,
+};
 use lru::LruCache;
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     ffi::{OsStr, OsString, c_void},
+    fmt,
+    hash::BuildHasher,
     num::NonZeroUsize,
-    os::windows::ffi::OsStringExt,
-    path::{Component, Path, PathBuf},
+    os::windows::{ffi::OsStringExt, fs::OpenOptionsExt, io::AsRawHandle},
+    path::{Component, Path, PathBuf, Prefix},
 };
 use windows::{
     Win32::{
@@ -22,19 +32,30 @@ use windows::{
 #[allow(clippy::useless_nonzero_new_unchecked)]
 const LRU_CACHE_CAPACITY: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(4 * 1024) }; // 4K
 
+// NTFS paths are at most 32,767 characters and every component takes at least two of them.
+const MAX_PATH_INDEX_DEPTH: usize = 16 * 1024;
+
+// FILE_NAME_INFO header plus room for a MAX_PATH file name.
+const FILE_NAME_INFO_INIT_LEN: usize =
+    size_of::<u32>() + (Foundation::MAX_PATH as usize) * size_of::<u16>();
+
+/// Hasher used by [`PathResolver`]'s directory caches unless another one is chosen with
+/// [`PathResolver::new_with_cache_hasher`].
+pub type DefaultCacheHasher = lru::DefaultHasher;
+
 /// Trait for entries that can be resolved to a file path.
 pub trait PathResolvableEntry {
-    fn fid(&self) -> u64;
-    fn parent_fid(&self) -> u64;
+    fn fid(&self) -> FileId;
+    fn parent_fid(&self) -> FileId;
     fn file_name(&self) -> &OsString;
     fn is_dir(&self) -> bool;
 }
 
 impl PathResolvableEntry for MftEntry {
-    fn fid(&self) -> u64 {
+    fn fid(&self) -> FileId {
         self.fid
     }
-    fn parent_fid(&self) -> u64 {
+    fn parent_fid(&self) -> FileId {
         self.parent_fid
     }
     fn file_name(&self) -> &OsString {
@@ -46,10 +67,10 @@ impl PathResolvableEntry for MftEntry {
 }
 
 impl PathResolvableEntry for UsnEntry {
-    fn fid(&self) -> u64 {
+    fn fid(&self) -> FileId {
         self.fid
     }
-    fn parent_fid(&self) -> u64 {
+    fn parent_fid(&self) -> FileId {
         self.parent_fid
     }
     fn file_name(&self) -> &OsString {
@@ -60,11 +81,48 @@ impl PathResolvableEntry for UsnEntry {
     }
 }
 
+/// Hit and miss counters of a [`PathResolver`]'s directory cache.
+///
+/// A hit is a path resolved from the cache; a miss had to open the parent directory by ID.
+/// Returned by [`PathResolver::cache_stats`]; all zero for resolvers without a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
 /// Resolves file paths from file IDs on an NTFS/ReFS volume, optionally using an LRU cache for efficiency.
-#[derive(Debug)]
-pub struct PathResolver<'a> {
+///
+/// The resolver keeps a `FILE_NAME_INFO` buffer that is reused across lookups, so resolving
+/// many entries with the same resolver does not allocate a new buffer per file ID.
+///
+/// The directory caches hash file IDs with `S`; see [`PathResolver::new_with_cache_hasher`].
+pub struct PathResolver<'a, S = DefaultCacheHasher> {
     volume: &'a Volume,
-    dir_fid_path_cache: Option<LruCache<u64, (PathBuf, OsString)>>,
+    dir_fid_path_cache: Option<LruCache<FileId, (PathBuf, OsString), S>>,
+    /// Parent and name of directories, for [`PathResolver::resolve_ancestors`]. Enabled
+    /// together with `dir_fid_path_cache`.
+    parent_link_cache: Option<LruCache<FileId, (FileId, OsString), S>>,
+    name_info_buffer: Vec<u8>,
+    name_encoding_policy: NameEncodingPolicy,
+    extended_length_paths: bool,
+    /// Drive root to report paths under instead of the volume's mount folder.
+    drive_letter_root: Option<PathBuf>,
+    cache_stats: CacheStats,
+}
+
+impl<S: BuildHasher> fmt::Debug for PathResolver<'_, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PathResolver")
+            .field("volume", &self.volume)
+            .field("dir_fid_path_cache", &self.dir_fid_path_cache)
+            .field("parent_link_cache", &self.parent_link_cache)
+            .field("name_encoding_policy", &self.name_encoding_policy)
+            .field("extended_length_paths", &self.extended_length_paths)
+            .field("drive_letter_root", &self.drive_letter_root)
+            .field("cache_stats", &self.cache_stats)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<'a> PathResolver<'a> {
@@ -76,6 +134,12 @@ impl<'a> PathResolver<'a> {
         PathResolver {
             volume,
             dir_fid_path_cache: None,
+            parent_link_cache: None,
+            name_info_buffer: vec![0u8; FILE_NAME_INFO_INIT_LEN],
+            name_encoding_policy: NameEncodingPolicy::default(),
+            extended_length_paths: false,
+            drive_letter_root: None,
+            cache_stats: CacheStats::default(),
         }
     }
 
@@ -84,22 +148,123 @@ impl<'a> PathResolver<'a> {
     /// # Arguments
     /// * `volume` - Reference to the `Volume` struct representing the NTFS/ReFS volume.
     pub fn new_with_cache(volume: &'a Volume) -> Self {
-        let cache = LruCache::new(LRU_CACHE_CAPACITY);
+        Self::with_cache_capacity(volume, LRU_CACHE_CAPACITY)
+    }
+
+    /// Create a new caching `PathResolver` that remembers up to `capacity` directories.
+    ///
+    /// [`PathResolver::new_with_cache`] keeps 4096. A larger cache trades memory, roughly one
+    /// path per directory, for fewer `OpenFileById` calls: whole-volume scans benefit from a
+    /// capacity close to the number of directories on the volume, while a monitor watching a
+    /// few folders can use a much smaller one. [`PathResolver::cache_stats`] shows how well
+    /// the chosen size works.
+    pub fn with_cache_capacity(volume: &'a Volume, capacity: NonZeroUsize) -> Self {
         PathResolver {
             volume,
-            dir_fid_path_cache: Some(cache),
+            dir_fid_path_cache: Some(LruCache::new(capacity)),
+            parent_link_cache: Some(LruCache::new(capacity)),
+            name_info_buffer: vec![0u8; FILE_NAME_INFO_INIT_LEN],
+            name_encoding_policy: NameEncodingPolicy::default(),
+            extended_length_paths: false,
+            drive_letter_root: None,
+            cache_stats: CacheStats::default(),
         }
     }
+}
+
+impl<'a, S: BuildHasher> PathResolver<'a, S> {
+    /// Create a new caching `PathResolver` whose directory caches hash with `hash_builder`.
+    ///
+    /// Useful to plug in a faster hasher, or one that resists adversarial file IDs, e.g.
+    /// `PathResolver::new_with_cache_hasher(&volume, ahash::RandomState::new())`.
+    pub fn new_with_cache_hasher(volume: &'a Volume, hash_builder: S) -> Self
+    where
+        S: Clone,
+    {
+        Self::with_cache_capacity_and_hasher(volume, LRU_CACHE_CAPACITY, hash_builder)
+    }
+
+    /// Create a new caching `PathResolver` that remembers up to `capacity` directories and
+    /// hashes them with `hash_builder`.
+    ///
+    /// Combines [`PathResolver::with_cache_capacity`] and
+    /// [`PathResolver::new_with_cache_hasher`].
+    pub fn with_cache_capacity_and_hasher(
+        volume: &'a Volume,
+        capacity: NonZeroUsize,
+        hash_builder: S,
+    ) -> Self
+    where
+        S: Clone,
+    {
+        PathResolver {
+            volume,
+            dir_fid_path_cache: Some(LruCache::with_hasher(capacity, hash_builder.clone())),
+            parent_link_cache: Some(LruCache::with_hasher(capacity, hash_builder)),
+            name_info_buffer: vec![0u8; FILE_NAME_INFO_INIT_LEN],
+            name_encoding_policy: NameEncodingPolicy::default(),
+            extended_length_paths: false,
+            drive_letter_root: None,
+            cache_stats: CacheStats::default(),
+        }
+    }
+
+    /// Sets how resolved paths containing invalid UTF-16 are returned.
+    ///
+    /// With [`NameEncodingPolicy::Skip`], such paths are reported as unresolved. Cached
+    /// directory paths are always kept in their raw form.
+    pub fn with_name_encoding_policy(mut self, policy: NameEncodingPolicy) -> Self {
+        self.name_encoding_policy = policy;
+        self
+    }
+
+    /// Sets whether resolved paths are returned in their `\\?\` long-path form.
+    ///
+    /// Off by default. When enabled, every path this resolver returns, including through
+    /// the iterator adapters built on it, has the form described for
+    /// [`PathResolver::resolve_path_extended`]. Cached directory paths are always kept in
+    /// their short form.
+    pub fn with_extended_length_paths(mut self, enabled: bool) -> Self {
+        self.extended_length_paths = enabled;
+        self
+    }
+
+    /// Sets whether paths on a volume opened through a folder mount are reported under the
+    /// volume's drive letter instead, e.g. `E:\Docs` rather than `C:\Mounts\Data\Docs`.
+    ///
+    /// Off by default. The drive letter is looked up once, with
+    /// [`Volume::find_drive_letter`]; if the volume has none, or the lookup fails, paths keep
+    /// the mount folder form. Volumes opened by drive letter are unaffected.
+    pub fn prefer_drive_letter(mut self, enabled: bool) -> Self {
+        self.drive_letter_root = None;
+        if enabled
+            && self.volume.drive_letter.is_none()
+            && let Ok(Some(drive_letter)) = self.volume.find_drive_letter()
+        {
+            let drive_letter = drive_letter.to_ascii_uppercase();
+            self.drive_letter_root = Some(PathBuf::from(format!("{drive_letter}:\\")));
+        }
+        self
+    }
 
     pub fn resolve_path<E: PathResolvableEntry>(&mut self, entry: &E) -> Option<PathBuf> {
-        if let Some(cache) = &mut self.dir_fid_path_cache {
+        self.try_resolve_path(entry).ok()
+    }
+
+    /// Resolves the full path of an entry, returning the underlying error when it cannot be resolved.
+    pub fn try_resolve_path<E: PathResolvableEntry>(&mut self, entry: &E) -> UsnResult<PathBuf> {
+        if entry.is_dir()
+            && let Some(links) = &mut self.parent_link_cache
+        {
+            links.put(entry.fid(), (entry.parent_fid(), entry.file_name().clone()));
+        }
+        let resolved = if let Some(cache) = &mut self.dir_fid_path_cache {
             resolve_path_with_cache(
                 self.volume,
-                entry.fid(),
-                entry.parent_fid(),
-                entry.file_name(),
-                entry.is_dir(),
+                entry,
                 cache,
+                &mut self.cache_stats,
+                &mut self.name_info_buffer,
             )
         } else {
             resolve_path(
@@ -107,56 +272,570 @@ impl<'a> PathResolver<'a> {
                 entry.fid(),
                 entry.parent_fid(),
                 entry.file_name(),
+                &mut self.name_info_buffer,
+            )
+        };
+
+        let mut resolved = resolved?;
+        if let Some(drive_root) = &self.drive_letter_root
+            && let Ok(relative) = resolved.strip_prefix(volume_root_path(self.volume))
+        {
+            resolved = drive_root.join(relative);
+        }
+        if self.extended_length_paths {
+            resolved = to_extended_volume_path(self.volume, &resolved);
+        }
+        match self.name_encoding_policy.apply(resolved.as_os_str()) {
+            Some(Cow::Borrowed(_)) => Ok(resolved),
+            Some(Cow::Owned(replaced)) => Ok(PathBuf::from(replaced)),
+            None => Err(UsnError::OtherError(format!(
+                "Path is not valid UTF-16: {}",
+                resolved.display()
+            ))),
+        }
+    }
+
+    /// Resolves the path of a file from its parent directory's file ID and its name.
+    ///
+    /// Useful when only partial data was persisted and there is no entry to hand to
+    /// [`PathResolver::resolve_path`]. The parent's path is looked up in (and added to) the
+    /// cache when one is enabled. With `is_dir` set, the resolved directory is cached as well:
+    /// since its file ID is not passed in, it is read by opening the directory, which is
+    /// skipped silently if the directory no longer exists.
+    pub fn resolve_from_parts(
+        &mut self,
+        parent_fid: FileId,
+        file_name: &OsStr,
+        is_dir: bool,
+    ) -> Option<PathBuf> {
+        let path = self.resolve_dir_fid(parent_fid).ok()?.join(file_name);
+        if is_dir
+            && let Some(cache) = &mut self.dir_fid_path_cache
+            && let Ok(fid) = file_id_from_path(&path)
+        {
+            cache.put(FileId::from(fid), (path.clone(), file_name.to_os_string()));
+        }
+        Some(path)
+    }
+
+    /// Resolves the path of an entry relative to the directory `base_fid`.
+    ///
+    /// Relative paths stay valid when the volume is remounted under another drive letter or
+    /// mount point, which makes them suitable for persisting. Returns `None` if either path
+    /// cannot be resolved or the entry is not below the base directory.
+    pub fn resolve_relative<E: PathResolvableEntry>(
+        &mut self,
+        entry: &E,
+        base_fid: FileId,
+    ) -> Option<PathBuf> {
+        let base_path = self.resolve_dir_fid(base_fid).ok()?;
+        let path = self.resolve_path(entry)?;
+        path.strip_prefix(&base_path)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+    }
+
+    /// Returns the directories between the volume root and an entry, as `(fid, name)` pairs.
+    ///
+    /// The list starts with the top-level directory and ends with the entry's parent; the
+    /// root itself (record 5 on NTFS, or a directory that is its own parent, as on ReFS) ends
+    /// the walk and is not included, so entries directly in the root get an empty list. Each
+    /// directory's parent and name are read with `FSCTL_READ_FILE_USN_DATA` and, when the
+    /// resolver caches, remembered along with the directories it resolves. Returns `None` if a
+    /// directory cannot be looked up or the parent chain loops.
+    pub fn resolve_ancestors<E: PathResolvableEntry>(
+        &mut self,
+        entry: &E,
+    ) -> Option<Vec<(FileId, OsString)>> {
+        let mut ancestors = Vec::new();
+        let mut visited = HashSet::from([entry.fid()]);
+        let mut current = entry.parent_fid();
+        while !is_root_record(current) {
+            if !visited.insert(current) || ancestors.len() >= MAX_PATH_INDEX_DEPTH {
+                return None;
+            }
+            let (parent_fid, name) = self.parent_link(current).ok()?;
+            if parent_fid == current {
+                break;
+            }
+            ancestors.push((current, name));
+            current = parent_fid;
+        }
+        ancestors.reverse();
+        Some(ancestors)
+    }
+
+    /// Looks up the parent and name of the directory `dir_fid`, using the cache when enabled.
+    fn parent_link(&mut self, dir_fid: FileId) -> UsnResult<(FileId, OsString)> {
+        if let Some(link) = self
+            .parent_link_cache
+            .as_mut()
+            .and_then(|links| links.get(&dir_fid))
+        {
+            return Ok(link.clone());
+        }
+
+        let handle = open_file_by_id(self.volume.handle(), dir_fid, 0)?;
+        let entry = journal::read_file_usn_data(*handle)?;
+        let link = (entry.parent_fid, entry.file_name);
+        if let Some(links) = &mut self.parent_link_cache {
+            links.put(dir_fid, link.clone());
+        }
+        Ok(link)
+    }
+
+    /// Returns whether the file an entry refers to still exists.
+    ///
+    /// The file is opened by ID without requesting any access, and no metadata is read.
+    /// Returns `Ok(false)` if no file has the entry's ID any more, including a file record
+    /// that has since been reused for another file.
+    ///
+    /// # Errors
+    /// Returns `UsnError::WinApiError` if the file could not be opened for another reason,
+    /// such as access denied, in which case whether it exists is unknown.
+    pub fn entry_still_exists<E: PathResolvableEntry>(&self, entry: &E) -> UsnResult<bool> {
+        match open_file_by_id(self.volume.handle(), entry.fid(), 0) {
+            Ok(_) => Ok(true),
+            Err(err)
+                if [
+                    Foundation::ERROR_INVALID_PARAMETER,
+                    Foundation::ERROR_FILE_NOT_FOUND,
+                    Foundation::ERROR_PATH_NOT_FOUND,
+                ]
+                .iter()
+                .any(|&code| err.code() == code.into()) =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns how many directories the cache holds at most, or `None` without a cache.
+    pub fn cache_capacity(&self) -> Option<usize> {
+        self.dir_fid_path_cache
+            .as_ref()
+            .map(|cache| cache.cap().get())
+    }
+
+    /// Returns the directory cache's hit and miss counts since the resolver was created.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats
+    }
+
+    /// Returns the cached directories as `(fid, path, name)`, most recently used first.
+    ///
+    /// Meant for diagnosing wrongly resolved paths; the dump is a copy and does not affect the
+    /// LRU order. Empty if the resolver was created without a cache. Only available with the
+    /// `testing` feature.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn cache_dump(&self) -> Vec<(FileId, PathBuf, OsString)> {
+        self.dir_fid_path_cache
+            .iter()
+            .flat_map(|cache| cache.iter())
+            .map(|(&fid, (path, name))| (fid, path.clone(), name.clone()))
+            .collect()
+    }
+
+    /// Puts a directory into the cache, so tests elsewhere in the crate can resolve its
+    /// children on a mock volume.
+    #[cfg(test)]
+    pub(crate) fn cache_directory(&mut self, fid: FileId, path: PathBuf) {
+        if let Some(cache) = &mut self.dir_fid_path_cache {
+            let name = path
+                .file_name()
+                .map(OsStr::to_os_string)
+                .unwrap_or_default();
+            cache.put(fid, (path, name));
+        }
+    }
+
+    /// Updates the cached path of directory `fid` after it was renamed or moved to `new_path`.
+    ///
+    /// Cached directories below it are moved along. Does nothing if `fid` is not cached.
+    pub(crate) fn rename_cached_directory(
+        &mut self,
+        fid: FileId,
+        new_path: &Path,
+        new_name: &OsStr,
+    ) {
+        if let Some(links) = &mut self.parent_link_cache {
+            links.pop(&fid);
+        }
+        let Some(cache) = &mut self.dir_fid_path_cache else {
+            return;
+        };
+        let Some((old_path, _)) = cache.pop(&fid) else {
+            return;
+        };
+        for (_, (path, _)) in cache.iter_mut() {
+            if let Ok(rest) = path.strip_prefix(&old_path) {
+                let moved = new_path.join(rest);
+                *path = moved;
+            }
+        }
+        cache.put(fid, (new_path.to_path_buf(), new_name.to_os_string()));
+    }
+
+    /// Drops directory `fid` from the caches, e.g. after it was deleted.
+    pub(crate) fn forget_directory(&mut self, fid: FileId) {
+        if let Some(links) = &mut self.parent_link_cache {
+            links.pop(&fid);
+        }
+        if let Some(cache) = &mut self.dir_fid_path_cache {
+            cache.pop(&fid);
+        }
+    }
+
+    /// Resolves the path of a directory from its file ID, using the cache when enabled.
+    pub(crate) fn resolve_dir_fid(&mut self, dir_fid: FileId) -> UsnResult<PathBuf> {
+        let dir_path = if let Some(cache) = &mut self.dir_fid_path_cache {
+            cached_dir_path(
+                self.volume,
+                dir_fid,
+                cache,
+                &mut self.cache_stats,
+                &mut self.name_info_buffer,
             )
+        } else {
+            file_id_to_path(self.volume, dir_fid, &mut self.name_info_buffer)
+        };
+
+        Ok(dir_path?)
+    }
+
+    /// Resolves the full path of an entry in its `\\?\` long-path form.
+    ///
+    /// The result can be passed directly to `CreateFileW` and `std::fs` even when it exceeds
+    /// `MAX_PATH`. Paths on drive-letter volumes start with `\\?\C:\`; paths on volumes
+    /// without a drive letter start with the volume GUID path, `\\?\Volume{GUID}\`, when it
+    /// can be looked up. Use [`PathResolver::resolve_path`] for paths shown to users.
+    pub fn resolve_path_extended<E: PathResolvableEntry>(&mut self, entry: &E) -> Option<PathBuf> {
+        self.resolve_path(entry)
+            .map(|path| to_extended_volume_path(self.volume, &path))
+    }
+}
+
+/// Iterator adapter that pairs each entry with its resolved path.
+///
+/// Created by [`UsnJournalIter::resolve_paths`](crate::journal::UsnJournalIter::resolve_paths),
+/// or as a [`WithPaths`] by `with_paths`.
+/// Entries whose path cannot be resolved are still yielded (with `None`), and the
+/// `on_unresolved` callback is invoked with the entry and the resolution error.
+pub struct ResolvePaths<'r, 'v, I, F, S = DefaultCacheHasher> {
+    iter: I,
+    resolver: &'r mut PathResolver<'v, S>,
+    on_unresolved: F,
+}
+
+/// [`ResolvePaths`] without a callback, pairing each entry of type `E` with its path.
+///
+/// Created by [`UsnJournalIter::with_paths`](crate::journal::UsnJournalIter::with_paths) and
+/// [`MftIter::with_paths`](crate::mft::MftIter::with_paths).
+pub type WithPaths<'r, 'v, I, E, S = DefaultCacheHasher> =
+    ResolvePaths<'r, 'v, I, fn(&E, UsnError), S>;
+
+impl<'r, 'v, I, E, S> WithPaths<'r, 'v, I, E, S> {
+    pub(crate) fn without_callback(iter: I, resolver: &'r mut PathResolver<'v, S>) -> Self {
+        ResolvePaths::new(iter, resolver, |_, _| {})
+    }
+}
+
+impl<'r, 'v, I, F, S> ResolvePaths<'r, 'v, I, F, S> {
+    pub(crate) fn new(iter: I, resolver: &'r mut PathResolver<'v, S>, on_unresolved: F) -> Self {
+        ResolvePaths {
+            iter,
+            resolver,
+            on_unresolved,
         }
     }
 }
 
+impl<I, E, F, S> Iterator for ResolvePaths<'_, '_, I, F, S>
+where
+    I: Iterator<Item = UsnResult<E>>,
+    E: PathResolvableEntry,
+    F: FnMut(&E, UsnError),
+    S: BuildHasher,
+{
+    type Item = UsnResult<(E, Option<PathBuf>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.iter.next()? {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err)),
+        };
+
+        match self.resolver.try_resolve_path(&entry) {
+            Ok(path) => Some(Ok((entry, Some(path)))),
+            Err(err) => {
+                (self.on_unresolved)(&entry, err);
+                Some(Ok((entry, None)))
+            }
+        }
+    }
+}
+
+/// Iterator adapter that yields only creations inside a directory subtree.
+///
+/// Created by [`UsnJournalIter::creations_under`](crate::journal::UsnJournalIter::creations_under).
+/// Each `FILE_CREATE` record is yielded with its resolved path when its parent is a directory
+/// created inside the subtree during iteration, or when its resolved path lies below the
+/// subtree root. Directories created inside the subtree extend the scope, so their contents
+/// are reported even when the directory itself is gone by the time they are read. The file
+/// IDs of those directories are kept for the lifetime of the adapter.
+pub struct CreationsUnder<'r, 'v, I, S = DefaultCacheHasher> {
+    iter: I,
+    resolver: &'r mut PathResolver<'v, S>,
+    root_path: PathBuf,
+    scope: HashSet<FileId>,
+}
+
+impl<'r, 'v, I, S: BuildHasher> CreationsUnder<'r, 'v, I, S> {
+    pub(crate) fn new(
+        iter: I,
+        resolver: &'r mut PathResolver<'v, S>,
+        root_fid: FileId,
+    ) -> UsnResult<Self> {
+        let root_path = resolver.resolve_dir_fid(root_fid)?;
+        Ok(CreationsUnder {
+            iter,
+            resolver,
+            root_path,
+            scope: HashSet::from([root_fid]),
+        })
+    }
+}
+
+impl<I, S> Iterator for CreationsUnder<'_, '_, I, S>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+    S: BuildHasher,
+{
+    type Item = UsnResult<(UsnEntry, Option<PathBuf>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.iter.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            if !entry.reason.contains(UsnReason::FILE_CREATE) {
+                continue;
+            }
+
+            let path = self.resolver.resolve_path(&entry);
+            let inside = self.scope.contains(&entry.parent_fid)
+                || path
+                    .as_ref()
+                    .is_some_and(|p| p != &self.root_path && p.starts_with(&self.root_path));
+            if !inside {
+                continue;
+            }
+
+            if entry.is_dir() {
+                self.scope.insert(entry.fid);
+            }
+            return Some(Ok((entry, path)));
+        }
+    }
+}
+
+/// Iterator adapter that drops entries located inside any of a set of directory subtrees.
+///
+/// Created by [`UsnJournalIter::ignore_subtrees`](crate::journal::UsnJournalIter::ignore_subtrees).
+/// An entry is dropped when its parent directory is one of the ignored roots or its path lies
+/// below one of them; changes to the roots themselves are kept. The decision is remembered
+/// per parent directory for the lifetime of the adapter, so a directory moved into or out of
+/// an ignored subtree afterwards keeps its earlier decision. Entries whose parent cannot be
+/// resolved, e.g. because it was deleted, are kept.
+pub struct IgnoreSubtrees<'r, 'v, I, S = DefaultCacheHasher> {
+    iter: I,
+    resolver: &'r mut PathResolver<'v, S>,
+    root_paths: Vec<PathBuf>,
+    ignored_dirs: HashMap<FileId, bool>,
+}
+
+impl<'r, 'v, I, S: BuildHasher> IgnoreSubtrees<'r, 'v, I, S> {
+    pub(crate) fn new(
+        iter: I,
+        resolver: &'r mut PathResolver<'v, S>,
+        roots: &[FileId],
+    ) -> UsnResult<Self> {
+        let root_paths = roots
+            .iter()
+            .map(|&root_fid| resolver.resolve_dir_fid(root_fid))
+            .collect::<UsnResult<Vec<_>>>()?;
+        Ok(IgnoreSubtrees {
+            iter,
+            resolver,
+            root_paths,
+            ignored_dirs: roots.iter().map(|&root_fid| (root_fid, true)).collect(),
+        })
+    }
+
+    fn is_ignored_dir(&mut self, dir_fid: FileId) -> bool {
+        if let Some(&ignored) = self.ignored_dirs.get(&dir_fid) {
+            return ignored;
+        }
+
+        let Ok(dir_path) = self.resolver.resolve_dir_fid(dir_fid) else {
+            return false;
+        };
+        let ignored = self
+            .root_paths
+            .iter()
+            .any(|root| dir_path.starts_with(root));
+        self.ignored_dirs.insert(dir_fid, ignored);
+        ignored
+    }
+}
+
+impl<I, S> Iterator for IgnoreSubtrees<'_, '_, I, S>
+where
+    I: Iterator<Item = UsnResult<UsnEntry>>,
+    S: BuildHasher,
+{
+    type Item = UsnResult<UsnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.iter.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            if !self.is_ignored_dir(entry.parent_fid) {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
+
+/// In-memory index of parent links and names, for resolving paths without opening files.
+///
+/// Created by [`Mft::path_index`](crate::mft::Mft::path_index) from a single MFT pass.
+/// Unlike [`PathResolver`], resolving a path issues no Windows API calls, so it neither fails
+/// on files that cannot be opened nor costs a handle per lookup; the index holds one entry per
+/// file on the volume, and reflects the volume as it was when the MFT was enumerated.
+#[derive(Debug, Clone)]
+pub struct PathIndex {
+    root_path: PathBuf,
+    parents: HashMap<FileId, (FileId, OsString)>,
+}
+
+impl PathIndex {
+    /// Builds an index from MFT entries, resolving paths below `root_path`.
+    pub(crate) fn from_entries(
+        root_path: PathBuf,
+        entries: impl IntoIterator<Item = MftEntry>,
+    ) -> Self {
+        let parents = entries
+            .into_iter()
+            .map(|entry| (entry.fid, (entry.parent_fid, entry.file_name)))
+            .collect();
+        PathIndex { root_path, parents }
+    }
+
+    /// Resolves the full path of `fid` by walking its parents up to the volume root.
+    ///
+    /// Returns `None` if `fid` or one of its ancestors is missing from the index, or if the
+    /// parent chain loops or is deeper than any valid NTFS path.
+    pub fn resolve(&self, fid: FileId) -> Option<PathBuf> {
+        walk_parent_links(
+            fid,
+            |fid| is_root_record(fid).then(|| self.root_path.clone()),
+            |fid| {
+                let (parent_fid, name) = self.parents.get(&fid)?;
+                Some((*parent_fid, name.as_os_str()))
+            },
+        )
+    }
+
+    /// Returns the number of files in the index.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Returns true if the index holds no files.
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+/// Builds the path of `fid` by walking in-memory parent links until a directory of known path.
+///
+/// `known_path` returns the path of a directory the walk can stop at, such as the volume root;
+/// `parent_link` returns the parent and name of any other file. Returns `None` if a link is
+/// missing, or if the parent chain loops or is deeper than any valid NTFS path.
+pub(crate) fn walk_parent_links<'a>(
+    fid: FileId,
+    mut known_path: impl FnMut(FileId) -> Option<PathBuf>,
+    mut parent_link: impl FnMut(FileId) -> Option<(FileId, &'a OsStr)>,
+) -> Option<PathBuf> {
+    let mut names = Vec::new();
+    let mut current = fid;
+    loop {
+        if let Some(mut path) = known_path(current) {
+            path.extend(names.iter().rev());
+            return Some(path);
+        }
+        if names.len() >= MAX_PATH_INDEX_DEPTH {
+            return None;
+        }
+        let (parent_fid, name) = parent_link(current)?;
+        if parent_fid == current {
+            return None;
+        }
+        names.push(name);
+        current = parent_fid;
+    }
+}
+
 fn resolve_path(
     volume: &Volume,
-    fid: u64,
-    parent_fid: u64,
+    fid: FileId,
+    parent_fid: FileId,
     file_name: &OsString,
-) -> Option<PathBuf> {
-    if let Ok(resolved_parent_path) = file_id_to_path(volume, parent_fid) {
-        return Some(join_resolved_path(
+    info_buffer: &mut Vec<u8>,
+) -> windows::core::Result<PathBuf> {
+    if let Ok(resolved_parent_path) = file_id_to_path(volume, parent_fid, info_buffer) {
+        return Ok(join_resolved_path(
             &resolved_parent_path,
             fid,
             parent_fid,
             file_name,
         ));
-    } else if let Ok(resolved_path) = file_id_to_path(volume, fid) {
-        return Some(resolved_path);
     }
 
-    None
+    file_id_to_path(volume, fid, info_buffer)
 }
 
 /// Internal: Resolve the full path from file ID, parent file ID, and file name.
 ///
 /// # Arguments
-/// * `fid` - File ID of the target file.
-/// * `parent_fid` - File ID of the parent directory.
-/// * `file_name` - File or directory name.
-/// * `is_dir` - Indicates if the target is a directory.
+/// * `entry` - The target file or directory, with its parent file ID and name.
+/// * `stats` - Hit and miss counters, updated for the lookup.
+/// * `info_buffer` - Reusable buffer for `FILE_NAME_INFO` lookups.
 ///
 /// # Returns
-/// * `Some(PathBuf)` - The resolved path if found.
-/// * `None` - If the path cannot be resolved.
-fn resolve_path_with_cache(
+/// * `Ok(PathBuf)` - The resolved path if found.
+/// * `Err(windows::core::Error)` - If the parent path cannot be resolved.
+fn resolve_path_with_cache<E: PathResolvableEntry, S: BuildHasher>(
     volume: &Volume,
-    fid: u64,
-    parent_fid: u64,
-    file_name: &OsString,
-    is_dir: bool,
-    cache: &mut LruCache<u64, (PathBuf, OsString)>,
-) -> Option<PathBuf> {
+    entry: &E,
+    cache: &mut LruCache<FileId, (PathBuf, OsString), S>,
+    stats: &mut CacheStats,
+    info_buffer: &mut Vec<u8>,
+) -> windows::core::Result<PathBuf> {
+    let (fid, parent_fid, file_name) = (entry.fid(), entry.parent_fid(), entry.file_name());
     // 1. Check cache for the current FID.
     if let Some((cached_path, cached_file_name)) = cache.get(&fid) {
         // If the FID is in cache, check if the filename matches the one used to create the cached path.
         if cached_file_name == file_name {
             // Names match. The cached path is valid for this FID with this name.
-            return Some(cached_path.clone());
+            stats.hits += 1;
+            return Ok(cached_path.clone());
         } else {
             // Names differ. This means the directory (fid) was renamed since it was cached.
             // The cached_path is stale because its last component is the old name.
@@ -170,46 +849,54 @@ fn resolve_path_with_cache(
     // or it wasn't in cache at all.
 
     // 2. Try to get the parent directory's path.
-    let parent_dir_path: PathBuf;
-
-    // 2a. Check cache for parent_fid.
-    if let Some((cached_parent_path, _)) = cache.get(&parent_fid) {
-        // We use the cached_parent_path. If the parent itself was renamed, this path might be
-        // stale. However, this strategy prioritizes using the cache. The check for 'fid' above
-        // handles if 'fid' itself was renamed. If this cached_parent_path leads to issues,
-        // eventually the parent's entry might get updated when it's resolved directly.
-        parent_dir_path = cached_parent_path.clone();
-    }
-    // 2b. Parent not in cache, resolve it from the file system.
-    else if let Ok(resolved_parent_path) = file_id_to_path(volume, parent_fid) {
-        parent_dir_path = resolved_parent_path;
-        // Cache this newly resolved parent path.
-        // The name stored is the actual name of the parent directory as resolved.
-        let parent_actual_name = parent_dir_path
-            .file_name()
-            .map_or_else(OsString::new, |s| s.to_os_string());
-        cache.put(parent_fid, (parent_dir_path.clone(), parent_actual_name));
-    }
-    // 2c. Parent path could not be resolved.
-    else {
-        return None; // Cannot determine parent path.
-    }
+    let parent_dir_path = cached_dir_path(volume, parent_fid, cache, stats, info_buffer)?;
 
     // 3. Construct the current item's path using the parent's path and the current file_name.
     let current_path = join_resolved_path(&parent_dir_path, fid, parent_fid, file_name);
 
     // 4. If the current item is a directory, cache its path and current name.
-    if is_dir {
+    if entry.is_dir() {
         cache.put(fid, (current_path.clone(), file_name.clone()));
     }
 
-    Some(current_path)
+    Ok(current_path)
 }
 
-fn join_resolved_path(
+/// Internal: Look up a directory's path in the cache, resolving and caching it on a miss.
+fn cached_dir_path<S: BuildHasher>(
+    volume: &Volume,
+    dir_fid: FileId,
+    cache: &mut LruCache<FileId, (PathBuf, OsString), S>,
+    stats: &mut CacheStats,
+    info_buffer: &mut Vec<u8>,
+) -> windows::core::Result<PathBuf> {
+    // Check cache for dir_fid.
+    if let Some((cached_dir_path, _)) = cache.get(&dir_fid) {
+        stats.hits += 1;
+        // We use the cached_dir_path. If the directory itself was renamed, this path might be
+        // stale. However, this strategy prioritizes using the cache. If this cached_dir_path
+        // leads to issues, eventually the directory's entry might get updated when it's
+        // resolved directly.
+        return Ok(cached_dir_path.clone());
+    }
+
+    // Not in cache, resolve it from the file system.
+    // Propagate the error if the path could not be resolved.
+    stats.misses += 1;
+    let dir_path = file_id_to_path(volume, dir_fid, info_buffer)?;
+    // Cache this newly resolved path.
+    // The name stored is the actual name of the directory as resolved.
+    let dir_actual_name = dir_path
+        .file_name()
+        .map_or_else(OsString::new, |s| s.to_os_string());
+    cache.put(dir_fid, (dir_path.clone(), dir_actual_name));
+    Ok(dir_path)
+}
+
+pub(crate) fn join_resolved_path(
     parent_dir_path: &Path,
-    fid: u64,
-    parent_fid: u64,
+    fid: FileId,
+    parent_fid: FileId,
     file_name: &OsStr,
 ) -> PathBuf {
     // NTFS can surface the volume root as a self-entry in USN/MFT data where
@@ -236,44 +923,72 @@ fn push_volume_relative_path(base_path: &mut PathBuf, volume_relative_path: &Pat
     }
 }
 
-/// Resolves a file ID to its full path on the specified NTFS/ReFS volume.
-fn file_id_to_path(volume: &Volume, file_id: u64) -> windows::core::Result<PathBuf> {
-    let file_id_desc = FILE_ID_DESCRIPTOR {
-        Type: FileSystem::FileIdType,
-        dwSize: size_of::<FileSystem::FILE_ID_DESCRIPTOR>() as u32,
-        Anonymous: FileSystem::FILE_ID_DESCRIPTOR_0 {
-            FileId: file_id.try_into()?,
+/// Opens the file or directory with the given ID, requesting `desired_access`.
+pub(crate) fn open_file_by_id(
+    volume_handle: Foundation::HANDLE,
+    file_id: FileId,
+    desired_access: u32,
+) -> windows::core::Result<Owned<Foundation::HANDLE>> {
+    // 64-bit NTFS IDs use the classic descriptor; anything wider needs a FILE_ID_128.
+    let file_id_desc = match file_id.as_u64() {
+        Some(file_id) => FILE_ID_DESCRIPTOR {
+            Type: FileSystem::FileIdType,
+            dwSize: size_of::<FileSystem::FILE_ID_DESCRIPTOR>() as u32,
+            Anonymous: FileSystem::FILE_ID_DESCRIPTOR_0 {
+                FileId: file_id.try_into()?,
+            },
+        },
+        None => FILE_ID_DESCRIPTOR {
+            Type: FileSystem::ExtendedFileIdType,
+            dwSize: size_of::<FileSystem::FILE_ID_DESCRIPTOR>() as u32,
+            Anonymous: FileSystem::FILE_ID_DESCRIPTOR_0 {
+                ExtendedFileId: FileSystem::FILE_ID_128 {
+                    Identifier: file_id.to_le_bytes(),
+                },
+            },
         },
     };
 
-    let file_handle = unsafe {
-        Owned::new(FileSystem::OpenFileById(
-            volume.handle(),
+    unsafe {
+        Ok(Owned::new(FileSystem::OpenFileById(
+            volume_handle,
             &file_id_desc,
-            FileSystem::FILE_GENERIC_READ.0,
+            desired_access,
             FileSystem::FILE_SHARE_READ
                 | FileSystem::FILE_SHARE_WRITE
                 | FileSystem::FILE_SHARE_DELETE,
             None,
             FILE_FLAG_BACKUP_SEMANTICS,
-        )?)
-    };
+        )?))
+    }
+}
 
-    let init_len = size_of::<u32>() + (Foundation::MAX_PATH as usize) * size_of::<u16>();
-    let mut info_buffer = vec![0u8; init_len];
+/// Resolves a file ID to its full path on the specified NTFS/ReFS volume.
+///
+/// `info_buffer` is reused across calls; it only grows when a path does not fit.
+fn file_id_to_path(
+    volume: &Volume,
+    file_id: FileId,
+    info_buffer: &mut Vec<u8>,
+) -> windows::core::Result<PathBuf> {
+    let file_handle = open_file_by_id(volume.handle(), file_id, FileSystem::FILE_GENERIC_READ.0)?;
+
+    if info_buffer.len() < FILE_NAME_INFO_INIT_LEN {
+        info_buffer.resize(FILE_NAME_INFO_INIT_LEN, 0);
+    }
 
     loop {
         if let Err(err) = unsafe {
             FileSystem::GetFileInformationByHandleEx(
                 *file_handle,
                 FileSystem::FileNameInfo,
-                &mut *info_buffer as *mut _ as *mut c_void,
+                info_buffer.as_mut_ptr() as *mut c_void,
                 info_buffer.len() as u32,
             )
         } {
             if err.code() == Foundation::ERROR_MORE_DATA.into() {
                 // Long paths, needs to extend buffer size to hold it.
-                let name_len = read_u32_le(&info_buffer, 0).ok_or_else(|| {
+                let name_len = read_u32_le(info_buffer, 0).ok_or_else(|| {
                     windows::core::Error::new(
                         Foundation::ERROR_INVALID_DATA.to_hresult(),
                         "Invalid FILE_NAME_INFO header",
@@ -292,7 +1007,7 @@ fn file_id_to_path(volume: &Volume, file_id: u64) -> windows::core::Result<PathB
 
         break;
     }
-    let file_name_len_bytes = read_u32_le(&info_buffer, 0).ok_or_else(|| {
+    let file_name_len_bytes = read_u32_le(info_buffer, 0).ok_or_else(|| {
         windows::core::Error::new(
             Foundation::ERROR_INVALID_DATA.to_hresult(),
             "Invalid FILE_NAME_INFO header",
@@ -323,8 +1038,94 @@ fn file_id_to_path(volume: &Volume, file_id: u64) -> windows::core::Result<PathB
     }
     let sub_path = OsString::from_wide(&name_u16);
 
-    // Create the full path directly with a single allocation
-    let mut full_path = PathBuf::new();
+    let mut full_path = volume_root_path(volume);
+    push_volume_relative_path(&mut full_path, Path::new(&sub_path));
+    Ok(full_path)
+}
+
+/// Opens the file or directory at `path` without requesting any access rights, so this
+/// works for files that other processes hold open.
+pub(crate) fn open_for_query(path: &Path) -> UsnResult<std::fs::File> {
+    Ok(std::fs::OpenOptions::new()
+        .access_mode(0)
+        .share_mode(
+            (FileSystem::FILE_SHARE_READ
+                | FileSystem::FILE_SHARE_WRITE
+                | FileSystem::FILE_SHARE_DELETE)
+                .0,
+        )
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+        .open(path)?)
+}
+
+/// Returns the file reference number of the file or directory at `path`.
+///
+/// See [`open_for_query`] for how the file is opened.
+pub(crate) fn file_id_from_path(path: &Path) -> UsnResult<u64> {
+    let file = open_for_query(path)?;
+
+    let mut info = FileSystem::BY_HANDLE_FILE_INFORMATION::default();
+    unsafe {
+        FileSystem::GetFileInformationByHandle(Foundation::HANDLE(file.as_raw_handle()), &mut info)?
+    };
+
+    Ok((u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow))
+}
+
+/// Converts an absolute path to its `\\?\` extended-length form.
+///
+/// Drive paths gain the `\\?\` prefix, UNC paths become `\\?\UNC\server\share\...`
+/// and `\\.\` device paths switch to `\\?\`. Paths that are already verbatim, or that are
+/// relative, are returned unchanged.
+pub(crate) fn to_extended_length_path(path: &Path) -> PathBuf {
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return path.to_path_buf();
+    };
+
+    let mut extended = OsString::from(r"\\?\");
+    match prefix.kind() {
+        Prefix::Verbatim(_) | Prefix::VerbatimUNC(..) | Prefix::VerbatimDisk(_) => {
+            return path.to_path_buf();
+        }
+        Prefix::Disk(_) => {
+            extended.push(path.as_os_str());
+            return PathBuf::from(extended);
+        }
+        Prefix::UNC(server, share) => {
+            extended.push(r"UNC\");
+            extended.push(server);
+            extended.push(r"\");
+            extended.push(share);
+        }
+        Prefix::DeviceNS(device) => extended.push(device),
+    }
+
+    let mut extended = PathBuf::from(extended);
+    extended.push(components.as_path());
+    extended
+}
+
+/// Converts a path resolved on `volume` to its `\\?\` extended-length form.
+///
+/// Volumes without a drive letter are rooted at their volume GUID path, which stays valid
+/// when the mount folder is moved or itself exceeds `MAX_PATH`. If the GUID path cannot be
+/// looked up, the path is converted with [`to_extended_length_path`].
+fn to_extended_volume_path(volume: &Volume, path: &Path) -> PathBuf {
+    if volume.drive_letter.is_none()
+        && let Ok(guid_path) = volume.guid_path()
+        && let Ok(relative) = path.strip_prefix(volume_root_path(volume))
+    {
+        let mut extended = PathBuf::from(guid_path);
+        extended.push(relative);
+        return extended;
+    }
+    to_extended_length_path(path)
+}
+
+/// Returns the path under which the volume's files are reported, e.g. `C:\` or the mount point.
+pub(crate) fn volume_root_path(volume: &Volume) -> PathBuf {
+    let mut root_path = PathBuf::new();
 
     if let Some(drive_letter) = volume.drive_letter {
         let drive_letter = if drive_letter.is_ascii_lowercase() {
@@ -333,13 +1134,12 @@ fn file_id_to_path(volume: &Volume, file_id: u64) -> windows::core::Result<PathB
             drive_letter
         };
 
-        full_path.push(format!("{drive_letter}:\\"));
+        root_path.push(format!("{drive_letter}:\\"));
     } else if let Some(mount_point) = &volume.mount_point {
-        full_path.push(mount_point);
+        root_path.push(mount_point);
     }
 
-    push_volume_relative_path(&mut full_path, Path::new(&sub_path));
-    Ok(full_path)
+    root_path
 }
 
 fn read_u32_le(buffer: &[u8], offset: usize) -> Option<u32> {
@@ -364,11 +1164,11 @@ mod tests {
     }
 
     impl PathResolvableEntry for MockEntry {
-        fn fid(&self) -> u64 {
-            self.fid
+        fn fid(&self) -> FileId {
+            self.fid.into()
         }
-        fn parent_fid(&self) -> u64 {
-            self.parent_fid
+        fn parent_fid(&self) -> FileId {
+            self.parent_fid.into()
         }
         fn file_name(&self) -> &OsString {
             &self.file_name
@@ -384,7 +1184,7 @@ mod tests {
 
     #[test]
     fn test_join_resolved_path_keeps_root_self_entry_at_volume_root() {
-        let path = join_resolved_path(Path::new(r"C:\"), 0x5, 0x5, OsStr::new("."));
+        let path = join_resolved_path(Path::new(r"C:\"), FileId(0x5), FileId(0x5), OsStr::new("."));
 
         assert_eq!(path, PathBuf::from(r"C:\"));
     }
@@ -401,10 +1201,12 @@ mod tests {
     fn test_mft_entry_path_resolvable_trait() {
         let entry = MftEntry {
             usn: 0x1000,
-            fid: 0x123456,
-            parent_fid: 0x654321,
+            fid: FileId(0x123456),
+            parent_fid: FileId(0x654321),
             file_name: OsString::from("test.txt"),
             file_attributes: 0,
+            timestamps: None,
+            sizes: None,
         };
 
         assert_eq!(entry.fid(), 0x123456);
@@ -418,9 +1220,9 @@ mod tests {
         let entry = crate::journal::UsnEntry {
             usn: 0x2000,
             time: SystemTime::UNIX_EPOCH,
-            fid: 0x789ABC,
-            parent_fid: 0xDEF123,
-            reason: 0x80000000,
+            fid: FileId(0x789ABC),
+            parent_fid: FileId(0xDEF123),
+            reason: UsnReason::CLOSE,
             source_info: 0,
             file_name: OsString::from("document.txt"),
             file_attributes: 0,
@@ -441,7 +1243,7 @@ mod tests {
         let cached_path = std::path::PathBuf::from("C:\\Documents\\Folder");
         let cached_name = OsString::from("test.txt");
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x123456, (cached_path.clone(), cached_name.clone()));
+            cache.put(FileId(0x123456), (cached_path.clone(), cached_name.clone()));
         }
 
         let entry = MockEntry {
@@ -457,6 +1259,76 @@ mod tests {
         assert_eq!(path, cached_path);
     }
 
+    #[test]
+    fn test_cache_dump_lists_entries_most_recent_first() {
+        let volume = create_mock_volume();
+        assert!(PathResolver::new(&volume).cache_dump().is_empty());
+
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Docs"), OsString::from("Docs")),
+            );
+            cache.put(
+                FileId(0x20),
+                (PathBuf::from(r"C:\Docs\Sub"), OsString::from("Sub")),
+            );
+        }
+        // Resolving a child of 0x10 makes it the most recently used entry.
+        assert_eq!(
+            resolver.resolve_from_parts(FileId(0x10), OsStr::new("a.txt"), false),
+            Some(PathBuf::from(r"C:\Docs\a.txt"))
+        );
+
+        assert_eq!(
+            resolver.cache_dump(),
+            vec![
+                (
+                    FileId(0x10),
+                    PathBuf::from(r"C:\Docs"),
+                    OsString::from("Docs")
+                ),
+                (
+                    FileId(0x20),
+                    PathBuf::from(r"C:\Docs\Sub"),
+                    OsString::from("Sub")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_path_with_custom_cache_hasher() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache_hasher(
+            &volume,
+            std::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default(),
+        );
+
+        let cached_parent_path = std::path::PathBuf::from("C:\\Documents");
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x654321),
+                (cached_parent_path, OsString::from("Documents")),
+            );
+        }
+
+        let entry = MockEntry {
+            fid: 0x123456,
+            parent_fid: 0x654321,
+            file_name: OsString::from("newfile.txt"),
+            is_dir: false,
+        };
+
+        let path = resolver.resolve_path(&entry).unwrap();
+        assert_eq!(path.to_string_lossy(), "C:\\Documents\\newfile.txt");
+        assert_eq!(
+            resolver.resolve_from_parts(FileId(0x654321), OsStr::new("other.txt"), false),
+            Some(std::path::PathBuf::from("C:\\Documents\\other.txt"))
+        );
+    }
+
     #[test]
     fn test_resolve_path_with_cache_miss_parent_hit() {
         let volume = create_mock_volume();
@@ -466,7 +1338,10 @@ mod tests {
         let cached_parent_path = std::path::PathBuf::from("C:\\Documents");
         let cached_parent_name = OsString::from("Documents");
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x654321, (cached_parent_path.clone(), cached_parent_name));
+            cache.put(
+                FileId(0x654321),
+                (cached_parent_path.clone(), cached_parent_name),
+            );
         }
 
         let entry = MockEntry {
@@ -482,6 +1357,64 @@ mod tests {
         assert_eq!(path.to_string_lossy(), "C:\\Documents\\newfile.txt");
     }
 
+    #[test]
+    fn test_resolve_from_parts_with_cached_parent() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+
+        // Pre-populate cache with parent directory
+        let cached_parent_path = std::path::PathBuf::from("C:\\Documents");
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x654321),
+                (cached_parent_path, OsString::from("Documents")),
+            );
+        }
+
+        let path =
+            resolver.resolve_from_parts(FileId(0x654321), OsStr::new("persisted.txt"), false);
+        assert_eq!(
+            path,
+            Some(std::path::PathBuf::from("C:\\Documents\\persisted.txt"))
+        );
+
+        // An unknown parent on the mock volume cannot be resolved.
+        assert!(
+            resolver
+                .resolve_from_parts(FileId(0x111111), OsStr::new("lost.txt"), false)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_resolve_from_parts_caches_directories() -> Result<(), UsnError> {
+        let parent = std::env::temp_dir();
+        let dir = parent.join("usn-journal-rs-resolve-from-parts");
+        std::fs::create_dir_all(&dir)?;
+        let dir_fid = FileId::from(file_id_from_path(&dir)?);
+
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        resolver.cache_directory(FileId(0x10), parent.clone());
+        let file = resolver.resolve_from_parts(FileId(0x10), OsStr::new("plain.txt"), false);
+        let resolved = resolver.resolve_from_parts(
+            FileId(0x10),
+            OsStr::new("usn-journal-rs-resolve-from-parts"),
+            true,
+        );
+        let cached = resolver
+            .cache_dump()
+            .into_iter()
+            .map(|(fid, path, _)| (fid, path))
+            .collect::<Vec<_>>();
+        std::fs::remove_dir(&dir)?;
+
+        assert_eq!(file, Some(parent.join("plain.txt")));
+        assert_eq!(resolved, Some(dir.clone()));
+        assert_eq!(cached, vec![(dir_fid, dir), (FileId(0x10), parent)]);
+        Ok(())
+    }
+
     #[test]
     fn test_resolve_path_with_cache_directory_caching() {
         let volume = create_mock_volume();
@@ -491,7 +1424,10 @@ mod tests {
         let cached_parent_path = std::path::PathBuf::from("C:\\Documents");
         let cached_parent_name = OsString::from("Documents");
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x654321, (cached_parent_path.clone(), cached_parent_name));
+            cache.put(
+                FileId(0x654321),
+                (cached_parent_path.clone(), cached_parent_name),
+            );
         }
 
         let entry = MockEntry {
@@ -508,8 +1444,8 @@ mod tests {
 
         // Verify the directory was cached
         if let Some(ref cache) = resolver.dir_fid_path_cache {
-            assert!(cache.peek(&0x123456).is_some());
-            let (cached_path, cached_name) = cache.peek(&0x123456).unwrap();
+            assert!(cache.peek(&FileId(0x123456)).is_some());
+            let (cached_path, cached_name) = cache.peek(&FileId(0x123456)).unwrap();
             assert_eq!(cached_path, &path);
             assert_eq!(cached_name, &OsString::from("NewFolder"));
         }
@@ -524,14 +1460,17 @@ mod tests {
         let cached_path = std::path::PathBuf::from("C:\\Documents\\OldName");
         let cached_old_name = OsString::from("OldName");
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x123456, (cached_path.clone(), cached_old_name));
+            cache.put(FileId(0x123456), (cached_path.clone(), cached_old_name));
         }
 
         // Pre-populate parent cache
         let cached_parent_path = std::path::PathBuf::from("C:\\Documents");
         let cached_parent_name = OsString::from("Documents");
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x654321, (cached_parent_path.clone(), cached_parent_name));
+            cache.put(
+                FileId(0x654321),
+                (cached_parent_path.clone(), cached_parent_name),
+            );
         }
 
         let entry = MockEntry {
@@ -548,7 +1487,7 @@ mod tests {
 
         // Verify the cache was updated with the new name
         if let Some(ref cache) = resolver.dir_fid_path_cache {
-            let (updated_path, updated_name) = cache.peek(&0x123456).unwrap();
+            let (updated_path, updated_name) = cache.peek(&FileId(0x123456)).unwrap();
             assert_eq!(updated_path.to_string_lossy(), "C:\\Documents\\NewName");
             assert_eq!(updated_name, &OsString::from("NewName"));
         }
@@ -560,7 +1499,7 @@ mod tests {
         let mut resolver = PathResolver::new_with_cache(&volume);
 
         if let Some(ref mut cache) = resolver.dir_fid_path_cache {
-            cache.put(0x1, (PathBuf::from(r"C:\"), OsString::new()));
+            cache.put(FileId(0x1), (PathBuf::from(r"C:\"), OsString::new()));
         }
 
         let root_marker_entry = MockEntry {
@@ -584,6 +1523,449 @@ mod tests {
         assert_eq!(child_path, PathBuf::from(r"C:\New folder"));
     }
 
+    #[test]
+    fn test_resolve_path_extended_adds_long_path_prefix() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Documents"), OsString::from("Documents")),
+            );
+        }
+
+        let entry = MockEntry {
+            fid: 0x11,
+            parent_fid: 0x10,
+            file_name: OsString::from("report.txt"),
+            is_dir: false,
+        };
+
+        assert_eq!(
+            resolver.resolve_path(&entry),
+            Some(PathBuf::from(r"C:\Documents\report.txt"))
+        );
+        assert_eq!(
+            resolver.resolve_path_extended(&entry),
+            Some(PathBuf::from(r"\\?\C:\Documents\report.txt"))
+        );
+    }
+
+    #[test]
+    fn test_with_extended_length_paths_prefixes_every_resolved_path() {
+        let volume = create_mock_volume();
+        let resolver = PathResolver::new_with_cache(&volume);
+        let mut resolver = resolver.with_extended_length_paths(true);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Documents"), OsString::from("Documents")),
+            );
+        }
+
+        let entry = MockEntry {
+            fid: 0x11,
+            parent_fid: 0x10,
+            file_name: OsString::from("report.txt"),
+            is_dir: false,
+        };
+
+        assert_eq!(
+            resolver.resolve_path(&entry),
+            Some(PathBuf::from(r"\\?\C:\Documents\report.txt"))
+        );
+        // The cache keeps the short form.
+        assert_eq!(
+            resolver
+                .dir_fid_path_cache
+                .as_mut()
+                .and_then(|cache| cache.get(&FileId(0x10)).map(|(path, _)| path.clone())),
+            Some(PathBuf::from(r"C:\Documents"))
+        );
+    }
+
+    #[test]
+    fn test_prefer_drive_letter_replaces_mount_folder() {
+        let mut volume = Volume::from_handle(
+            HANDLE(std::ptr::null_mut()),
+            None,
+            Some(r"C:\Mounts\Data".to_string()),
+        );
+        volume.mount_points = vec![r"C:\Mounts\Data\".to_string(), r"E:\".to_string()];
+        let mut resolver = PathResolver::new_with_cache(&volume).prefer_drive_letter(true);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (
+                    PathBuf::from(r"C:\Mounts\Data\Docs"),
+                    OsString::from("Docs"),
+                ),
+            );
+        }
+
+        let entry = MockEntry {
+            fid: 0x11,
+            parent_fid: 0x10,
+            file_name: OsString::from("report.txt"),
+            is_dir: false,
+        };
+
+        assert_eq!(
+            resolver.resolve_path(&entry),
+            Some(PathBuf::from(r"E:\Docs\report.txt"))
+        );
+
+        let mut resolver = resolver.prefer_drive_letter(false);
+        assert_eq!(
+            resolver.resolve_path(&entry),
+            Some(PathBuf::from(r"C:\Mounts\Data\Docs\report.txt"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_ancestors_walks_cached_parent_links() {
+        const ROOT: u64 = 0x0005_0000_0000_0005;
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut links) = resolver.parent_link_cache {
+            links.put(FileId(0x10), (FileId::from(ROOT), OsString::from("Docs")));
+            links.put(FileId(0x20), (FileId(0x10), OsString::from("Projects")));
+            links.put(FileId(0x30), (FileId(0x20), OsString::from("Rust")));
+        }
+
+        let entry = MockEntry {
+            fid: 0x40,
+            parent_fid: 0x30,
+            file_name: OsString::from("main.rs"),
+            is_dir: false,
+        };
+        assert_eq!(
+            resolver.resolve_ancestors(&entry),
+            Some(vec![
+                (FileId(0x10), OsString::from("Docs")),
+                (FileId(0x20), OsString::from("Projects")),
+                (FileId(0x30), OsString::from("Rust")),
+            ])
+        );
+
+        let top_level = MockEntry {
+            fid: 0x10,
+            parent_fid: ROOT,
+            file_name: OsString::from("Docs"),
+            is_dir: true,
+        };
+        assert_eq!(resolver.resolve_ancestors(&top_level), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_resolve_ancestors_stops_at_self_parented_root() {
+        // ReFS file IDs are 128-bit and the root is not record 5, but it is its own parent.
+        const ROOT: u128 = 0x0000_0000_0000_0001_0000_0000_0000_0600;
+        const DOCS: u128 = 0x0000_0000_0000_0001_0000_0000_0000_0700;
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut links) = resolver.parent_link_cache {
+            links.put(FileId(ROOT), (FileId(ROOT), OsString::from(".")));
+            links.put(FileId(DOCS), (FileId(ROOT), OsString::from("Docs")));
+        }
+
+        let entry = crate::journal::UsnEntry {
+            usn: 0x2000,
+            time: SystemTime::UNIX_EPOCH,
+            fid: FileId(0x0000_0000_0000_0001_0000_0000_0000_0800),
+            parent_fid: FileId(DOCS),
+            reason: UsnReason::CLOSE,
+            source_info: 0,
+            file_name: OsString::from("report.txt"),
+            file_attributes: 0,
+        };
+        assert_eq!(
+            resolver.resolve_ancestors(&entry),
+            Some(vec![(FileId(DOCS), OsString::from("Docs"))])
+        );
+    }
+
+    #[test]
+    fn test_resolve_ancestors_detects_cycles() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut links) = resolver.parent_link_cache {
+            links.put(FileId(0x50), (FileId(0x51), OsString::from("loop-a")));
+            links.put(FileId(0x51), (FileId(0x50), OsString::from("loop-b")));
+        }
+
+        let entry = MockEntry {
+            fid: 0x60,
+            parent_fid: 0x50,
+            file_name: OsString::from("stuck.txt"),
+            is_dir: false,
+        };
+        assert_eq!(resolver.resolve_ancestors(&entry), None);
+    }
+
+    #[test]
+    fn test_with_cache_capacity_bounds_directory_cache() {
+        let volume = create_mock_volume();
+        let mut resolver =
+            PathResolver::with_cache_capacity(&volume, NonZeroUsize::new(2).unwrap());
+        assert_eq!(resolver.cache_capacity(), Some(2));
+        assert_eq!(PathResolver::new(&volume).cache_capacity(), None);
+        assert_eq!(
+            PathResolver::new_with_cache(&volume).cache_capacity(),
+            Some(4096)
+        );
+
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            for (fid, name) in [(0x10, "A"), (0x11, "B"), (0x12, "C")] {
+                cache.put(
+                    FileId(fid),
+                    (PathBuf::from(r"C:\").join(name), OsString::from(name)),
+                );
+            }
+        }
+
+        let cached: Vec<_> = resolver
+            .cache_dump()
+            .into_iter()
+            .map(|(fid, _, _)| fid)
+            .collect();
+        assert_eq!(cached, vec![FileId(0x12), FileId(0x11)]);
+
+        let hashed = PathResolver::with_cache_capacity_and_hasher(
+            &volume,
+            NonZeroUsize::new(2).unwrap(),
+            std::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default(),
+        );
+        assert_eq!(hashed.cache_capacity(), Some(2));
+    }
+
+    #[test]
+    fn test_cache_stats_count_hits_and_misses() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Documents"), OsString::from("Documents")),
+            );
+        }
+        let entry = |fid: u64, parent_fid: u64| MockEntry {
+            fid,
+            parent_fid,
+            file_name: OsString::from("report.txt"),
+            is_dir: false,
+        };
+
+        assert!(resolver.resolve_path(&entry(0x11, 0x10)).is_some());
+        assert!(resolver.resolve_path(&entry(0x12, 0x10)).is_some());
+        // The mock volume cannot open the uncached parent.
+        assert!(resolver.resolve_path(&entry(0x21, 0x20)).is_none());
+
+        assert_eq!(resolver.cache_stats(), CacheStats { hits: 2, misses: 1 });
+    }
+
+    #[test]
+    fn test_to_extended_length_path_handles_each_prefix() {
+        let cases = [
+            (r"C:\a\b.txt", r"\\?\C:\a\b.txt"),
+            (r"\\server\share\a\b.txt", r"\\?\UNC\server\share\a\b.txt"),
+            (r"\\.\C:\a\b.txt", r"\\?\C:\a\b.txt"),
+            (r"\\?\C:\a\b.txt", r"\\?\C:\a\b.txt"),
+            (r"\\?\UNC\server\share\b.txt", r"\\?\UNC\server\share\b.txt"),
+            (r"relative\b.txt", r"relative\b.txt"),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(
+                to_extended_length_path(Path::new(path)),
+                PathBuf::from(expected),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_creations_under_yields_only_subtree_creations() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Watched"), OsString::from("Watched")),
+            );
+            cache.put(
+                FileId(0x20),
+                (PathBuf::from(r"C:\Other"), OsString::from("Other")),
+            );
+        }
+
+        let create = |fid: u64, parent_fid: u64, name: &str, is_dir: bool, reason: UsnReason| {
+            Ok(UsnEntry {
+                usn: fid as i64,
+                time: SystemTime::UNIX_EPOCH,
+                fid: fid.into(),
+                parent_fid: parent_fid.into(),
+                reason,
+                source_info: 0,
+                file_name: OsString::from(name),
+                file_attributes: if is_dir { 0x10 } else { 0x20 },
+            })
+        };
+        let entries = vec![
+            create(0x10, 0x5, "Watched", true, UsnReason::FILE_CREATE),
+            create(0x11, 0x10, "a.txt", false, UsnReason::FILE_CREATE),
+            create(0x12, 0x10, "sub", true, UsnReason::FILE_CREATE),
+            create(
+                0x13,
+                0x12,
+                "b.txt",
+                false,
+                UsnReason::FILE_CREATE | UsnReason::CLOSE,
+            ),
+            create(0x11, 0x10, "a.txt", false, UsnReason::DATA_EXTEND),
+            create(0x21, 0x20, "c.txt", false, UsnReason::FILE_CREATE),
+        ];
+
+        let creations = CreationsUnder::new(entries.into_iter(), &mut resolver, FileId(0x10))
+            .unwrap()
+            .map(|result| result.map(|(entry, path)| (entry.fid.0, path)))
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            creations,
+            vec![
+                (0x11, Some(PathBuf::from(r"C:\Watched\a.txt"))),
+                (0x12, Some(PathBuf::from(r"C:\Watched\sub"))),
+                (0x13, Some(PathBuf::from(r"C:\Watched\sub\b.txt"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignore_subtrees_drops_changes_under_ignored_roots() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            for (fid, path, name) in [
+                (0x10, r"C:\Windows\Temp", "Temp"),
+                (0x11, r"C:\Windows\Temp\sub", "sub"),
+                (0x20, r"C:\Logs", "Logs"),
+                (0x30, r"C:\Docs", "Docs"),
+                (0x40, r"C:\Windows\TempFiles", "TempFiles"),
+            ] {
+                cache.put(FileId(fid), (PathBuf::from(path), OsString::from(name)));
+            }
+        }
+
+        let change = |fid: u64, parent_fid: u64, name: &str| {
+            Ok(UsnEntry {
+                usn: fid as i64,
+                time: SystemTime::UNIX_EPOCH,
+                fid: fid.into(),
+                parent_fid: parent_fid.into(),
+                reason: UsnReason::DATA_EXTEND,
+                source_info: 0,
+                file_name: OsString::from(name),
+                file_attributes: 0x20,
+            })
+        };
+        let entries = vec![
+            change(0x12, 0x10, "scratch.tmp"),
+            change(0x31, 0x30, "report.docx"),
+            change(0x13, 0x11, "nested.tmp"),
+            change(0x21, 0x20, "app.log"),
+            change(0x41, 0x40, "kept.txt"),
+            change(0x14, 0x10, "again.tmp"),
+        ];
+
+        let kept = IgnoreSubtrees::new(
+            entries.into_iter(),
+            &mut resolver,
+            &[FileId(0x10), FileId(0x20)],
+        )
+        .unwrap()
+        .map(|result| result.map(|entry| entry.fid.0))
+        .collect::<UsnResult<Vec<_>>>()
+        .unwrap();
+
+        assert_eq!(kept, vec![0x31, 0x41]);
+    }
+
+    #[test]
+    fn test_resolve_path_applies_name_encoding_policy() {
+        use std::os::windows::ffi::OsStringExt;
+
+        let volume = create_mock_volume();
+        let invalid_name = OsString::from_wide(&[0x61, 0xD800, 0x62]);
+        let entry = MockEntry {
+            fid: 0x11,
+            parent_fid: 0x10,
+            file_name: invalid_name.clone(),
+            is_dir: false,
+        };
+        let resolve = |policy| {
+            let mut resolver =
+                PathResolver::new_with_cache(&volume).with_name_encoding_policy(policy);
+            if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+                cache.put(
+                    FileId(0x10),
+                    (PathBuf::from(r"C:\Docs"), OsString::from("Docs")),
+                );
+            }
+            resolver.resolve_path(&entry)
+        };
+
+        assert_eq!(
+            resolve(NameEncodingPolicy::Preserve),
+            Some(PathBuf::from(r"C:\Docs").join(&invalid_name))
+        );
+        assert_eq!(
+            resolve(NameEncodingPolicy::ReplaceWith('_')),
+            Some(PathBuf::from(r"C:\Docs\a_b"))
+        );
+        assert_eq!(resolve(NameEncodingPolicy::Skip), None);
+    }
+
+    #[test]
+    fn test_resolve_relative_to_base_directory() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Watched"), OsString::from("Watched")),
+            );
+            cache.put(
+                FileId(0x11),
+                (PathBuf::from(r"C:\Watched\sub"), OsString::from("sub")),
+            );
+            cache.put(
+                FileId(0x20),
+                (PathBuf::from(r"C:\Other"), OsString::from("Other")),
+            );
+        }
+        let entry = |fid, parent_fid, name: &str| MockEntry {
+            fid,
+            parent_fid,
+            file_name: OsString::from(name),
+            is_dir: false,
+        };
+
+        assert_eq!(
+            resolver.resolve_relative(&entry(0x12, 0x10, "a.txt"), FileId(0x10)),
+            Some(PathBuf::from("a.txt"))
+        );
+        assert_eq!(
+            resolver.resolve_relative(&entry(0x13, 0x11, "b.txt"), FileId(0x10)),
+            Some(PathBuf::from(r"sub\b.txt"))
+        );
+        assert_eq!(
+            resolver.resolve_relative(&entry(0x21, 0x20, "c.txt"), FileId(0x10)),
+            None
+        );
+    }
+
     #[test]
     fn test_resolve_path_failure() {
         let volume = create_mock_volume();
@@ -602,4 +1984,337 @@ mod tests {
         let result = resolver.resolve_path(&entry);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_resolve_paths_reports_each_unresolved_entry() {
+        let volume = create_mock_volume();
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        if let Some(ref mut cache) = resolver.dir_fid_path_cache {
+            cache.put(
+                FileId(0x10),
+                (PathBuf::from(r"C:\Documents"), OsString::from("Documents")),
+            );
+        }
+
+        let entries = vec![
+            MockEntry {
+                fid: 0x1,
+                parent_fid: 0x10,
+                file_name: OsString::from("resolved.txt"),
+                is_dir: false,
+            },
+            MockEntry {
+                fid: 0x2,
+                parent_fid: 0x99,
+                file_name: OsString::from("orphan1.txt"),
+                is_dir: false,
+            },
+            MockEntry {
+                fid: 0x3,
+                parent_fid: 0x98,
+                file_name: OsString::from("orphan2.txt"),
+                is_dir: false,
+            },
+        ];
+
+        let mut unresolved = Vec::new();
+        let results = ResolvePaths::new(
+            entries.into_iter().map(Ok),
+            &mut resolver,
+            |entry: &MockEntry, err: UsnError| {
+                assert!(matches!(err, UsnError::WinApiError(_)));
+                unresolved.push(entry.fid);
+            },
+        )
+        .collect::<UsnResult<Vec<_>>>()
+        .unwrap();
+
+        assert_eq!(unresolved, vec![0x2, 0x3]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].1,
+            Some(PathBuf::from(r"C:\Documents\resolved.txt"))
+        );
+        assert!(results[1].1.is_none());
+        assert!(results[2].1.is_none());
+    }
+
+    #[test]
+    fn test_entry_still_exists_tracks_deletion() -> UsnResult<()> {
+        let volume = match Volume::from_drive_letter('C') {
+            Ok(volume) => volume,
+            Err(UsnError::PermissionError(_)) => {
+                eprintln!("Skipping test - requires admin privileges");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let resolver = PathResolver::new(&volume);
+
+        let file_path = std::env::temp_dir().join("usn_journal_rs_still_exists.txt");
+        std::fs::write(&file_path, b"exists")?;
+        let entry = MockEntry {
+            fid: file_id_from_path(&file_path)?,
+            parent_fid: file_id_from_path(&std::env::temp_dir())?,
+            file_name: OsString::from("usn_journal_rs_still_exists.txt"),
+            is_dir: false,
+        };
+
+        assert!(resolver.entry_still_exists(&entry)?);
+        std::fs::remove_file(&file_path)?;
+        assert!(!resolver.entry_still_exists(&entry)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extended_length_paths_resolve_deeply_nested_file() -> UsnResult<()> {
+        let volume = match Volume::from_drive_letter('C') {
+            Ok(volume) => volume,
+            Err(UsnError::PermissionError(_)) => {
+                eprintln!("Skipping test - requires admin privileges");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let mut resolver = PathResolver::new_with_cache(&volume).with_extended_length_paths(true);
+
+        // Nest well past MAX_PATH; only the extended form can create and open the file.
+        let root = to_extended_length_path(&std::env::temp_dir().join("usn_journal_rs_long_paths"));
+        let mut dir = root.clone();
+        for depth in 0..6 {
+            dir.push(format!("{depth}_{}", "p".repeat(60)));
+        }
+        std::fs::create_dir_all(&dir)?;
+        let file_name = "usn_journal_rs_long_paths.txt";
+        std::fs::write(dir.join(file_name), b"deep")?;
+        let entry = MockEntry {
+            fid: file_id_from_path(&dir.join(file_name))?,
+            parent_fid: file_id_from_path(&dir)?,
+            file_name: OsString::from(file_name),
+            is_dir: false,
+        };
+
+        let resolved = resolver.try_resolve_path(&entry);
+        let contents = resolved.as_ref().ok().map(std::fs::read).transpose();
+        std::fs::remove_dir_all(&root)?;
+
+        let resolved = resolved?;
+        assert!(resolved.as_os_str().len() > 260);
+        assert!(resolved.to_string_lossy().starts_with(r"\\?\C:\"));
+        assert_eq!(contents?.as_deref(), Some(&b"deep"[..]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefer_drive_letter_on_folder_mounted_volume() -> UsnResult<()> {
+        let volumes = match Volume::enumerate() {
+            Ok(volumes) => volumes,
+            Err(UsnError::PermissionError(_)) => {
+                eprintln!("Skipping test - requires admin privileges");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        // Needs a volume mounted both at a drive letter and in a folder.
+        let Some((drive_root, folder)) = volumes.iter().find_map(|volume| {
+            let drive_root = volume.mount_points.iter().find(|path| path.len() <= 3)?;
+            let folder = volume.mount_points.iter().find(|path| path.len() > 3)?;
+            Some((drive_root.clone(), folder.clone()))
+        }) else {
+            eprintln!("Skipping test - no volume has both a drive letter and a folder mount");
+            return Ok(());
+        };
+
+        let volume = Volume::from_mount_point(Path::new(folder.trim_end_matches('\\')))?;
+        let mut resolver = PathResolver::new(&volume).prefer_drive_letter(true);
+        let root = MockEntry {
+            fid: file_id_from_path(Path::new(&folder))?,
+            parent_fid: file_id_from_path(Path::new(&folder))?,
+            file_name: OsString::from("."),
+            is_dir: true,
+        };
+
+        let resolved = resolver.try_resolve_path(&root)?;
+        assert_eq!(resolved, PathBuf::from(&drive_root));
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_index_resolves_nested_paths() {
+        const ROOT: u64 = 0x0005_0000_0000_0005;
+        let entry = |fid: u64, parent_fid: u64, name: &str| MftEntry {
+            usn: 0,
+            fid: fid.into(),
+            parent_fid: parent_fid.into(),
+            file_name: OsString::from(name),
+            file_attributes: 0x20,
+            timestamps: None,
+            sizes: None,
+        };
+        let index = PathIndex::from_entries(
+            PathBuf::from(r"C:\"),
+            vec![
+                entry(ROOT, ROOT, "."),
+                entry(0x30, 0x20, "deep.txt"),
+                entry(0x20, 0x10, "Sub"),
+                entry(0x10, ROOT, "Docs"),
+                entry(0x40, 0x99, "orphan.txt"),
+                entry(0x50, 0x51, "loop-a"),
+                entry(0x51, 0x50, "loop-b"),
+            ],
+        );
+
+        assert_eq!(index.len(), 7);
+        assert_eq!(
+            index.resolve(FileId(0x30)),
+            Some(PathBuf::from(r"C:\Docs\Sub\deep.txt"))
+        );
+        assert_eq!(index.resolve(FileId(0x10)), Some(PathBuf::from(r"C:\Docs")));
+        assert_eq!(
+            index.resolve(FileId::from(ROOT)),
+            Some(PathBuf::from(r"C:\"))
+        );
+        assert_eq!(index.resolve(FileId(0x40)), None);
+        assert_eq!(index.resolve(FileId(0x50)), None);
+        assert_eq!(index.resolve(FileId(0x77)), None);
+    }
+
+    mod mocked_tests {
+        use super::*;
+        use injectorpp::interface::injector::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Number of `FILE_NAME_INFO` queries answered by the mock below.
+        static NAME_QUERIES: AtomicUsize = AtomicUsize::new(0);
+
+        /// Answers a mocked `FileNameInfo` query with `\Docs`.
+        fn write_mock_name_info(info: *mut std::ffi::c_void, size: u32) {
+            NAME_QUERIES.fetch_add(1, Ordering::SeqCst);
+            let name = r"\Docs".encode_utf16().collect::<Vec<_>>();
+            let name_len = name.len() * size_of::<u16>();
+            assert!(size as usize >= size_of::<u32>() + name_len);
+            unsafe {
+                let info = info.cast::<u8>();
+                info.cast::<u32>().write_unaligned(name_len as u32);
+                std::ptr::copy_nonoverlapping(
+                    name.as_ptr().cast::<u8>(),
+                    info.add(size_of::<u32>()),
+                    name_len,
+                );
+            }
+        }
+
+        #[test]
+        fn test_resolve_path_reuses_name_info_buffer() {
+            let mut injector = InjectorPP::new();
+            NAME_QUERIES.store(0, Ordering::SeqCst);
+
+            // Every file ID opens; the pseudo handle is never closed.
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (FileSystem::OpenFileById)(
+                        Foundation::HANDLE,
+                        *const FILE_ID_DESCRIPTOR,
+                        u32,
+                        FileSystem::FILE_SHARE_MODE,
+                        Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        FileSystem::FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<Foundation::HANDLE>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _volume: Foundation::HANDLE,
+                        _file_id: *const FILE_ID_DESCRIPTOR,
+                        _access: u32,
+                        _share_mode: FileSystem::FILE_SHARE_MODE,
+                        _security: Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        _flags: FileSystem::FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<Foundation::HANDLE>,
+                    returns: Ok(Foundation::HANDLE(-1isize as *mut std::ffi::c_void))
+                ));
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (FileSystem::GetFileInformationByHandleEx)(
+                        Foundation::HANDLE,
+                        FileSystem::FILE_INFO_BY_HANDLE_CLASS,
+                        *mut std::ffi::c_void,
+                        u32
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _file: Foundation::HANDLE,
+                        _class: FileSystem::FILE_INFO_BY_HANDLE_CLASS,
+                        _info: *mut std::ffi::c_void,
+                        _size: u32
+                    ) -> windows::core::Result<()>,
+                    assign: { write_mock_name_info(_info, _size) },
+                    returns: Ok(())
+                ));
+
+            let volume = create_mock_volume();
+            let mut resolver = PathResolver::new(&volume);
+            let buffer_ptr = resolver.name_info_buffer.as_ptr();
+            let buffer_capacity = resolver.name_info_buffer.capacity();
+
+            for fid in 0x100..0x110 {
+                let entry = MockEntry {
+                    fid,
+                    parent_fid: 0x654321,
+                    file_name: OsString::from("test.txt"),
+                    is_dir: false,
+                };
+                assert_eq!(
+                    resolver.resolve_path(&entry),
+                    Some(PathBuf::from(r"C:\Docs\test.txt"))
+                );
+            }
+
+            // Every lookup went through the buffer allocated with the resolver.
+            assert_eq!(NAME_QUERIES.load(Ordering::SeqCst), 0x10);
+            assert_eq!(resolver.name_info_buffer.as_ptr(), buffer_ptr);
+            assert_eq!(resolver.name_info_buffer.capacity(), buffer_capacity);
+            assert_eq!(resolver.name_info_buffer.len(), FILE_NAME_INFO_INIT_LEN);
+        }
+
+        #[test]
+        fn test_entry_still_exists_reports_access_denied_as_error() {
+            let mut injector = InjectorPP::new();
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (FileSystem::OpenFileById)(
+                        Foundation::HANDLE,
+                        *const FILE_ID_DESCRIPTOR,
+                        u32,
+                        FileSystem::FILE_SHARE_MODE,
+                        Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        FileSystem::FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<Foundation::HANDLE>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _volume: Foundation::HANDLE,
+                        _file_id: *const FILE_ID_DESCRIPTOR,
+                        _access: u32,
+                        _share_mode: FileSystem::FILE_SHARE_MODE,
+                        _security: Option<*const windows::Win32::Security::SECURITY_ATTRIBUTES>,
+                        _flags: FileSystem::FILE_FLAGS_AND_ATTRIBUTES
+                    ) -> windows::core::Result<Foundation::HANDLE>,
+                    returns: Err(Foundation::ERROR_ACCESS_DENIED.into())
+                ));
+
+            let volume = create_mock_volume();
+            let resolver = PathResolver::new(&volume);
+            let entry = MockEntry {
+                fid: 0x100,
+                parent_fid: 0x654321,
+                file_name: OsString::from("locked.txt"),
+                is_dir: false,
+            };
+            assert!(matches!(
+                resolver.entry_still_exists(&entry),
+                Err(UsnError::WinApiError(err)) if err.code() == Foundation::ERROR_ACCESS_DENIED.into()
+            ));
+        }
+    }
 }
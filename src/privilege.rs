@@ -1,18 +1,53 @@
+//! Privilege checks for the current process.
+//!
+//! Reading the USN journal or enumerating the MFT requires an elevated process. Use
+//! [`current_privileges`] to find out which of the relevant privileges are held when
+//! troubleshooting a [`UsnError::PermissionError`](crate::UsnError::PermissionError).
+
 use std::mem::size_of;
 
 use windows::{
     Win32::{
-        Foundation::HANDLE,
-        Security::{GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation},
+        Foundation::{HANDLE, LUID},
+        Security::{
+            GetTokenInformation, LUID_AND_ATTRIBUTES, LookupPrivilegeValueW, SE_BACKUP_NAME,
+            SE_MANAGE_VOLUME_NAME, SE_PRIVILEGE_ENABLED, TOKEN_ELEVATION, TOKEN_PRIVILEGES,
+            TOKEN_QUERY, TokenElevation, TokenPrivileges,
+        },
         System::Threading::{GetCurrentProcess, OpenProcessToken},
     },
-    core::Owned,
+    core::{Owned, PCWSTR},
 };
 
-pub(crate) fn is_elevated() -> windows::core::Result<bool> {
+use crate::{UsnError, UsnResult};
+
+/// Name reported by [`current_privileges`] for the process elevation status.
+pub const ELEVATED: &str = "Elevated";
+
+/// Privileges relevant to volume access, as reported by [`current_privileges`].
+const RELEVANT_PRIVILEGES: [(&str, PCWSTR); 2] = [
+    ("SeBackupPrivilege", SE_BACKUP_NAME),
+    ("SeManageVolumePrivilege", SE_MANAGE_VOLUME_NAME),
+];
+
+/// Whether the process token holds a privilege, and if so whether it is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrivilegeState {
+    /// The token does not hold the privilege at all.
+    Missing,
+    /// The token holds the privilege, but it has not been enabled.
+    Disabled,
+    Enabled,
+}
+
+fn open_process_token() -> windows::core::Result<Owned<HANDLE>> {
     let mut handle: HANDLE = HANDLE::default();
     unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut handle)? };
-    let handle = unsafe { Owned::new(handle) };
+    Ok(unsafe { Owned::new(handle) })
+}
+
+pub(crate) fn is_elevated() -> windows::core::Result<bool> {
+    let handle = open_process_token()?;
 
     let mut elevation = TOKEN_ELEVATION::default();
     let mut returned_length = 0;
@@ -30,6 +65,128 @@ pub(crate) fn is_elevated() -> windows::core::Result<bool> {
     Ok(elevation.TokenIsElevated != 0)
 }
 
+/// Lists the privileges relevant to volume access and whether each is enabled.
+///
+/// The result holds `SeBackupPrivilege`, `SeManageVolumePrivilege` and, last, [`ELEVATED`]
+/// for the process elevation status. A privilege the token does not hold at all is reported
+/// as not enabled.
+///
+/// # Example
+/// ```rust,no_run
+/// use usn_journal_rs::privilege;
+///
+/// for (name, enabled) in privilege::current_privileges()? {
+///     println!("{name}: {}", if enabled { "enabled" } else { "disabled" });
+/// }
+/// # Ok::<(), usn_journal_rs::UsnError>(())
+/// ```
+pub fn current_privileges() -> UsnResult<Vec<(String, bool)>> {
+    let mut privileges = relevant_privilege_states()?
+        .into_iter()
+        .map(|(name, state)| (name.to_string(), state == PrivilegeState::Enabled))
+        .collect::<Vec<_>>();
+    privileges.push((ELEVATED.to_string(), is_elevated()?));
+
+    Ok(privileges)
+}
+
+/// Returns the state of each of [`RELEVANT_PRIVILEGES`] in the current process token.
+fn relevant_privilege_states() -> UsnResult<Vec<(&'static str, PrivilegeState)>> {
+    let held = token_privileges()?;
+
+    let mut states = Vec::with_capacity(RELEVANT_PRIVILEGES.len());
+    for (name, system_name) in RELEVANT_PRIVILEGES {
+        let mut luid = LUID::default();
+        unsafe { LookupPrivilegeValueW(PCWSTR::null(), system_name, &mut luid)? };
+        let state = match held
+            .iter()
+            .find(|h| h.Luid.LowPart == luid.LowPart && h.Luid.HighPart == luid.HighPart)
+        {
+            None => PrivilegeState::Missing,
+            Some(h) if h.Attributes.contains(SE_PRIVILEGE_ENABLED) => PrivilegeState::Enabled,
+            Some(_) => PrivilegeState::Disabled,
+        };
+        states.push((name, state));
+    }
+
+    Ok(states)
+}
+
+/// Builds a `UsnError::PermissionError` naming the likely cause of an access failure.
+pub(crate) fn permission_error() -> UsnError {
+    let cause = match is_elevated()
+        .map_err(UsnError::from)
+        .and_then(|elevated| Ok((elevated, relevant_privilege_states()?)))
+    {
+        Ok((elevated, privileges)) => permission_cause(elevated, &privileges),
+        Err(err) => format!("could not inspect the process token: {err}"),
+    };
+    UsnError::PermissionError(cause)
+}
+
+/// Describes why access was denied, given the elevation status and privilege states.
+///
+/// Only privileges the token does not hold are reported as missing; a held privilege that
+/// is not enabled can still be enabled by the process, so it is reported separately.
+fn permission_cause(elevated: bool, privileges: &[(&str, PrivilegeState)]) -> String {
+    if !elevated {
+        return "not elevated".to_string();
+    }
+
+    let names_in = |wanted: PrivilegeState| {
+        privileges
+            .iter()
+            .filter(|(_, state)| *state == wanted)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+    };
+    let missing = names_in(PrivilegeState::Missing);
+    let disabled = names_in(PrivilegeState::Disabled);
+
+    let mut causes = Vec::new();
+    if !missing.is_empty() {
+        causes.push(format!("missing {}", missing.join(", ")));
+    }
+    if !disabled.is_empty() {
+        causes.push(format!("{} present but not enabled", disabled.join(", ")));
+    }
+    if causes.is_empty() {
+        "elevated, but the volume could not be opened; it may be locked by another process"
+            .to_string()
+    } else {
+        causes.join("; ")
+    }
+}
+
+/// Returns the privileges held in the current process token, enabled or not.
+fn token_privileges() -> windows::core::Result<Vec<LUID_AND_ATTRIBUTES>> {
+    let handle = open_process_token()?;
+
+    // The first call only reports the required buffer size, so its error is expected.
+    let mut required_length = 0;
+    let _ = unsafe { GetTokenInformation(*handle, TokenPrivileges, None, 0, &mut required_length) };
+
+    // Back the buffer with u64s so the TOKEN_PRIVILEGES header is suitably aligned.
+    let mut buffer = vec![0u64; (required_length as usize).div_ceil(size_of::<u64>()).max(1)];
+    unsafe {
+        GetTokenInformation(
+            *handle,
+            TokenPrivileges,
+            Some(buffer.as_mut_ptr() as *mut _),
+            (buffer.len() * size_of::<u64>()) as u32,
+            &mut required_length,
+        )?
+    };
+
+    let header = buffer.as_ptr() as *const TOKEN_PRIVILEGES;
+    let privileges: &[LUID_AND_ATTRIBUTES] = unsafe {
+        let count = (*header).PrivilegeCount as usize;
+        std::slice::from_raw_parts(std::ptr::addr_of!((*header).Privileges).cast(), count)
+    };
+
+    Ok(privileges.to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -39,6 +196,69 @@ mod tests {
     mod privilege_tests {
         use super::*;
 
+        #[test]
+        fn test_current_privileges_lists_expected_names() {
+            let privileges = match current_privileges() {
+                Ok(privileges) => privileges,
+                Err(e) => {
+                    eprintln!("Failed to query privileges: {e}");
+                    return;
+                }
+            };
+
+            let names = privileges
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                vec!["SeBackupPrivilege", "SeManageVolumePrivilege", ELEVATED]
+            );
+        }
+
+        #[test]
+        fn test_permission_cause_names_missing_privilege() {
+            use PrivilegeState::*;
+            let privileges = |backup, manage_volume| {
+                vec![
+                    ("SeBackupPrivilege", backup),
+                    ("SeManageVolumePrivilege", manage_volume),
+                ]
+            };
+
+            assert_eq!(
+                permission_cause(false, &privileges(Enabled, Enabled)),
+                "not elevated"
+            );
+            assert_eq!(
+                permission_cause(true, &privileges(Enabled, Missing)),
+                "missing SeManageVolumePrivilege"
+            );
+            assert_eq!(
+                permission_cause(true, &privileges(Missing, Missing)),
+                "missing SeBackupPrivilege, SeManageVolumePrivilege"
+            );
+            assert!(permission_cause(true, &privileges(Enabled, Enabled)).contains("locked"));
+        }
+
+        #[test]
+        fn test_permission_cause_tells_disabled_from_missing_privileges() {
+            use PrivilegeState::*;
+            let privileges = [
+                ("SeBackupPrivilege", Disabled),
+                ("SeManageVolumePrivilege", Missing),
+            ];
+
+            assert_eq!(
+                permission_cause(true, &privileges),
+                "missing SeManageVolumePrivilege; SeBackupPrivilege present but not enabled"
+            );
+            assert_eq!(
+                permission_cause(true, &privileges[..1]),
+                "SeBackupPrivilege present but not enabled"
+            );
+        }
+
         #[test]
         fn test_is_elevated_returns_bool() {
             // Test that the function returns a Result<bool, _>
@@ -120,6 +340,37 @@ mod tests {
                 assert_eq!(err.code(), ERROR_INVALID_HANDLE.into());
             }
         }
+
+        #[test]
+        fn test_permission_error_reports_not_elevated() {
+            let mut injector = InjectorPP::new();
+
+            // Leave the token information zeroed: not elevated, and no privileges held.
+            injector
+                .when_called(injectorpp::func!(
+                    unsafe{} fn (GetTokenInformation)(
+                        HANDLE,
+                        windows::Win32::Security::TOKEN_INFORMATION_CLASS,
+                        Option<*mut std::ffi::c_void>,
+                        u32,
+                        *mut u32
+                    ) -> windows::core::Result<()>
+                ))
+                .will_execute(injectorpp::fake!(
+                    func_type: unsafe fn(
+                        _token: HANDLE,
+                        _class: windows::Win32::Security::TOKEN_INFORMATION_CLASS,
+                        _info: Option<*mut std::ffi::c_void>,
+                        _length: u32,
+                        _return_length: *mut u32
+                    ) -> windows::core::Result<()>,
+                    returns: Ok(())
+                ));
+
+            let error = permission_error();
+            assert!(matches!(&error, UsnError::PermissionError(cause) if cause == "not elevated"));
+            assert_eq!(error.to_string(), "Access denied: not elevated");
+        }
     }
 
     // Integration tests that check actual privilege status
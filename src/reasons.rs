@@ -0,0 +1,238 @@
+//! USN reason flags.
+//!
+//! Stable re-exports of the `USN_REASON_*` bit flags reported in
+//! [`UsnEntry::reason`](crate::journal::UsnEntry::reason) and accepted by
+//! [`EnumOptions::reason_mask`](crate::journal::EnumOptions::reason_mask), so callers building
+//! masks don't depend on the module layout of the `windows` crate. [`UsnReason`] wraps the same
+//! flags in a typed set.
+
+use std::fmt;
+use windows::Win32::System::Ioctl;
+
+/// The data in the file or directory was overwritten.
+pub const DATA_OVERWRITE: u32 = Ioctl::USN_REASON_DATA_OVERWRITE;
+/// The file or directory was extended (added to).
+pub const DATA_EXTEND: u32 = Ioctl::USN_REASON_DATA_EXTEND;
+/// The file or directory was truncated.
+pub const DATA_TRUNCATION: u32 = Ioctl::USN_REASON_DATA_TRUNCATION;
+/// Data in one or more named data streams of a file was overwritten.
+pub const NAMED_DATA_OVERWRITE: u32 = Ioctl::USN_REASON_NAMED_DATA_OVERWRITE;
+/// One or more named data streams of a file were extended (added to).
+pub const NAMED_DATA_EXTEND: u32 = Ioctl::USN_REASON_NAMED_DATA_EXTEND;
+/// One or more named data streams of a file were truncated.
+pub const NAMED_DATA_TRUNCATION: u32 = Ioctl::USN_REASON_NAMED_DATA_TRUNCATION;
+/// The file or directory was created for the first time.
+pub const FILE_CREATE: u32 = Ioctl::USN_REASON_FILE_CREATE;
+/// The file or directory was deleted.
+pub const FILE_DELETE: u32 = Ioctl::USN_REASON_FILE_DELETE;
+/// The extended attributes of the file or directory changed.
+pub const EA_CHANGE: u32 = Ioctl::USN_REASON_EA_CHANGE;
+/// The access rights to the file or directory changed.
+pub const SECURITY_CHANGE: u32 = Ioctl::USN_REASON_SECURITY_CHANGE;
+/// The file or directory was renamed; the record carries the old name.
+pub const RENAME_OLD_NAME: u32 = Ioctl::USN_REASON_RENAME_OLD_NAME;
+/// The file or directory was renamed; the record carries the new name.
+pub const RENAME_NEW_NAME: u32 = Ioctl::USN_REASON_RENAME_NEW_NAME;
+/// The `FILE_ATTRIBUTE_NOT_CONTENT_INDEXED` attribute changed.
+pub const INDEXABLE_CHANGE: u32 = Ioctl::USN_REASON_INDEXABLE_CHANGE;
+/// A user changed file or directory attributes or time stamps.
+pub const BASIC_INFO_CHANGE: u32 = Ioctl::USN_REASON_BASIC_INFO_CHANGE;
+/// A hard link was added to or removed from the file or directory.
+pub const HARD_LINK_CHANGE: u32 = Ioctl::USN_REASON_HARD_LINK_CHANGE;
+/// The compression state of the file or directory changed.
+pub const COMPRESSION_CHANGE: u32 = Ioctl::USN_REASON_COMPRESSION_CHANGE;
+/// The file or directory was encrypted or decrypted.
+pub const ENCRYPTION_CHANGE: u32 = Ioctl::USN_REASON_ENCRYPTION_CHANGE;
+/// The object identifier of the file or directory changed.
+pub const OBJECT_ID_CHANGE: u32 = Ioctl::USN_REASON_OBJECT_ID_CHANGE;
+/// The reparse point of the file or directory was added, changed, or removed.
+pub const REPARSE_POINT_CHANGE: u32 = Ioctl::USN_REASON_REPARSE_POINT_CHANGE;
+/// A named stream was added to or removed from the file, or renamed.
+pub const STREAM_CHANGE: u32 = Ioctl::USN_REASON_STREAM_CHANGE;
+/// The change was made within a transaction.
+pub const TRANSACTED_CHANGE: u32 = Ioctl::USN_REASON_TRANSACTED_CHANGE;
+/// The integrity attribute of a file stream changed.
+pub const INTEGRITY_CHANGE: u32 = Ioctl::USN_REASON_INTEGRITY_CHANGE;
+/// The desired storage class of the file changed.
+pub const DESIRED_STORAGE_CLASS_CHANGE: u32 = Ioctl::USN_REASON_DESIRED_STORAGE_CLASS_CHANGE;
+/// The file or directory was closed; this is the final record for a set of changes.
+pub const CLOSE: u32 = Ioctl::USN_REASON_CLOSE;
+
+/// Mask matching every reason flag.
+pub const ALL: u32 = crate::USN_REASON_MASK_ALL;
+
+bitflags::bitflags! {
+    /// The set of reasons recorded in a journal entry.
+    ///
+    /// Bits without a named flag are kept, so [`UsnReason::raw`] always returns the value
+    /// Windows reported.
+    ///
+    /// # Example
+    /// ```rust
+    /// use usn_journal_rs::reasons::UsnReason;
+    ///
+    /// let reason = UsnReason::RENAME_NEW_NAME | UsnReason::CLOSE;
+    /// assert!(reason.contains(UsnReason::RENAME_NEW_NAME));
+    /// assert_eq!(reason.to_string(), "RENAME_NEW_NAME | CLOSE");
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct UsnReason: u32 {
+        const DATA_OVERWRITE = DATA_OVERWRITE;
+        const DATA_EXTEND = DATA_EXTEND;
+        const DATA_TRUNCATION = DATA_TRUNCATION;
+        const NAMED_DATA_OVERWRITE = NAMED_DATA_OVERWRITE;
+        const NAMED_DATA_EXTEND = NAMED_DATA_EXTEND;
+        const NAMED_DATA_TRUNCATION = NAMED_DATA_TRUNCATION;
+        const FILE_CREATE = FILE_CREATE;
+        const FILE_DELETE = FILE_DELETE;
+        const EA_CHANGE = EA_CHANGE;
+        const SECURITY_CHANGE = SECURITY_CHANGE;
+        const RENAME_OLD_NAME = RENAME_OLD_NAME;
+        const RENAME_NEW_NAME = RENAME_NEW_NAME;
+        const INDEXABLE_CHANGE = INDEXABLE_CHANGE;
+        const BASIC_INFO_CHANGE = BASIC_INFO_CHANGE;
+        const HARD_LINK_CHANGE = HARD_LINK_CHANGE;
+        const COMPRESSION_CHANGE = COMPRESSION_CHANGE;
+        const ENCRYPTION_CHANGE = ENCRYPTION_CHANGE;
+        const OBJECT_ID_CHANGE = OBJECT_ID_CHANGE;
+        const REPARSE_POINT_CHANGE = REPARSE_POINT_CHANGE;
+        const STREAM_CHANGE = STREAM_CHANGE;
+        const TRANSACTED_CHANGE = TRANSACTED_CHANGE;
+        const INTEGRITY_CHANGE = INTEGRITY_CHANGE;
+        const DESIRED_STORAGE_CLASS_CHANGE = DESIRED_STORAGE_CLASS_CHANGE;
+        const CLOSE = CLOSE;
+
+        const _ = !0;
+    }
+}
+
+impl UsnReason {
+    /// Returns the underlying `USN_REASON_*` bits.
+    pub const fn raw(self) -> u32 {
+        self.bits()
+    }
+}
+
+impl From<u32> for UsnReason {
+    fn from(raw: u32) -> Self {
+        UsnReason::from_bits_retain(raw)
+    }
+}
+
+impl From<UsnReason> for u32 {
+    fn from(reason: UsnReason) -> Self {
+        reason.raw()
+    }
+}
+
+/// Serialized as `{ "bits": <u32>, "flags": ["FILE_CREATE", ...] }`; only `bits` is read back.
+#[cfg(feature = "serde")]
+impl serde::Serialize for UsnReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let flags = self.iter_names().map(|(name, _)| name).collect::<Vec<_>>();
+        let mut state = serializer.serialize_struct("UsnReason", 2)?;
+        state.serialize_field("bits", &self.raw())?;
+        state.serialize_field("flags", &flags)?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for UsnReason {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Bits {
+            bits: u32,
+        }
+
+        Bits::deserialize(deserializer).map(|repr| UsnReason::from(repr.bits))
+    }
+}
+
+impl fmt::Display for UsnReason {
+    /// Joins the names of the set flags with ` | `, or writes `UNKNOWN` if none is set.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = self.iter_names().map(|(name, _)| name).peekable();
+        if names.peek().is_none() {
+            return f.write_str("UNKNOWN");
+        }
+        for (i, name) in names.enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reasons_match_documented_values() {
+        let pairs = [
+            (DATA_OVERWRITE, 0x0000_0001),
+            (DATA_EXTEND, 0x0000_0002),
+            (DATA_TRUNCATION, 0x0000_0004),
+            (NAMED_DATA_OVERWRITE, 0x0000_0010),
+            (NAMED_DATA_EXTEND, 0x0000_0020),
+            (NAMED_DATA_TRUNCATION, 0x0000_0040),
+            (FILE_CREATE, 0x0000_0100),
+            (FILE_DELETE, 0x0000_0200),
+            (EA_CHANGE, 0x0000_0400),
+            (SECURITY_CHANGE, 0x0000_0800),
+            (RENAME_OLD_NAME, 0x0000_1000),
+            (RENAME_NEW_NAME, 0x0000_2000),
+            (INDEXABLE_CHANGE, 0x0000_4000),
+            (BASIC_INFO_CHANGE, 0x0000_8000),
+            (HARD_LINK_CHANGE, 0x0001_0000),
+            (COMPRESSION_CHANGE, 0x0002_0000),
+            (ENCRYPTION_CHANGE, 0x0004_0000),
+            (OBJECT_ID_CHANGE, 0x0008_0000),
+            (REPARSE_POINT_CHANGE, 0x0010_0000),
+            (STREAM_CHANGE, 0x0020_0000),
+            (TRANSACTED_CHANGE, 0x0040_0000),
+            (INTEGRITY_CHANGE, 0x0080_0000),
+            (DESIRED_STORAGE_CLASS_CHANGE, 0x0100_0000),
+            (CLOSE, 0x8000_0000),
+        ];
+
+        for (constant, documented) in pairs {
+            assert_eq!(constant, documented, "{constant:#x} != {documented:#x}");
+        }
+    }
+
+    #[test]
+    fn test_usn_reason_flags_match_constants() {
+        assert_eq!(UsnReason::FILE_CREATE.raw(), FILE_CREATE);
+        assert_eq!(UsnReason::CLOSE.raw(), CLOSE);
+        assert_eq!(UsnReason::all().iter_names().count(), 24);
+        assert_eq!(
+            UsnReason::from(0x8000_0100),
+            UsnReason::CLOSE | UsnReason::FILE_CREATE
+        );
+    }
+
+    #[test]
+    fn test_usn_reason_display_and_unknown_bits() {
+        let reason = UsnReason::DATA_EXTEND | UsnReason::FILE_CREATE | UsnReason::CLOSE;
+        assert_eq!(reason.to_string(), "DATA_EXTEND | FILE_CREATE | CLOSE");
+        assert!(reason.intersects(UsnReason::FILE_CREATE | UsnReason::FILE_DELETE));
+        assert!(!reason.contains(UsnReason::FILE_CREATE | UsnReason::FILE_DELETE));
+
+        // Bits without a name survive the round trip but aren't displayed.
+        let unknown = UsnReason::from(0x0000_0080);
+        assert_eq!(unknown.raw(), 0x0000_0080);
+        assert_eq!(unknown.to_string(), "UNKNOWN");
+    }
+
+    #[test]
+    fn test_all_covers_every_reason() {
+        assert_eq!(ALL, 0xFFFFFFFF);
+        assert_eq!(ALL & (FILE_CREATE | CLOSE), FILE_CREATE | CLOSE);
+    }
+}
@@ -0,0 +1,76 @@
+//! Field representations used by the optional `serde` support.
+//!
+//! File names are written as UTF-8 strings. Windows names are UTF-16 and may contain unpaired
+//! surrogates; those are replaced with U+FFFD when serializing, so such names do not survive
+//! a round trip. Times are written as RFC 3339 timestamps in UTC with up to nanosecond
+//! precision.
+
+/// `serde(with)` module writing an `OsString` as a lossy UTF-8 string.
+pub(crate) mod lossy_os_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::ffi::OsString;
+
+    pub(crate) fn serialize<S: Serializer>(
+        name: &OsString,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&name.to_string_lossy())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OsString, D::Error> {
+        String::deserialize(deserializer).map(OsString::from)
+    }
+}
+
+/// `serde(with)` module writing a `SystemTime` as an RFC 3339 timestamp.
+pub(crate) mod rfc3339 {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+    use std::time::SystemTime;
+
+    pub(crate) fn serialize<S: Serializer>(
+        time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let time = DateTime::<Utc>::from(*time);
+        serializer.serialize_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&text)
+            .map(SystemTime::from)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `serde(with)` module writing an `Option<SystemTime>` as an RFC 3339 timestamp or null.
+pub(crate) mod rfc3339_option {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+    use std::time::SystemTime;
+
+    pub(crate) fn serialize<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.map(|time| DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| {
+                DateTime::parse_from_rfc3339(&text)
+                    .map(SystemTime::from)
+                    .map_err(D::Error::custom)
+            })
+            .transpose()
+    }
+}
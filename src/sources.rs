@@ -0,0 +1,29 @@
+//! USN source-info flags.
+//!
+//! Stable re-exports of the `USN_SOURCE_*` flags reported in
+//! [`UsnEntry::source_info`](crate::journal::UsnEntry::source_info), so callers don't depend on
+//! the module layout of the `windows` crate.
+
+use windows::Win32::System::Ioctl;
+
+/// The operation added a private data stream or changed the data without changing its content.
+pub const DATA_MANAGEMENT: u32 = Ioctl::USN_SOURCE_DATA_MANAGEMENT.0;
+/// The operation was performed on an auxiliary (hidden) copy of the data.
+pub const AUXILIARY_DATA: u32 = Ioctl::USN_SOURCE_AUXILIARY_DATA.0;
+/// The operation was performed by replication to keep a file in sync with another copy.
+pub const REPLICATION_MANAGEMENT: u32 = Ioctl::USN_SOURCE_REPLICATION_MANAGEMENT.0;
+/// The operation was performed by a client-side replication service, such as the Cloud Files API.
+pub const CLIENT_REPLICATION_MANAGEMENT: u32 = Ioctl::USN_SOURCE_CLIENT_REPLICATION_MANAGEMENT.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_match_documented_values() {
+        assert_eq!(DATA_MANAGEMENT, 0x1);
+        assert_eq!(AUXILIARY_DATA, 0x2);
+        assert_eq!(REPLICATION_MANAGEMENT, 0x4);
+        assert_eq!(CLIENT_REPLICATION_MANAGEMENT, 0x8);
+    }
+}
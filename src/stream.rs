@@ -0,0 +1,211 @@
+//! Async access to the USN journal, available with the `tokio` feature.
+//!
+//! [`UsnJournal::stream`](crate::journal::UsnJournal::stream) runs a
+//! [`UsnJournalIter`] on Tokio's blocking thread pool and hands its entries to an async
+//! [`Stream`], so services can `.await` journal changes without dedicating a thread of their
+//! own to `wait_for_more` reads.
+
+use crate::{
+    UsnResult,
+    errors::UsnError,
+    journal::{UsnEntry, UsnJournalIter},
+};
+use futures_core::Stream;
+use log::debug;
+use std::{
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
+use windows::{
+    Win32::{
+        Foundation::{DUPLICATE_SAME_ACCESS, DuplicateHandle, HANDLE},
+        System::{
+            IO::CancelSynchronousIo,
+            Threading::{GetCurrentProcess, GetCurrentThread},
+        },
+    },
+    core::Owned,
+};
+
+/// Number of entries buffered between the blocking reader and the stream consumer.
+const STREAM_CHANNEL_CAPACITY: usize = 256;
+
+/// Async stream of USN journal entries.
+///
+/// Created by [`UsnJournal::stream`](crate::journal::UsnJournal::stream). Entries are read on
+/// a [`spawn_blocking`](tokio::task::spawn_blocking) task and yielded in journal order; the
+/// stream ends when the underlying iterator does, which with `wait_for_more` set only
+/// happens once its `timeout` elapses without new records.
+///
+/// # Cancellation
+/// Dropping the stream stops the background task. A read blocked waiting for more records is
+/// cancelled with `CancelSynchronousIo`; if the drop races with the start of a read, the task
+/// stops once that read returns, i.e. after the next record or the read `timeout`.
+pub struct UsnJournalStream {
+    receiver: mpsc::Receiver<UsnResult<UsnEntry>>,
+    worker: Arc<Worker>,
+}
+
+impl UsnJournalStream {
+    /// Moves `iter` onto a blocking task of the current Tokio runtime.
+    pub(crate) fn spawn(iter: UsnJournalIter) -> UsnResult<Self> {
+        let runtime = tokio::runtime::Handle::try_current().map_err(|err| {
+            UsnError::OtherError(format!("journal streams need a Tokio runtime: {err}"))
+        })?;
+
+        let (sender, receiver) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        let worker = Arc::new(Worker::default());
+        let task_worker = Arc::clone(&worker);
+        runtime.spawn_blocking(move || task_worker.run(iter, sender));
+
+        Ok(UsnJournalStream { receiver, worker })
+    }
+}
+
+impl Stream for UsnJournalStream {
+    type Item = UsnResult<UsnEntry>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for UsnJournalStream {
+    fn drop(&mut self) {
+        self.worker.cancel();
+    }
+}
+
+/// Real handle to the thread running a [`Worker`], used to cancel its blocked reads.
+struct WorkerThread(Owned<HANDLE>);
+
+// SAFETY: a thread handle may be used from any thread; it is only passed to
+// `CancelSynchronousIo` and closed once, under the worker's mutex.
+unsafe impl Send for WorkerThread {}
+
+/// State shared between a [`UsnJournalStream`] and its background task.
+#[derive(Default)]
+struct Worker {
+    cancelled: AtomicBool,
+    /// Set while the task runs, so reads are only ever cancelled on the task's own thread.
+    thread: Mutex<Option<WorkerThread>>,
+}
+
+impl Worker {
+    fn run(&self, mut iter: UsnJournalIter, sender: mpsc::Sender<UsnResult<UsnEntry>>) {
+        self.register_current_thread();
+        while !self.cancelled.load(Ordering::Acquire) {
+            let Some(result) = iter.next() else {
+                break;
+            };
+            if sender.blocking_send(result).is_err() {
+                break;
+            }
+        }
+        // The blocking pool reuses this thread, so it must not be cancelled once we are done.
+        if let Ok(mut thread) = self.thread.lock() {
+            thread.take();
+        }
+        debug!("Journal stream task finished");
+    }
+
+    fn register_current_thread(&self) {
+        let mut handle = HANDLE::default();
+        // SAFETY: duplicates the current thread's pseudo-handle into a real handle owned by
+        // this process; all arguments are valid for the duration of the call.
+        let duplicated = unsafe {
+            DuplicateHandle(
+                GetCurrentProcess(),
+                GetCurrentThread(),
+                GetCurrentProcess(),
+                &mut handle,
+                0,
+                false,
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        if let Err(err) = duplicated {
+            debug!("Journal stream reads cannot be cancelled early: {err}");
+            return;
+        }
+        // SAFETY: `handle` was just created for us and is closed when `Owned` drops.
+        let handle = unsafe { Owned::new(handle) };
+        if let Ok(mut thread) = self.thread.lock() {
+            *thread = Some(WorkerThread(handle));
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        if let Ok(thread) = self.thread.lock()
+            && let Some(WorkerThread(handle)) = thread.as_ref()
+        {
+            // SAFETY: the handle refers to the task's thread, which is still running the
+            // task while the handle is registered. Fails harmlessly if no read is pending.
+            let _ = unsafe { CancelSynchronousIo(**handle) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{journal::EnumOptions, volume::Volume};
+    use std::{future::poll_fn, time::Duration};
+
+    async fn next_entry(stream: &mut UsnJournalStream) -> Option<UsnResult<UsnEntry>> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_yields_new_changes() -> Result<(), UsnError> {
+        let volume = match Volume::from_drive_letter('C') {
+            Ok(volume) => volume,
+            Err(UsnError::PermissionError(_)) => {
+                eprintln!("Skipping test - requires admin privileges");
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let journal = volume.journal();
+        let start_usn = journal.query()?.next_usn;
+
+        let mut stream = journal.stream(EnumOptions {
+            start_usn,
+            wait_for_more: true,
+            timeout: 1,
+            ..Default::default()
+        })?;
+
+        let file_name = "usn_journal_rs_stream.txt";
+        let file_path = std::env::temp_dir().join(file_name);
+        tokio::task::spawn_blocking({
+            let file_path = file_path.clone();
+            move || std::fs::write(file_path, b"streamed")
+        })
+        .await
+        .map_err(|err| UsnError::OtherError(err.to_string()))??;
+
+        let found = tokio::time::timeout(Duration::from_secs(10), async {
+            while let Some(result) = next_entry(&mut stream).await {
+                if result?.file_name == file_name {
+                    return Ok(true);
+                }
+            }
+            Ok::<_, UsnError>(false)
+        })
+        .await
+        .unwrap_or(Ok(false));
+        std::fs::remove_file(&file_path)?;
+
+        assert!(found?, "the new file should be streamed");
+        // Dropping the stream must not hang on the pending read.
+        drop(stream);
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+//! Encoding helpers for the opaque tokens produced by [`Cursor::to_token`].
+//!
+//! Tokens are URL-safe base64 without padding, so they can be stored in files, environment
+//! variables or URLs as-is. The payload ends with a CRC-32 of the preceding bytes.
+//!
+//! [`Cursor::to_token`]: crate::journal::Cursor::to_token
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes `bytes` as URL-safe base64 without padding.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        // A chunk of n bytes carries n + 1 significant sextets.
+        for i in 0..=chunk.len() {
+            let sextet = (group >> (18 - 6 * i)) & 0x3F;
+            encoded.push(char::from(ALPHABET[sextet as usize]));
+        }
+    }
+    encoded
+}
+
+/// Decodes URL-safe base64 without padding, returning `None` on any invalid input.
+pub(crate) fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut group = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let sextet = ALPHABET.iter().position(|&a| a == c)? as u32;
+            group |= sextet << (18 - 6 * i);
+        }
+        let byte_count = chunk.len() - 1;
+        // Bits below the last full byte must be zero, so each token has one spelling.
+        if group & (0x00FF_FFFF >> (8 * byte_count)) != 0 {
+            return None;
+        }
+        decoded.extend((0..byte_count).map(|i| (group >> (16 - 8 * i)) as u8));
+    }
+    Some(decoded)
+}
+
+/// Computes the CRC-32 (IEEE) checksum of `bytes`.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_round_trips_every_length() {
+        let bytes = (0u8..=255).collect::<Vec<_>>();
+        for len in 0..16 {
+            let encoded = encode_base64(&bytes[250 - len..250]);
+            assert_eq!(encoded.len(), (len * 4).div_ceil(3));
+            assert_eq!(decode_base64(&encoded).unwrap(), &bytes[250 - len..250]);
+        }
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode_base64(&[0xFB, 0xFF]), "-_8");
+    }
+
+    #[test]
+    fn test_base64_rejects_invalid_input() {
+        assert!(decode_base64("Zm9vY").is_none());
+        assert!(decode_base64("Zm9v+mFy").is_none());
+        assert!(decode_base64("Zm9=").is_none());
+        // "Zh" has non-zero trailing bits; "Zg" is the canonical encoding of "f".
+        assert!(decode_base64("Zh").is_none());
+        assert_eq!(decode_base64("Zg").unwrap(), b"f");
+    }
+
+    #[test]
+    fn test_crc32_matches_reference_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+}
@@ -0,0 +1,395 @@
+//! In-memory directory tree built from MFT entries.
+//!
+//! The `DirectoryTree` struct indexes a full MFT enumeration by parent so callers can walk
+//! the volume's directory hierarchy without issuing further Windows API calls.
+
+use crate::journal::UsnEntry;
+use crate::mft::{MftEntry, ROOT_RECORD_NUMBER, is_root_record};
+use crate::{FileId, UsnReason, path};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Maximum nesting depth emitted by [`DirectoryTree::to_serializable`].
+///
+/// Deeper descendants are left out so that serializing (and dropping) the nested structure
+/// cannot overflow the stack.
+#[cfg(feature = "serde")]
+pub const MAX_SERIALIZABLE_DEPTH: usize = 512;
+
+/// Directory hierarchy of a volume, indexed by file reference number.
+#[derive(Debug)]
+pub struct DirectoryTree {
+    root_fid: FileId,
+    root_name: String,
+    entries: HashMap<FileId, MftEntry>,
+    children: HashMap<FileId, Vec<FileId>>,
+}
+
+impl DirectoryTree {
+    /// Builds a tree from MFT entries, naming the root node `root_name`.
+    ///
+    /// Entries whose parent chain does not reach the volume root are kept but are not
+    /// reachable from [`DirectoryTree::root_fid`].
+    pub(crate) fn from_entries(root_name: String, entries: Vec<MftEntry>) -> Self {
+        let mut root_fid = None;
+        let mut children: HashMap<FileId, Vec<FileId>> = HashMap::new();
+        let mut by_fid = HashMap::with_capacity(entries.len());
+
+        for entry in entries {
+            if entry.fid == entry.parent_fid {
+                // The volume root reports itself as its own parent.
+                root_fid = Some(entry.fid);
+            } else {
+                children
+                    .entry(entry.parent_fid)
+                    .or_default()
+                    .push(entry.fid);
+            }
+            by_fid.insert(entry.fid, entry);
+        }
+
+        // The root may be missing from the enumeration; its record number is fixed on NTFS.
+        let root_fid = root_fid
+            .or_else(|| children.keys().copied().find(|&fid| is_root_record(fid)))
+            .unwrap_or(FileId::from_u64(ROOT_RECORD_NUMBER));
+
+        DirectoryTree {
+            root_fid,
+            root_name,
+            entries: by_fid,
+            children,
+        }
+    }
+
+    /// Returns the file reference number of the volume root.
+    pub fn root_fid(&self) -> FileId {
+        self.root_fid
+    }
+
+    /// Returns the display name of the volume root, e.g. `C:\`.
+    pub fn root_name(&self) -> &str {
+        &self.root_name
+    }
+
+    /// Returns the MFT entry for `fid`, if it was part of the enumeration.
+    pub fn entry(&self, fid: FileId) -> Option<&MftEntry> {
+        self.entries.get(&fid)
+    }
+
+    /// Returns the file reference numbers of the direct children of `fid`.
+    pub fn children(&self, fid: FileId) -> &[FileId] {
+        self.children.get(&fid).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the file reference numbers of entries whose parent is not in the tree, sorted.
+    ///
+    /// The root is never reported, and neither are its children when the root itself was
+    /// missing from the enumeration. Orphans point at corruption or at directories deleted
+    /// while the MFT was being enumerated; they are not reachable from the root.
+    pub fn orphans(&self) -> Vec<FileId> {
+        let mut orphans = self
+            .entries
+            .values()
+            .filter(|entry| {
+                entry.fid != self.root_fid
+                    && entry.parent_fid != self.root_fid
+                    && !self.entries.contains_key(&entry.parent_fid)
+            })
+            .map(|entry| entry.fid)
+            .collect::<Vec<_>>();
+        orphans.sort_unstable();
+        orphans
+    }
+
+    /// Returns the path of `fid`, starting at the root name.
+    ///
+    /// Returns `None` if `fid` is not in the tree or its parent chain does not reach the root.
+    pub fn path(&self, fid: FileId) -> Option<PathBuf> {
+        path::walk_parent_links(
+            fid,
+            |fid| {
+                (fid == self.root_fid || is_root_record(fid))
+                    .then(|| PathBuf::from(&self.root_name))
+            },
+            |fid| {
+                let entry = self.entries.get(&fid)?;
+                Some((entry.parent_fid, entry.file_name.as_os_str()))
+            },
+        )
+    }
+
+    /// Updates the tree with a change read from the USN journal.
+    ///
+    /// Deletions remove the entry; `RENAME_OLD_NAME` records are ignored, as the matching
+    /// `RENAME_NEW_NAME` record carries the new name and parent. Any other record inserts the
+    /// entry or moves it under its reported parent. Keeps a tree built from an MFT scan in
+    /// sync with later changes.
+    pub fn apply(&mut self, entry: &UsnEntry) {
+        if entry.reason.contains(UsnReason::FILE_DELETE) {
+            self.remove(entry.fid);
+        } else if !entry.reason.contains(UsnReason::RENAME_OLD_NAME) {
+            self.upsert(entry.to_mft_entry());
+        }
+    }
+
+    fn upsert(&mut self, entry: MftEntry) {
+        let (fid, parent_fid) = (entry.fid, entry.parent_fid);
+        if let Some(old) = self.entries.insert(fid, entry) {
+            if old.parent_fid == parent_fid {
+                return;
+            }
+            self.unlink(old.parent_fid, fid);
+        }
+        if fid != parent_fid {
+            self.children.entry(parent_fid).or_default().push(fid);
+        }
+    }
+
+    fn remove(&mut self, fid: FileId) {
+        if let Some(old) = self.entries.remove(&fid) {
+            self.unlink(old.parent_fid, fid);
+        }
+    }
+
+    fn unlink(&mut self, parent_fid: FileId, fid: FileId) {
+        if let Some(siblings) = self.children.get_mut(&parent_fid) {
+            siblings.retain(|&sibling| sibling != fid);
+        }
+    }
+
+    /// Returns the number of entries in the tree.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the tree holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Converts the tree into nested [`TreeNode`]s that serde can serialize, e.g. to JSON.
+    ///
+    /// The tree is built iteratively, and descendants deeper than
+    /// [`MAX_SERIALIZABLE_DEPTH`] are omitted so that serializing the result cannot overflow
+    /// the stack.
+    #[cfg(feature = "serde")]
+    pub fn to_serializable(&self) -> TreeNode {
+        // Pre-order walk; reversing it visits every child before its parent.
+        let mut order = Vec::new();
+        let mut stack = vec![(self.root_fid, 0)];
+        while let Some((fid, depth)) = stack.pop() {
+            order.push(fid);
+            if depth < MAX_SERIALIZABLE_DEPTH {
+                stack.extend(self.children(fid).iter().rev().map(|&c| (c, depth + 1)));
+            }
+        }
+
+        let mut built: HashMap<FileId, TreeNode> = HashMap::new();
+        for &fid in order.iter().rev() {
+            let children = self
+                .children(fid)
+                .iter()
+                .filter_map(|child| built.remove(child))
+                .collect();
+            let node = TreeNode {
+                name: self.node_name(fid),
+                fid,
+                is_dir: fid == self.root_fid || self.entry(fid).is_some_and(MftEntry::is_dir),
+                children,
+            };
+            built.insert(fid, node);
+        }
+
+        built.remove(&self.root_fid).unwrap_or_else(|| TreeNode {
+            name: self.root_name().to_string(),
+            fid: self.root_fid,
+            is_dir: true,
+            children: Vec::new(),
+        })
+    }
+
+    #[cfg(feature = "serde")]
+    fn node_name(&self, fid: FileId) -> String {
+        if fid == self.root_fid {
+            return self.root_name().to_string();
+        }
+        self.entry(fid)
+            .map(|entry| entry.file_name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// A serializable node of a [`DirectoryTree`].
+///
+/// Created by [`DirectoryTree::to_serializable`].
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    pub fid: FileId,
+    pub is_dir: bool,
+    pub children: Vec<TreeNode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    const ROOT: u64 = 0x0005_0000_0000_0005;
+
+    fn mock_entry(fid: u64, parent_fid: u64, name: &str, is_dir: bool) -> MftEntry {
+        MftEntry {
+            usn: 0,
+            fid: fid.into(),
+            parent_fid: parent_fid.into(),
+            file_name: OsString::from(name),
+            file_attributes: if is_dir { 0x10 } else { 0x20 },
+            timestamps: None,
+            sizes: None,
+        }
+    }
+
+    fn mock_tree() -> DirectoryTree {
+        DirectoryTree::from_entries(
+            r"C:\".to_string(),
+            vec![
+                mock_entry(0x30, 0x20, "deep.txt", false),
+                mock_entry(0x20, 0x10, "Sub", true),
+                mock_entry(0x10, ROOT, "Docs", true),
+                mock_entry(0x11, ROOT, "top.txt", false),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_tree_indexes_children_by_parent() {
+        let tree = mock_tree();
+
+        assert_eq!(tree.root_fid(), ROOT);
+        assert_eq!(tree.root_name(), r"C:\");
+        assert_eq!(tree.len(), 4);
+        assert_eq!(
+            tree.children(FileId::from(ROOT)),
+            &[FileId(0x10), FileId(0x11)]
+        );
+        assert_eq!(tree.children(FileId(0x20)), &[FileId(0x30)]);
+        assert!(tree.children(FileId(0x30)).is_empty());
+        assert_eq!(tree.entry(FileId(0x20)).unwrap().file_name, "Sub");
+    }
+
+    #[test]
+    fn test_orphans_lists_entries_with_missing_parent() {
+        let tree = DirectoryTree::from_entries(
+            r"C:\".to_string(),
+            vec![
+                mock_entry(0x10, ROOT, "Docs", true),
+                mock_entry(0x11, 0x10, "kept.txt", false),
+                mock_entry(0x40, 0x99, "lost.txt", false),
+                mock_entry(0x41, 0x40, "child-of-orphan.txt", false),
+                mock_entry(0x30, 0x98, "lost-dir", true),
+            ],
+        );
+
+        assert_eq!(tree.orphans(), vec![FileId(0x30), FileId(0x40)]);
+        assert!(mock_tree().orphans().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_serializable_matches_directory_layout() {
+        let json = serde_json::to_value(mock_tree().to_serializable()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": r"C:\",
+                "fid": ROOT,
+                "is_dir": true,
+                "children": [
+                    {
+                        "name": "Docs",
+                        "fid": 0x10,
+                        "is_dir": true,
+                        "children": [
+                            {
+                                "name": "Sub",
+                                "fid": 0x20,
+                                "is_dir": true,
+                                "children": [
+                                    { "name": "deep.txt", "fid": 0x30, "is_dir": false, "children": [] }
+                                ]
+                            }
+                        ]
+                    },
+                    { "name": "top.txt", "fid": 0x11, "is_dir": false, "children": [] }
+                ]
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_serializable_caps_depth() {
+        let entries = (1..=MAX_SERIALIZABLE_DEPTH as u64 + 10)
+            .map(|fid| {
+                let parent_fid = if fid == 1 { ROOT } else { 0x1000 + fid - 1 };
+                mock_entry(0x1000 + fid, parent_fid, "nested", true)
+            })
+            .collect();
+        let tree = DirectoryTree::from_entries(r"C:\".to_string(), entries);
+
+        let mut depth = 0;
+        let mut node = &tree.to_serializable();
+        while let Some(child) = node.children.first() {
+            depth += 1;
+            node = child;
+        }
+
+        assert_eq!(depth, MAX_SERIALIZABLE_DEPTH);
+    }
+
+    fn journal_entry(fid: u64, parent_fid: u64, reason: UsnReason, name: &str) -> UsnEntry {
+        UsnEntry {
+            usn: 0,
+            time: std::time::SystemTime::UNIX_EPOCH,
+            fid: fid.into(),
+            parent_fid: parent_fid.into(),
+            reason,
+            source_info: 0,
+            file_name: OsString::from(name),
+            file_attributes: 0x20,
+        }
+    }
+
+    #[test]
+    fn test_apply_journal_events_updates_paths() {
+        let mut tree = DirectoryTree::from_entries(r"C:\".to_string(), Vec::new());
+        let events = [
+            journal_entry(0x10, ROOT, UsnReason::FILE_CREATE, "Docs"),
+            journal_entry(0x11, ROOT, UsnReason::FILE_CREATE, "draft.txt"),
+            journal_entry(0x12, 0x10, UsnReason::FILE_CREATE, "gone.txt"),
+            journal_entry(0x11, ROOT, UsnReason::RENAME_OLD_NAME, "draft.txt"),
+            journal_entry(0x11, 0x10, UsnReason::RENAME_NEW_NAME, "final.txt"),
+            journal_entry(
+                0x12,
+                0x10,
+                UsnReason::FILE_DELETE | UsnReason::CLOSE,
+                "gone.txt",
+            ),
+        ];
+        for event in &events {
+            tree.apply(event);
+        }
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(
+            tree.path(FileId(0x11)),
+            Some(PathBuf::from(r"C:\Docs\final.txt"))
+        );
+        assert_eq!(tree.path(FileId(0x10)), Some(PathBuf::from(r"C:\Docs")));
+        assert_eq!(tree.path(FileId(0x12)), None);
+        assert_eq!(tree.children(FileId::from(ROOT)), &[FileId(0x10)]);
+        assert_eq!(tree.children(FileId(0x10)), &[FileId(0x11)]);
+    }
+}
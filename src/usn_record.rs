@@ -1,68 +1,333 @@
-use crate::errors::UsnError;
+use crate::{FileId, errors::UsnError};
+use log::debug;
 use std::{
-    ffi::OsString,
-    mem::{MaybeUninit, offset_of, size_of},
-    os::windows::ffi::OsStringExt,
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    mem::{offset_of, size_of},
+    ops::{Deref, DerefMut},
+    os::windows::ffi::{OsStrExt, OsStringExt},
 };
-use windows::Win32::System::Ioctl::USN_RECORD_V2;
+use windows::Win32::System::Ioctl::{USN_RECORD_V2, USN_RECORD_V3, USN_RECORD_V4};
 
 const USN_RECORD_V2_HEADER_LEN: usize = offset_of!(USN_RECORD_V2, FileName);
+const USN_RECORD_V3_HEADER_LEN: usize = offset_of!(USN_RECORD_V3, FileName);
+const USN_RECORD_V4_HEADER_LEN: usize = offset_of!(USN_RECORD_V4, Extents);
 
-#[repr(C)]
+// The record layouts this parser understands.
+const SUPPORTED_MAJOR_VERSIONS: [u16; 3] = [2, 3, 4];
+
+// `USN_RECORD_V4` only lists the byte ranges a file's writes touched, with range tracking
+// enabled. It has no name and is followed by a V3 record for the same change, so it is
+// stepped over rather than turned into an entry.
+const RANGE_TRACKING_MAJOR_VERSION: u16 = 4;
+
+/// Record versions requested from `FSCTL_READ_USN_JOURNAL` and `FSCTL_ENUM_USN_DATA`.
+///
+/// With `MaxMajorVersion` 3, ReFS returns `USN_RECORD_V3` with its full 128-bit file IDs; the
+/// V0 input structures only ever return `USN_RECORD_V2`.
+pub(crate) const MIN_MAJOR_VERSION: u16 = 2;
+pub(crate) const MAX_MAJOR_VERSION: u16 = 3;
+
+/// Controls how iterators handle records whose `MajorVersion` the parser does not understand.
+///
+/// Either way the record is stepped over using its `RecordLength`, so the records that
+/// follow it are still parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnknownVersionPolicy {
+    /// Yield a [`UsnError::UnsupportedRecordVersion`] in place of the record.
+    #[default]
+    Error,
+    /// Drop the record without reporting it.
+    Skip,
+}
+
+/// Controls how names that are not valid UTF-16 are presented to users.
+///
+/// NTFS allows unpaired surrogates in file names. Entries always keep the raw name in their
+/// `file_name`; the policy only applies to the names and paths handed out for display, e.g.
+/// by `display_name` on the entry types or by a
+/// [`PathResolver`](crate::path::PathResolver) configured with it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameEncodingPolicy {
+    /// Return the name unchanged, invalid code units included.
+    #[default]
+    Preserve,
+    /// Replace each invalid code unit with the given character.
+    ReplaceWith(char),
+    /// Leave out names that are not valid UTF-16.
+    Skip,
+}
+
+impl NameEncodingPolicy {
+    /// Applies the policy to `name`, returning `None` if the name should be skipped.
+    ///
+    /// Valid names are always returned unchanged and without copying.
+    pub fn apply<'a>(&self, name: &'a OsStr) -> Option<Cow<'a, OsStr>> {
+        if *self == NameEncodingPolicy::Preserve || name.to_str().is_some() {
+            return Some(Cow::Borrowed(name));
+        }
+
+        match *self {
+            NameEncodingPolicy::ReplaceWith(replacement) => {
+                let replaced = char::decode_utf16(name.encode_wide())
+                    .map(|c| c.unwrap_or(replacement))
+                    .collect::<String>();
+                Some(Cow::Owned(OsString::from(replaced)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Fixed fields of a `USN_RECORD_V2` or `USN_RECORD_V3`.
+///
+/// Records of other versions, `USN_RECORD_V4` included, are read with the V2 layout so that
+/// their length and version are available to [`check_record_version`]; their other fields
+/// are meaningless.
 #[derive(Clone, Copy, Debug)]
-pub(crate) struct UsnRecordV2Header {
+pub(crate) struct UsnRecordHeader {
     pub(crate) record_length: u32,
     pub(crate) major_version: u16,
-    pub(crate) _minor_version: u16,
-    pub(crate) file_reference_number: u64,
-    pub(crate) parent_file_reference_number: u64,
+    pub(crate) file_reference_number: FileId,
+    pub(crate) parent_file_reference_number: FileId,
     pub(crate) usn: i64,
     pub(crate) timestamp: i64,
     pub(crate) reason: u32,
     pub(crate) source_info: u32,
-    pub(crate) _security_id: u32,
     pub(crate) file_attributes: u32,
     pub(crate) file_name_length: u16,
     pub(crate) file_name_offset: u16,
 }
 
+impl UsnRecordHeader {
+    /// Length of the fixed part of the record, i.e. the offset of `FileName`.
+    fn fixed_len(&self) -> usize {
+        fixed_header_len(self.major_version)
+    }
+}
+
+fn fixed_header_len(major_version: u16) -> usize {
+    match major_version {
+        3 => USN_RECORD_V3_HEADER_LEN,
+        RANGE_TRACKING_MAJOR_VERSION => USN_RECORD_V4_HEADER_LEN,
+        _ => USN_RECORD_V2_HEADER_LEN,
+    }
+}
+
+/// Largest size the iterators grow their read buffer to when a record does not fit.
+///
+/// A record with the longest possible file name is well under 1 KiB, so this is only reached
+/// when the reported record length is corrupt.
+pub(crate) const MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Zero-initialized byte buffer whose storage is guaranteed to be 8-byte aligned.
+///
+/// The kernel writes `USN_RECORD_*` structures, which contain 8-byte fields, into this buffer.
+/// A `Vec<u8>` allocation is only guaranteed to be 1-byte aligned, so the bytes are backed by
+/// `u64` storage instead.
+#[derive(Debug, Clone)]
+pub(crate) struct AlignedBuffer {
+    storage: Vec<u64>,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    pub(crate) fn new(len: usize) -> Self {
+        AlignedBuffer {
+            storage: vec![0u64; len.div_ceil(size_of::<u64>())],
+            len,
+        }
+    }
+
+    /// Replaces the buffer with a zeroed one of at least `len` bytes, at least doubling it.
+    ///
+    /// Doubling stops at [`MAX_BUFFER_SIZE`], but a buffer configured larger than that is
+    /// never shrunk.
+    ///
+    /// # Errors
+    /// Returns `UsnError::OtherError` if `len` exceeds [`MAX_BUFFER_SIZE`].
+    pub(crate) fn grow_to(&mut self, len: usize) -> Result<(), UsnError> {
+        if len > MAX_BUFFER_SIZE {
+            return Err(UsnError::OtherError(format!(
+                "record of {len} bytes does not fit the maximum buffer size of {MAX_BUFFER_SIZE} bytes"
+            )));
+        }
+        let len = len
+            .max(self.len.saturating_mul(2))
+            .min(MAX_BUFFER_SIZE)
+            .max(self.len);
+        debug!("Growing read buffer from {} to {len} bytes", self.len);
+        *self = AlignedBuffer::new(len);
+        Ok(())
+    }
+}
+
+/// Returns the buffer size needed to read the first record, if it did not fit.
+///
+/// `header_len` is the size of the next-start header in front of the records. Only a full
+/// buffer can hold a cut-off record: then the record's `RecordLength` runs past the end, or
+/// the buffer is too small to even hold the length. A length running past a shorter read is
+/// corrupt and left for the parser to report.
+pub(crate) fn oversized_first_record(
+    buffer: &[u8],
+    bytes_read: u32,
+    header_len: usize,
+) -> Option<usize> {
+    let read = buffer.get(..bytes_read as usize).unwrap_or(buffer);
+    if read.len() < buffer.len() || read.len() <= header_len {
+        return None;
+    }
+
+    match read_unaligned_from::<u32>(read, header_len) {
+        Some(record_len) => {
+            let needed = header_len.saturating_add(record_len as usize);
+            (needed > read.len()).then_some(needed)
+        }
+        None => Some(buffer.len().saturating_mul(2)),
+    }
+}
+
+impl From<&[u8]> for AlignedBuffer {
+    fn from(bytes: &[u8]) -> Self {
+        let mut buffer = AlignedBuffer::new(bytes.len());
+        buffer.copy_from_slice(bytes);
+        buffer
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `storage` holds at least `len` initialized bytes, and u8 has no alignment
+        // requirement.
+        unsafe { std::slice::from_raw_parts(self.storage.as_ptr().cast::<u8>(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as in `deref`, and the slice borrows `storage` mutably.
+        unsafe { std::slice::from_raw_parts_mut(self.storage.as_mut_ptr().cast::<u8>(), self.len) }
+    }
+}
+
 pub(crate) fn read_unaligned_from<T: Copy>(buffer: &[u8], offset: usize) -> Option<T> {
     let bytes = buffer.get(offset..offset.checked_add(size_of::<T>())?)?;
     Some(unsafe { (bytes.as_ptr() as *const T).read_unaligned() })
 }
 
-pub(crate) fn parse_usn_record_v2_header(
+fn read_field<T: Copy>(buffer: &[u8], offset: usize, context: &str) -> Result<T, UsnError> {
+    read_unaligned_from(buffer, offset)
+        .ok_or_else(|| UsnError::OtherError(format!("{context} missing fixed header")))
+}
+
+pub(crate) fn parse_usn_record_header(
     buffer: &[u8],
     offset: u32,
     bytes_read: u32,
     context: &str,
-) -> Result<(UsnRecordV2Header, u32), UsnError> {
+) -> Result<(UsnRecordHeader, u32), UsnError> {
     let base = offset as usize;
     let read_end = bytes_read as usize;
+    let read_end = read_end.min(buffer.len());
+
+    // RecordLength and MajorVersion sit at the same offsets in every record version.
+    let prefix_len = offset_of!(USN_RECORD_V2, MinorVersion);
+    let major_version: u16 = read_field(buffer, base + prefix_len - size_of::<u16>(), context)?;
     let header_end = base
-        .checked_add(USN_RECORD_V2_HEADER_LEN)
+        .checked_add(fixed_header_len(major_version))
         .ok_or_else(|| UsnError::OtherError(format!("{context} header length overflow")))?;
     if header_end > read_end {
         return Err(UsnError::OtherError(format!(
             "{context} missing fixed header"
         )));
     }
-    let header_bytes = buffer
-        .get(base..header_end)
-        .ok_or_else(|| UsnError::OtherError(format!("{context} missing fixed header")))?;
-    let mut header = MaybeUninit::<UsnRecordV2Header>::zeroed();
-    unsafe {
-        std::ptr::copy_nonoverlapping(
-            header_bytes.as_ptr(),
-            header.as_mut_ptr().cast::<u8>(),
-            USN_RECORD_V2_HEADER_LEN,
-        );
-    }
-    let header = unsafe { header.assume_init() };
+
+    let header = if major_version == 3 {
+        let field = |offset: usize| base + offset;
+        UsnRecordHeader {
+            record_length: read_field(buffer, base, context)?,
+            major_version,
+            file_reference_number: FileId::from_le_bytes(read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, FileReferenceNumber)),
+                context,
+            )?),
+            parent_file_reference_number: FileId::from_le_bytes(read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, ParentFileReferenceNumber)),
+                context,
+            )?),
+            usn: read_field(buffer, field(offset_of!(USN_RECORD_V3, Usn)), context)?,
+            timestamp: read_field(buffer, field(offset_of!(USN_RECORD_V3, TimeStamp)), context)?,
+            reason: read_field(buffer, field(offset_of!(USN_RECORD_V3, Reason)), context)?,
+            source_info: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, SourceInfo)),
+                context,
+            )?,
+            file_attributes: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, FileAttributes)),
+                context,
+            )?,
+            file_name_length: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, FileNameLength)),
+                context,
+            )?,
+            file_name_offset: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V3, FileNameOffset)),
+                context,
+            )?,
+        }
+    } else {
+        let field = |offset: usize| base + offset;
+        UsnRecordHeader {
+            record_length: read_field(buffer, base, context)?,
+            major_version,
+            file_reference_number: FileId::from(read_field::<u64>(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, FileReferenceNumber)),
+                context,
+            )?),
+            parent_file_reference_number: FileId::from(read_field::<u64>(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, ParentFileReferenceNumber)),
+                context,
+            )?),
+            usn: read_field(buffer, field(offset_of!(USN_RECORD_V2, Usn)), context)?,
+            timestamp: read_field(buffer, field(offset_of!(USN_RECORD_V2, TimeStamp)), context)?,
+            reason: read_field(buffer, field(offset_of!(USN_RECORD_V2, Reason)), context)?,
+            source_info: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, SourceInfo)),
+                context,
+            )?,
+            file_attributes: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, FileAttributes)),
+                context,
+            )?,
+            file_name_length: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, FileNameLength)),
+                context,
+            )?,
+            file_name_offset: read_field(
+                buffer,
+                field(offset_of!(USN_RECORD_V2, FileNameOffset)),
+                context,
+            )?,
+        }
+    };
 
     if header.record_length == 0 {
         return Err(UsnError::OtherError(format!(
-            "{context} contains invalid zero RecordLength"
+            "{context} at offset {base} contains invalid zero RecordLength"
         )));
     }
 
@@ -71,26 +336,115 @@ pub(crate) fn parse_usn_record_v2_header(
         .ok_or_else(|| UsnError::OtherError(format!("{context} length overflow")))?;
     if record_end > read_end {
         return Err(UsnError::OtherError(format!(
-            "{context} extends past buffer bounds"
+            "{context} at offset {base} with RecordLength {} extends past buffer bounds",
+            header.record_length
         )));
     }
 
-    if header.major_version != 2 {
-        return Err(UsnError::OtherError(format!(
-            "Unsupported {context} version: {}",
-            header.major_version
-        )));
+    Ok((header, header.record_length))
+}
+
+/// Applies `policy` to the record's major version.
+///
+/// Returns `Ok(true)` if the record can be parsed and `Ok(false)` if it should be skipped.
+/// `USN_RECORD_V4` range records are always skipped, whatever the policy.
+pub(crate) fn check_record_version(
+    header: &UsnRecordHeader,
+    policy: UnknownVersionPolicy,
+) -> Result<bool, UsnError> {
+    if header.major_version == RANGE_TRACKING_MAJOR_VERSION {
+        return Ok(false);
+    }
+    if SUPPORTED_MAJOR_VERSIONS.contains(&header.major_version) {
+        return Ok(true);
     }
 
-    Ok((header, header.record_length))
+    match policy {
+        UnknownVersionPolicy::Error => {
+            Err(UsnError::UnsupportedRecordVersion(header.major_version))
+        }
+        UnknownVersionPolicy::Skip => {
+            debug!(
+                "Skipping record with unsupported major version {}",
+                header.major_version
+            );
+            Ok(false)
+        }
+    }
 }
 
-pub(crate) fn parse_usn_record_v2_name(
+pub(crate) fn parse_usn_record_name(
     buffer: &[u8],
     base: usize,
-    header: &UsnRecordV2Header,
+    header: &UsnRecordHeader,
     context: &str,
 ) -> Result<OsString, UsnError> {
+    let name_units = usn_record_name_bytes(buffer, base, header, context)?
+        .chunks_exact(size_of::<u16>())
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect::<Vec<_>>();
+
+    Ok(OsString::from_wide(&name_units))
+}
+
+/// File name extensions to keep, matched against the raw UTF-16 name of a record.
+///
+/// Matching happens before the name is decoded, so records that are filtered out cost no
+/// allocation. An empty filter accepts every name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExtensionFilter {
+    // Each suffix is `.ext` in UTF-16, with ASCII letters lowercased.
+    suffixes: Vec<Vec<u16>>,
+}
+
+impl ExtensionFilter {
+    /// Builds a filter from extensions written with or without the leading dot.
+    pub(crate) fn new(extensions: &[String]) -> Self {
+        let suffixes = extensions
+            .iter()
+            .map(|extension| {
+                format!(".{}", extension.trim_start_matches('.'))
+                    .to_ascii_lowercase()
+                    .encode_utf16()
+                    .collect()
+            })
+            .collect();
+        ExtensionFilter { suffixes }
+    }
+
+    /// Returns true if the UTF-16LE `name` ends with one of the extensions.
+    pub(crate) fn matches(&self, name: &[u8]) -> bool {
+        if self.suffixes.is_empty() {
+            return true;
+        }
+
+        let units = name.len() / size_of::<u16>();
+        self.suffixes.iter().any(|suffix| {
+            units > suffix.len()
+                && name[(units - suffix.len()) * size_of::<u16>()..]
+                    .chunks_exact(size_of::<u16>())
+                    .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                    .zip(suffix)
+                    .all(|(unit, &expected)| ascii_lowercase_unit(unit) == expected)
+        })
+    }
+}
+
+fn ascii_lowercase_unit(unit: u16) -> u16 {
+    if (u16::from(b'A')..=u16::from(b'Z')).contains(&unit) {
+        unit + u16::from(b'a' - b'A')
+    } else {
+        unit
+    }
+}
+
+/// Returns the raw UTF-16LE bytes of a record's file name, validated against the record.
+pub(crate) fn usn_record_name_bytes<'a>(
+    buffer: &'a [u8],
+    base: usize,
+    header: &UsnRecordHeader,
+    context: &str,
+) -> Result<&'a [u8], UsnError> {
     let file_name_len = header.file_name_length as usize;
     if !file_name_len.is_multiple_of(size_of::<u16>()) {
         return Err(UsnError::OtherError(format!(
@@ -99,7 +453,7 @@ pub(crate) fn parse_usn_record_v2_name(
     }
 
     let file_name_offset = header.file_name_offset as usize;
-    if file_name_offset < USN_RECORD_V2_HEADER_LEN
+    if file_name_offset < header.fixed_len()
         || file_name_offset
             .checked_add(file_name_len)
             .filter(|end| *end <= header.record_length as usize)
@@ -116,15 +470,9 @@ pub(crate) fn parse_usn_record_v2_name(
     let name_end = name_start
         .checked_add(file_name_len)
         .ok_or_else(|| UsnError::OtherError(format!("{context} file name length overflow")))?;
-    let name_bytes = buffer.get(name_start..name_end).ok_or_else(|| {
-        UsnError::OtherError(format!("{context} file name range is out of bounds"))
-    })?;
-    let name_units = name_bytes
-        .chunks_exact(size_of::<u16>())
-        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-        .collect::<Vec<_>>();
-
-    Ok(OsString::from_wide(&name_units))
+    buffer
+        .get(name_start..name_end)
+        .ok_or_else(|| UsnError::OtherError(format!("{context} file name range is out of bounds")))
 }
 
 #[cfg(test)]
@@ -132,10 +480,10 @@ mod tests {
     use super::*;
 
     #[test]
-    fn parse_usn_record_v2_header_rejects_truncated_valid_region() {
+    fn parse_usn_record_header_rejects_truncated_valid_region() {
         let buffer = vec![0u8; USN_RECORD_V2_HEADER_LEN];
 
-        let result = parse_usn_record_v2_header(
+        let result = parse_usn_record_header(
             &buffer,
             0,
             (USN_RECORD_V2_HEADER_LEN - 1) as u32,
@@ -148,14 +496,228 @@ mod tests {
     }
 
     #[test]
-    fn parse_usn_record_v2_header_rejects_offset_beyond_valid_bytes() {
+    fn parse_usn_record_header_rejects_offset_beyond_valid_bytes() {
         let buffer = vec![0u8; USN_RECORD_V2_HEADER_LEN * 2];
 
         let result =
-            parse_usn_record_v2_header(&buffer, USN_RECORD_V2_HEADER_LEN as u32, 0, "USN record");
+            parse_usn_record_header(&buffer, USN_RECORD_V2_HEADER_LEN as u32, 0, "USN record");
 
         assert!(
             matches!(result, Err(UsnError::OtherError(message)) if message == "USN record missing fixed header")
         );
     }
+
+    fn mock_record_bytes(usn: i64, file_name: &str) -> Vec<u8> {
+        let name_bytes = file_name
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let record_len = USN_RECORD_V2_HEADER_LEN + name_bytes.len();
+        let mut record = vec![0u8; record_len];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            record[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+        put(
+            offset_of!(USN_RECORD_V2, RecordLength),
+            &(record_len as u32).to_le_bytes(),
+        );
+        put(offset_of!(USN_RECORD_V2, MajorVersion), &2u16.to_le_bytes());
+        put(offset_of!(USN_RECORD_V2, Usn), &usn.to_le_bytes());
+        put(
+            offset_of!(USN_RECORD_V2, FileNameLength),
+            &(name_bytes.len() as u16).to_le_bytes(),
+        );
+        put(
+            offset_of!(USN_RECORD_V2, FileNameOffset),
+            &(USN_RECORD_V2_HEADER_LEN as u16).to_le_bytes(),
+        );
+        put(USN_RECORD_V2_HEADER_LEN, &name_bytes);
+        record
+    }
+
+    #[test]
+    fn aligned_buffer_is_eight_byte_aligned() {
+        for len in [0, 1, 7, 8, 9, 4096] {
+            let mut buffer = AlignedBuffer::new(len);
+            assert_eq!(buffer.len(), len);
+            assert!(buffer.iter().all(|&b| b == 0));
+            assert_eq!(buffer.as_mut_ptr() as usize % align_of::<u64>(), 0);
+        }
+    }
+
+    // Runs under miri: parsing must never rely on the alignment of the byte slice.
+    #[test]
+    fn parse_records_from_aligned_and_misaligned_buffers() {
+        let record = mock_record_bytes(0x1234, "a.txt");
+        let aligned = AlignedBuffer::from(record.as_slice());
+        let mut shifted = vec![0u8; record.len() + 1];
+        shifted[1..].copy_from_slice(&record);
+        let misaligned = &shifted[1..];
+
+        for buffer in [&aligned[..], misaligned] {
+            let (header, record_len) =
+                parse_usn_record_header(buffer, 0, buffer.len() as u32, "USN record").unwrap();
+            let name = parse_usn_record_name(buffer, 0, &header, "USN record").unwrap();
+
+            assert_eq!(record_len as usize, record.len());
+            assert_eq!(header.usn, 0x1234);
+            assert_eq!(name, "a.txt");
+        }
+    }
+
+    #[test]
+    fn check_record_version_applies_policy_to_unknown_versions() {
+        let mut buffer = vec![0u8; USN_RECORD_V2_HEADER_LEN];
+        buffer[..4].copy_from_slice(&(USN_RECORD_V2_HEADER_LEN as u32).to_le_bytes());
+        buffer[4..6].copy_from_slice(&9u16.to_le_bytes());
+        let (header, _) =
+            parse_usn_record_header(&buffer, 0, buffer.len() as u32, "USN record").unwrap();
+
+        assert!(matches!(
+            check_record_version(&header, UnknownVersionPolicy::Error),
+            Err(UsnError::UnsupportedRecordVersion(9))
+        ));
+        assert!(!check_record_version(&header, UnknownVersionPolicy::Skip).unwrap());
+    }
+
+    #[test]
+    fn check_record_version_skips_range_records_under_either_policy() {
+        let len = size_of::<USN_RECORD_V4>();
+        let mut buffer = vec![0u8; len];
+        buffer[..4].copy_from_slice(&(len as u32).to_le_bytes());
+        buffer[4..6].copy_from_slice(&4u16.to_le_bytes());
+        let (header, record_len) =
+            parse_usn_record_header(&buffer, 0, buffer.len() as u32, "USN record").unwrap();
+
+        assert_eq!(record_len as usize, len);
+        assert!(!check_record_version(&header, UnknownVersionPolicy::Error).unwrap());
+        assert!(!check_record_version(&header, UnknownVersionPolicy::Skip).unwrap());
+    }
+
+    #[test]
+    fn name_encoding_policy_handles_invalid_utf16() {
+        // "a", an unpaired high surrogate, then "b".
+        let invalid = OsString::from_wide(&[0x61, 0xD800, 0x62]);
+        let valid = OsString::from("ab");
+
+        assert_eq!(
+            NameEncodingPolicy::Preserve.apply(&invalid).as_deref(),
+            Some(invalid.as_os_str())
+        );
+        assert_eq!(
+            NameEncodingPolicy::ReplaceWith('?')
+                .apply(&invalid)
+                .as_deref(),
+            Some(OsStr::new("a?b"))
+        );
+        assert!(NameEncodingPolicy::Skip.apply(&invalid).is_none());
+
+        for policy in [
+            NameEncodingPolicy::Preserve,
+            NameEncodingPolicy::ReplaceWith('?'),
+            NameEncodingPolicy::Skip,
+        ] {
+            assert!(matches!(policy.apply(&valid), Some(Cow::Borrowed(name)) if name == "ab"));
+        }
+    }
+
+    #[test]
+    fn extension_filter_matches_suffix_ignoring_ascii_case() {
+        let utf16 = |name: &str| {
+            name.encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>()
+        };
+        let filter = ExtensionFilter::new(&["txt".to_string(), ".LOG".to_string()]);
+
+        for name in ["a.txt", "A.TXT", "server.log", "x.y.Log"] {
+            assert!(filter.matches(&utf16(name)), "{name}");
+        }
+        for name in ["a.txt.bak", "txt", ".txt", "alog", "a.tx"] {
+            assert!(!filter.matches(&utf16(name)), "{name}");
+        }
+        assert!(ExtensionFilter::default().matches(&utf16("anything")));
+    }
+
+    #[test]
+    fn tiny_buffer_grows_to_fit_long_file_name() {
+        let record = mock_record_bytes(0x1234, &"long-name-".repeat(20));
+        let mut buffer = AlignedBuffer::new(128);
+        let next_usn = 0x2000i64.to_le_bytes();
+        buffer[..8].copy_from_slice(&next_usn);
+        buffer[8..].copy_from_slice(&record[..120]);
+
+        let needed = oversized_first_record(&buffer, 128, size_of::<i64>()).unwrap();
+        assert_eq!(needed, size_of::<i64>() + record.len());
+
+        buffer.grow_to(needed).unwrap();
+        assert!(buffer.len() >= needed);
+        buffer[..8].copy_from_slice(&next_usn);
+        buffer[8..needed].copy_from_slice(&record);
+        assert_eq!(
+            oversized_first_record(&buffer, needed as u32, size_of::<i64>()),
+            None
+        );
+        let (header, _) = parse_usn_record_header(&buffer, 8, needed as u32, "USN record").unwrap();
+        assert_eq!(
+            parse_usn_record_name(&buffer, 8, &header, "USN record").unwrap(),
+            "long-name-".repeat(20).as_str()
+        );
+
+        // A full buffer too small to hold even the record length also asks for more room.
+        assert_eq!(oversized_first_record(&buffer[..10], 10, 8), Some(20));
+        assert_eq!(oversized_first_record(&buffer, 8, 8), None);
+    }
+
+    #[test]
+    fn oversized_first_record_ignores_corrupt_length_in_short_read() {
+        let mut record = mock_record_bytes(0x1234, "short.txt");
+        let bytes_read = (size_of::<i64>() + record.len()) as u32;
+        record[..4].copy_from_slice(&4096u32.to_le_bytes());
+        let mut buffer = AlignedBuffer::new(1024);
+        buffer[8..bytes_read as usize].copy_from_slice(&record);
+
+        assert_eq!(
+            oversized_first_record(&buffer, bytes_read, size_of::<i64>()),
+            None
+        );
+        assert!(parse_usn_record_header(&buffer, 8, bytes_read, "USN record").is_err());
+    }
+
+    #[test]
+    fn grow_to_rejects_sizes_past_the_maximum() {
+        let mut buffer = AlignedBuffer::new(64);
+        assert!(matches!(
+            buffer.grow_to(MAX_BUFFER_SIZE + 1),
+            Err(UsnError::OtherError(_))
+        ));
+        assert_eq!(buffer.len(), 64);
+    }
+
+    #[test]
+    fn grow_to_never_shrinks_a_large_buffer() {
+        let mut buffer = AlignedBuffer::new(2 * MAX_BUFFER_SIZE);
+        buffer.grow_to(1024).unwrap();
+        assert_eq!(buffer.len(), 2 * MAX_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn parse_usn_record_header_rejects_record_length_out_of_bounds() {
+        let record = mock_record_bytes(0x4242, "bounds.txt");
+        let len = record.len() as u32;
+        let parse = |buffer: &[u8], offset: u32, bytes_read: u32| {
+            parse_usn_record_header(buffer, offset, bytes_read, "USN record")
+        };
+
+        // RecordLength runs past the bytes read, or past the buffer itself.
+        assert!(parse(&record, 0, len - 2).is_err());
+        assert!(parse(&record[..record.len() - 2], 0, len).is_err());
+        // Offsets at or past the end of the buffer never wrap around.
+        assert!(parse(&record, len, len).is_err());
+        assert!(parse(&record, u32::MAX, u32::MAX).is_err());
+
+        let mut zero_length = record.clone();
+        zero_length[..4].copy_from_slice(&0u32.to_le_bytes());
+        assert!(parse(&zero_length, 0, len).is_err());
+    }
 }
@@ -1,28 +1,150 @@
 //! Volume handle management for NTFS/ReFS
 
-use crate::{errors::UsnError, journal::UsnJournal, mft::Mft, path::PathResolver, privilege};
+use crate::{
+    DEFAULT_JOURNAL_ALLOCATION_DELTA, DEFAULT_JOURNAL_MAX_SIZE, Usn,
+    errors::UsnError,
+    journal::{UsnJournal, UsnJournalData},
+    mft::{Mft, MftEntry},
+    path::PathResolver,
+    privilege,
+};
 use log::{debug, warn};
-use std::path::Path;
-use std::rc::Rc;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 use windows::{
     Win32::{
-        Foundation::{ERROR_ACCESS_DENIED, HANDLE},
+        Foundation::{
+            CloseHandle, ERROR_ACCESS_DENIED, ERROR_INVALID_FUNCTION, ERROR_MORE_DATA,
+            ERROR_NO_MORE_FILES, HANDLE, MAX_PATH,
+        },
         Storage::FileSystem::{
-            CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_GENERIC_READ, FILE_SHARE_READ,
-            FILE_SHARE_WRITE, GetVolumeNameForVolumeMountPointW, OPEN_EXISTING,
+            CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAGS_AND_ATTRIBUTES, FILE_GENERIC_READ,
+            FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+            FindFirstVolumeW, FindNextVolumeW, FindVolumeClose, GetDriveTypeW,
+            GetVolumeInformationByHandleW, GetVolumeNameForVolumeMountPointW, GetVolumePathNameW,
+            GetVolumePathNamesForVolumeNameW, OPEN_EXISTING,
         },
+        System::WindowsProgramming::{DRIVE_FIXED, DRIVE_REMOTE},
     },
     core::{HSTRING, Owned},
 };
 
+/// Owned volume handle that can be shared across threads.
+///
+/// The `windows` crate marks `HANDLE` as `!Send` because it is a raw pointer, but a kernel
+/// handle is valid process-wide and every call this crate makes on it is a synchronous
+/// `DeviceIoControl` or query. Each iterator owns its read buffer and position and is only
+/// driven through `&mut self`, so it is used by one thread at a time.
+#[derive(Debug, PartialEq)]
+pub(crate) struct VolumeHandle(Owned<HANDLE>);
+
+// SAFETY: see the type-level documentation; the handle is only used for synchronous calls
+// that the kernel serializes, and it is closed exactly once when the last owner drops it.
+unsafe impl Send for VolumeHandle {}
+// SAFETY: as above, shared references only ever pass the handle value to synchronous calls.
+unsafe impl Sync for VolumeHandle {}
+
+impl VolumeHandle {
+    /// Closes the handle now, reporting any error from `CloseHandle`.
+    ///
+    /// The handle is reset first, so dropping the value afterwards does not close it again.
+    fn close(mut self) -> windows::core::Result<()> {
+        let handle = std::mem::take(&mut *self.0);
+        if handle.is_invalid() {
+            return Ok(());
+        }
+        unsafe { CloseHandle(handle) }
+    }
+}
+
+impl Deref for VolumeHandle {
+    type Target = HANDLE;
+
+    fn deref(&self) -> &HANDLE {
+        &self.0
+    }
+}
+
+/// Journal status of one volume, as reported by [`Volume::survey`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeJournalStatus {
+    /// Device path of the volume, e.g. `\\?\Volume{...}\`.
+    pub volume: String,
+    /// Drive letters and folders the volume is mounted at, e.g. `C:\`.
+    pub mount_points: Vec<String>,
+    /// File system name, e.g. `NTFS` or `ReFS`.
+    pub fs_type: String,
+    pub journal_active: bool,
+    /// Maximum journal size in bytes; zero when the journal is not active.
+    pub max_size: u64,
+    /// Next USN the journal will assign; zero when the journal is not active.
+    pub next_usn: Usn,
+}
+
+/// A volume that [`Volume::survey`] could not open or query.
+#[derive(Debug, thiserror::Error)]
+#[error("Volume {volume} could not be surveyed: {error}")]
+pub struct VolumeSurveyError {
+    /// Device path of the volume, e.g. `\\?\Volume{...}\`.
+    pub volume: String,
+    #[source]
+    pub error: UsnError,
+}
+
+/// Values that identify a volume across runs, as returned by [`Volume::identity`].
+///
+/// Drive letters can be reassigned between runs; comparing a stored identity with the
+/// current one tells whether a letter still refers to the same volume.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeIdentity {
+    /// Volume serial number, as shown in hex by `vol`.
+    pub serial: u32,
+    /// Volume GUID path, e.g. `\\?\Volume{...}\`.
+    pub guid: String,
+    /// File system name, e.g. `NTFS` or `ReFS`.
+    pub fs_type: String,
+}
+
+/// File system of a volume, as returned by [`Volume::filesystem_type`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilesystemType {
+    Ntfs,
+    Refs,
+    /// Any other file system, with the name Windows reports for it, e.g. `FAT32` or `exFAT`.
+    Other(String),
+}
+
+impl FilesystemType {
+    /// Classifies a file system name reported by `GetVolumeInformationByHandleW`.
+    fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("NTFS") {
+            FilesystemType::Ntfs
+        } else if name.eq_ignore_ascii_case("ReFS") {
+            FilesystemType::Refs
+        } else {
+            FilesystemType::Other(name.to_string())
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Represents an NTFS/ReFS volume handle and its associated drive letter or mount point.
 ///
-/// Cloning a `Volume` shares ownership of the same underlying OS handle.
+/// Cloning a `Volume` shares ownership of the same underlying OS handle. `Volume`, and the
+/// journal and MFT iterators created from it, are `Send`, so an iterator can be moved to a
+/// worker thread that owns it.
 pub struct Volume {
-    handle: Rc<Owned<HANDLE>>,
+    handle: Arc<VolumeHandle>,
     pub drive_letter: Option<char>,
     pub mount_point: Option<String>,
+    /// Drive letters and folders the volume is mounted at, e.g. `C:\`.
+    ///
+    /// Only filled in by [`Volume::enumerate`]; empty for volumes opened by path.
+    pub mount_points: Vec<String>,
+    guid_path: OnceLock<String>,
+    filesystem_type: OnceLock<FilesystemType>,
 }
 
 impl Volume {
@@ -32,6 +154,19 @@ impl Volume {
         Ok(Self::from_handle(handle, Some(drive_letter), None))
     }
 
+    /// Opens the volume with the given drive letter for unprivileged journal reads.
+    ///
+    /// No elevation check is made: the handle refers to the volume's root directory rather
+    /// than the volume device, which standard users can open. It supports querying the
+    /// journal and reading it with [`EnumOptions::unprivileged`] set, which requires
+    /// Windows 8 or later; the MFT cannot be enumerated through it.
+    ///
+    /// [`EnumOptions::unprivileged`]: crate::journal::EnumOptions::unprivileged
+    pub fn from_drive_letter_unprivileged(drive_letter: char) -> Result<Self, UsnError> {
+        let handle = get_root_directory_handle(drive_letter)?;
+        Ok(Self::from_handle(handle, Some(drive_letter), None))
+    }
+
     /// Creates a new `Volume` instance with the given mount point.
     pub fn from_mount_point(mount_point: &Path) -> Result<Self, UsnError> {
         let handle = get_volume_handle_from_mount_point(mount_point)?;
@@ -42,6 +177,290 @@ impl Volume {
         ))
     }
 
+    /// Opens the volume that contains `path`, which may be any file or directory on it.
+    ///
+    /// The volume root is found with `GetVolumePathNameW`, so paths under folders that have
+    /// another volume mounted on them open that volume, whether or not it has a drive letter.
+    ///
+    /// # Errors
+    /// Returns `UsnError::InvalidMountPointError` for network paths, including mapped drives.
+    pub fn from_path(path: &Path) -> Result<Self, UsnError> {
+        let path_str = path.to_string_lossy();
+        if is_network_path(&path_str) {
+            return Err(UsnError::InvalidMountPointError(path_str.into_owned()));
+        }
+
+        let mut root = [0u16; MAX_PATH as usize + 1];
+        unsafe { GetVolumePathNameW(&HSTRING::from(path.as_os_str()), &mut root)? };
+        let root = string_from_wide_nul(&root);
+        debug!("Volume root of {path_str}: {root}");
+
+        if unsafe { GetDriveTypeW(&HSTRING::from(&root)) } == DRIVE_REMOTE {
+            return Err(UsnError::InvalidMountPointError(path_str.into_owned()));
+        }
+
+        match drive_letter_of(&root) {
+            Some(drive_letter) => Self::from_drive_letter(drive_letter),
+            None => Self::from_mount_point(Path::new(root.trim_end_matches('\\'))),
+        }
+    }
+
+    /// Creates a new `Volume` instance from a device path.
+    ///
+    /// Accepts paths in the `\\?\` or `\\.\` device namespaces, such as a volume GUID path or a
+    /// volume shadow copy exposed as `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy1`.
+    /// The volume is opened read-only, which is all journal and MFT reads need.
+    ///
+    /// A shadow copy is a frozen snapshot: its journal can be queried and read up to the moment
+    /// the snapshot was taken, but it never receives new records, so `wait_for_more` never
+    /// yields anything new.
+    pub fn from_device_path(device_path: &str) -> Result<Self, UsnError> {
+        let device_path = normalize_device_path(device_path)?;
+        let handle = get_volume_handle_from_device_path(&device_path)?;
+        Ok(Self::from_handle(
+            handle,
+            None,
+            Some(format!("{device_path}\\")),
+        ))
+    }
+
+    /// Opens the volume and makes sure its USN journal is ready to read.
+    ///
+    /// This packages the usual setup into one call: it fails with
+    /// `UsnError::PermissionError` when the process is not elevated, and creates the journal
+    /// with default settings if it is not active yet. Returns the volume together with the
+    /// current journal state.
+    pub fn open_for_journal(drive_letter: char) -> Result<(Self, UsnJournalData), UsnError> {
+        let volume = Self::from_drive_letter(drive_letter)?;
+        let journal_data = volume
+            .journal()
+            .ensure_active(DEFAULT_JOURNAL_MAX_SIZE, DEFAULT_JOURNAL_ALLOCATION_DELTA)?;
+        Ok((volume, journal_data))
+    }
+
+    /// Opens the volume and lists every file and directory on it with its full path.
+    ///
+    /// This is the MFT counterpart of [`Volume::open_for_journal`]: the whole MFT is read up
+    /// front and paths are built from an in-memory cache of directory paths, as described for
+    /// [`Mft::iter_with_paths_cached`], whose memory notes apply. Entries whose parent chain
+    /// does not reach the volume root, e.g. because the parent was deleted during the scan,
+    /// are yielded last as `UsnError::OtherError`.
+    ///
+    /// # Errors
+    /// Returns an error if the volume cannot be opened or the MFT cannot be enumerated.
+    pub fn enumerate_files(
+        drive_letter: char,
+    ) -> Result<impl Iterator<Item = Result<(MftEntry, PathBuf), UsnError>>, UsnError> {
+        let volume = Self::from_drive_letter(drive_letter)?;
+        let files = volume.mft().iter_with_paths_cached()?;
+        Ok(files.map(|(entry, path)| match path {
+            Some(path) => Ok((entry, path)),
+            None => Err(UsnError::OtherError(format!(
+                "path of {} does not reach the volume root",
+                entry.fid
+            ))),
+        }))
+    }
+
+    /// Lists the device paths of all volumes on the system, e.g. `\\?\Volume{...}\`.
+    ///
+    /// Each path can be passed to [`Volume::from_device_path`].
+    pub fn list_all() -> Result<Vec<String>, UsnError> {
+        let mut volume_name = [0u16; MAX_PATH as usize + 1];
+        let find_handle = FindVolumeHandle(unsafe { FindFirstVolumeW(&mut volume_name)? });
+
+        let mut volumes = vec![string_from_wide_nul(&volume_name)];
+        loop {
+            match unsafe { FindNextVolumeW(find_handle.0, &mut volume_name) } {
+                Ok(()) => volumes.push(string_from_wide_nul(&volume_name)),
+                Err(err) if err.code() == ERROR_NO_MORE_FILES.into() => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(volumes)
+    }
+
+    /// Opens every fixed NTFS or ReFS volume on the system.
+    ///
+    /// Each volume's `mount_points` lists where it is mounted; the first of them becomes its
+    /// `mount_point`, and its `drive_letter` when that is a drive root. Removable drives,
+    /// other file systems, and volumes that cannot be opened are logged and left out.
+    ///
+    /// # Errors
+    /// Returns `UsnError::PermissionError` when the process is not elevated.
+    pub fn enumerate() -> Result<Vec<Self>, UsnError> {
+        if !privilege::is_elevated()? {
+            return Err(privilege::permission_error());
+        }
+
+        let mut volumes = Vec::new();
+        for device_path in Self::list_all()? {
+            match open_enumerated_volume(&device_path) {
+                Ok(Some(volume)) => volumes.push(volume),
+                Ok(None) => {}
+                Err(err) => warn!("Skipping volume {device_path} in enumeration: {err}"),
+            }
+        }
+        Ok(volumes)
+    }
+
+    /// Reports the journal status of every volume on the system.
+    ///
+    /// Each volume gets one result. Volumes that cannot be opened or queried, such as empty
+    /// card readers, are reported as a [`VolumeSurveyError`] rather than failing the whole
+    /// survey. Volumes whose file system has no journal, such as FAT, are reported as not
+    /// active.
+    ///
+    /// # Errors
+    /// Returns `UsnError::PermissionError` when the process is not elevated.
+    pub fn survey() -> Result<Vec<Result<VolumeJournalStatus, VolumeSurveyError>>, UsnError> {
+        if !privilege::is_elevated()? {
+            return Err(privilege::permission_error());
+        }
+
+        Ok(Self::list_all()?
+            .into_iter()
+            .map(|device_path| {
+                survey_volume(&device_path).map_err(|error| VolumeSurveyError {
+                    volume: device_path,
+                    error,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the name of the volume's file system, e.g. `NTFS` or `ReFS`.
+    pub fn file_system_name(&self) -> Result<String, UsnError> {
+        let mut name = [0u16; MAX_PATH as usize + 1];
+        unsafe {
+            GetVolumeInformationByHandleW(self.handle(), None, None, None, None, Some(&mut name))?
+        };
+        Ok(string_from_wide_nul(&name))
+    }
+
+    /// Returns the volume's file system, read with [`Volume::file_system_name`] on first use
+    /// and cached.
+    ///
+    /// Only NTFS and ReFS volumes have a USN journal; ReFS uses 128-bit file IDs throughout.
+    pub fn filesystem_type(&self) -> Result<FilesystemType, UsnError> {
+        if let Some(filesystem_type) = self.filesystem_type.get() {
+            return Ok(filesystem_type.clone());
+        }
+
+        let filesystem_type = FilesystemType::from_name(&self.file_system_name()?);
+        Ok(self.filesystem_type.get_or_init(|| filesystem_type).clone())
+    }
+
+    /// Returns the volume GUID path, e.g. `\\?\Volume{...}\`.
+    ///
+    /// Unlike a drive letter, the GUID path stays the same across reboots and drive letter
+    /// changes. It is looked up from the drive letter or mount point on first use and cached.
+    ///
+    /// # Errors
+    /// Returns `UsnError::InvalidMountPointError` if the volume was opened without a drive
+    /// letter or mount point, or the lookup fails for it, e.g. for a shadow copy.
+    pub fn guid_path(&self) -> Result<String, UsnError> {
+        if let Some(guid_path) = self.guid_path.get() {
+            return Ok(guid_path.clone());
+        }
+
+        let mount_path = match (self.drive_letter, &self.mount_point) {
+            (Some(drive_letter), _) => format!("{drive_letter}:\\"),
+            (None, Some(mount_point)) => format!("{}\\", mount_point.trim_end_matches('\\')),
+            (None, None) => return Err(UsnError::InvalidMountPointError(String::new())),
+        };
+        let guid_path = volume_guid_path(&mount_path)
+            .map_err(|_| UsnError::InvalidMountPointError(mount_path))?;
+        Ok(self.guid_path.get_or_init(|| guid_path).clone())
+    }
+
+    /// Returns a drive letter the volume is mounted at, even if it was opened through a
+    /// folder mount.
+    ///
+    /// Uses `drive_letter` or `mount_points` when they name one, and otherwise lists the
+    /// volume's mount points through its GUID path. Returns `None` for volumes that are only
+    /// mounted in folders.
+    ///
+    /// # Errors
+    /// Returns an error if the GUID path or the mount points cannot be looked up.
+    pub fn find_drive_letter(&self) -> Result<Option<char>, UsnError> {
+        if let Some(drive_letter) = self.drive_letter {
+            return Ok(Some(drive_letter));
+        }
+        let first_letter =
+            |mount_points: &[String]| mount_points.iter().find_map(|path| drive_letter_of(path));
+        if let Some(drive_letter) = first_letter(&self.mount_points) {
+            return Ok(Some(drive_letter));
+        }
+        Ok(first_letter(&volume_mount_points(&self.guid_path()?)?))
+    }
+
+    /// Returns the values that identify this volume across runs.
+    ///
+    /// Cursors and indexes persisted for a drive letter are only valid while the letter
+    /// refers to the same volume. Store the identity alongside them and compare it on
+    /// startup; if it differs, the letter now points at another (or a reformatted) volume
+    /// and the persisted state must be rebuilt.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use usn_journal_rs::volume::{Volume, VolumeIdentity};
+    ///
+    /// # fn load_identity() -> Option<VolumeIdentity> { None }
+    /// let volume = Volume::from_drive_letter('D')?;
+    /// let identity = volume.identity()?;
+    /// if load_identity().is_some_and(|stored| stored != identity) {
+    ///     // Discard stored cursors and rescan the volume.
+    /// }
+    /// # Ok::<(), usn_journal_rs::UsnError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the serial number, GUID path or file system cannot be read; see
+    /// [`Volume::guid_path`].
+    pub fn identity(&self) -> Result<VolumeIdentity, UsnError> {
+        Ok(VolumeIdentity {
+            serial: self.serial_number()?,
+            guid: self.guid_path()?,
+            fs_type: self.file_system_name()?,
+        })
+    }
+
+    /// Returns the volume label, or an empty string when no label is set.
+    pub fn label(&self) -> Result<String, UsnError> {
+        // MAX_PATH + 1 is the documented maximum size of the volume name buffer.
+        let mut label = [0u16; MAX_PATH as usize + 1];
+        unsafe {
+            GetVolumeInformationByHandleW(self.handle(), Some(&mut label), None, None, None, None)?
+        };
+        Ok(string_from_wide_nul(&label))
+    }
+
+    /// Returns the volume serial number, as shown in hex by `vol` (for example `1A2B-3C4D`).
+    pub fn serial_number(&self) -> Result<u32, UsnError> {
+        serial_number_of(self.handle())
+    }
+
+    /// Closes the volume handle now instead of when the last owner is dropped.
+    ///
+    /// Useful when the handle must be gone at a precise moment, e.g. before the volume is
+    /// dismounted. Clones of the volume and the iterators created from it share the handle,
+    /// so it can only be closed once they have all been dropped.
+    ///
+    /// # Errors
+    /// Returns `UsnError::OtherError` if the handle is still shared, in which case it stays
+    /// open until the other owners are dropped, or `UsnError::WinApiError` if `CloseHandle`
+    /// fails.
+    pub fn close(self) -> Result<(), UsnError> {
+        match Arc::try_unwrap(self.handle) {
+            Ok(handle) => Ok(handle.close()?),
+            Err(shared) => Err(UsnError::OtherError(format!(
+                "volume handle is still shared by {} other owners",
+                Arc::strong_count(&shared) - 1
+            ))),
+        }
+    }
+
     /// Creates a USN journal view for this volume.
     pub fn journal(&self) -> UsnJournal<'_> {
         UsnJournal::new(self)
@@ -71,6 +490,25 @@ impl Volume {
             handle: share_handle(handle),
             drive_letter,
             mount_point,
+            mount_points: Vec::new(),
+            guid_path: OnceLock::new(),
+            filesystem_type: OnceLock::new(),
+        }
+    }
+
+    /// Opens another handle to the same volume, for reads that must not be serialized with
+    /// reads on this one.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn reopen(&self) -> Result<Self, UsnError> {
+        match (self.drive_letter, &self.mount_point) {
+            (Some(drive_letter), _) => Self::from_drive_letter(drive_letter),
+            (None, Some(mount_point))
+                if mount_point.starts_with(r"\\?\") || mount_point.starts_with(r"\\.\") =>
+            {
+                Self::from_device_path(mount_point.trim_end_matches('\\'))
+            }
+            (None, Some(mount_point)) => Self::from_mount_point(Path::new(mount_point)),
+            (None, None) => Err(UsnError::InvalidMountPointError(String::new())),
         }
     }
 
@@ -78,15 +516,15 @@ impl Volume {
         **self.handle
     }
 
-    pub(crate) fn shared_handle(&self) -> Rc<Owned<HANDLE>> {
-        Rc::clone(&self.handle)
+    pub(crate) fn shared_handle(&self) -> Arc<VolumeHandle> {
+        Arc::clone(&self.handle)
     }
 }
 
 /// Opens a handle to an NTFS/ReFS volume using a drive letter.
 fn get_volume_handle_from_drive_letter(drive_letter: char) -> Result<HANDLE, UsnError> {
     if !privilege::is_elevated()? {
-        return Err(UsnError::PermissionError);
+        return Err(privilege::permission_error());
     }
 
     // https://learn.microsoft.com/en-us/windows/win32/fileio/obtaining-a-volume-handle-for-change-journal-operations
@@ -107,7 +545,28 @@ fn get_volume_handle_from_drive_letter(drive_letter: char) -> Result<HANDLE, Usn
         )
     } {
         Ok(handle) => Ok(handle),
-        Err(err) if err == ERROR_ACCESS_DENIED.into() => Err(UsnError::PermissionError),
+        Err(err) if err == ERROR_ACCESS_DENIED.into() => Err(privilege::permission_error()),
+        Err(err) => Err(UsnError::WinApiError(err)),
+    }
+}
+
+/// Opens a handle to the root directory of a volume, which needs no elevation.
+fn get_root_directory_handle(drive_letter: char) -> Result<HANDLE, UsnError> {
+    let root_dir = format!(r"{drive_letter}:\");
+
+    match unsafe {
+        CreateFileW(
+            &HSTRING::from(&root_dir),
+            FILE_READ_ATTRIBUTES.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            None,
+        )
+    } {
+        Ok(handle) => Ok(handle),
+        Err(err) if err == ERROR_ACCESS_DENIED.into() => Err(privilege::permission_error()),
         Err(err) => Err(UsnError::WinApiError(err)),
     }
 }
@@ -115,66 +574,227 @@ fn get_volume_handle_from_drive_letter(drive_letter: char) -> Result<HANDLE, Usn
 /// Opens a handle to an NTFS/ReFS volume using a mount point path.
 fn get_volume_handle_from_mount_point(mount_point: &Path) -> Result<HANDLE, UsnError> {
     if !privilege::is_elevated()? {
-        return Err(UsnError::PermissionError);
+        return Err(privilege::permission_error());
     }
 
     // GetVolumeNameForVolumeMountPointW requires trailing backslash
     let mount_path = format!("{}\\", mount_point.to_string_lossy());
+    let volume_guid = volume_guid_path(&mount_path)?;
+
+    // IMPORTANT: Remove the trailing backslash for CreateFileW
+    let volume_path = volume_guid.trim_end_matches('\\').to_string();
+    debug!("Using volume path: {volume_path}");
 
+    let volume_handle = unsafe {
+        CreateFileW(
+            &HSTRING::from(&volume_path),
+            FILE_GENERIC_READ.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES::default(),
+            None,
+        )?
+    };
+
+    Ok(volume_handle)
+}
+
+/// Looks up the volume GUID path of a mount point, which must end with a backslash.
+fn volume_guid_path(mount_path: &str) -> Result<String, UsnError> {
     let mut volume_name = [0u16; 50]; // Enough space for volume GUID path
     if let Err(err) =
-        unsafe { GetVolumeNameForVolumeMountPointW(&HSTRING::from(&mount_path), &mut volume_name) }
+        unsafe { GetVolumeNameForVolumeMountPointW(&HSTRING::from(mount_path), &mut volume_name) }
     {
         warn!("GetVolumeNameForVolumeMountPointW failed, mount_point={mount_path}, error={err:?}");
         return Err(err.into());
     }
 
-    // Convert the null-terminated wide string to a Rust string
-    let end = volume_name
-        .iter()
-        .position(|&c| c == 0)
-        .unwrap_or(volume_name.len());
-    let name_data = volume_name.get(..end).ok_or(UsnError::OtherError(
-        "Failed to get volume name data".to_string(),
-    ))?;
-    let volume_guid = String::from_utf16_lossy(name_data);
-
+    let volume_guid = string_from_wide_nul(&volume_name);
     debug!("Volume GUID: {volume_guid}");
+    Ok(volume_guid)
+}
 
-    // IMPORTANT: Remove the trailing backslash for CreateFileW
-    let volume_path = volume_guid.trim_end_matches('\\').to_string();
-    debug!("Using volume path: {volume_path}");
+/// Search handle from `FindFirstVolumeW`, which must be closed with `FindVolumeClose`.
+struct FindVolumeHandle(HANDLE);
 
-    let volume_handle = unsafe {
+impl Drop for FindVolumeHandle {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { FindVolumeClose(self.0) } {
+            warn!("FindVolumeClose failed: {err}");
+        }
+    }
+}
+
+/// Opens the volume at `device_path` and reads its journal status.
+fn survey_volume(device_path: &str) -> Result<VolumeJournalStatus, UsnError> {
+    let volume = Volume::from_device_path(device_path)?;
+    let fs_type = volume.file_system_name()?;
+    let (journal_active, max_size, next_usn) = match volume.journal().query() {
+        Ok(journal_data) => (true, journal_data.maximum_size, journal_data.next_usn),
+        Err(UsnError::JournalNotActive) => (false, 0, 0),
+        Err(UsnError::WinApiError(err)) if err.code() == ERROR_INVALID_FUNCTION.into() => {
+            (false, 0, 0)
+        }
+        Err(err) => return Err(err),
+    };
+
+    Ok(VolumeJournalStatus {
+        volume: device_path.to_string(),
+        mount_points: volume_mount_points(device_path)?,
+        fs_type,
+        journal_active,
+        max_size,
+        next_usn,
+    })
+}
+
+/// Opens the volume at `device_path` for [`Volume::enumerate`].
+///
+/// Returns `None` for volumes that are not fixed disks or have no USN journal support.
+fn open_enumerated_volume(device_path: &str) -> Result<Option<Volume>, UsnError> {
+    let drive_type = unsafe { GetDriveTypeW(&HSTRING::from(device_path)) };
+    if drive_type != DRIVE_FIXED {
+        debug!("Skipping volume {device_path}: drive type {drive_type} is not fixed");
+        return Ok(None);
+    }
+
+    let mut volume = Volume::from_device_path(device_path)?;
+    match volume.filesystem_type()? {
+        FilesystemType::Ntfs | FilesystemType::Refs => {}
+        FilesystemType::Other(name) => {
+            debug!("Skipping volume {device_path}: unsupported file system {name}");
+            return Ok(None);
+        }
+    }
+
+    volume.mount_points = volume_mount_points(device_path)?;
+    // Volumes listed by FindFirstVolumeW are named by their GUID path already.
+    let _ = volume.guid_path.set(device_path.to_string());
+    if let Some(first) = volume.mount_points.first() {
+        volume.drive_letter = drive_letter_of(first);
+        volume.mount_point = Some(first.clone());
+    }
+    Ok(Some(volume))
+}
+
+/// Returns the drive letter of a drive root such as `C:\`, or `None` for a folder mount.
+fn drive_letter_of(mount_point: &str) -> Option<char> {
+    let mut chars = mount_point.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), Some('\\') | None, None) if letter.is_ascii_alphabetic() => {
+            Some(letter)
+        }
+        _ => None,
+    }
+}
+
+/// Returns true for UNC paths, such as `\\server\share` or `\\?\UNC\server\share`.
+fn is_network_path(path: &str) -> bool {
+    let is_unc = path.starts_with(r"\\") || path.starts_with("//");
+    let is_device = path.starts_with(r"\\?\") || path.starts_with(r"\\.\");
+    let is_device_unc = path
+        .get(..8)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(r"\\?\UNC\"));
+    (is_unc && !is_device) || is_device_unc
+}
+
+/// Returns the drive letters and folders a volume is mounted at.
+fn volume_mount_points(device_path: &str) -> Result<Vec<String>, UsnError> {
+    let volume_name = HSTRING::from(device_path);
+    let mut buffer = vec![0u16; MAX_PATH as usize + 1];
+    loop {
+        let mut needed = 0u32;
+        match unsafe {
+            GetVolumePathNamesForVolumeNameW(&volume_name, Some(&mut buffer), &mut needed)
+        } {
+            Ok(()) => break,
+            Err(err) if err.code() == ERROR_MORE_DATA.into() => {
+                buffer.resize(needed as usize, 0);
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    // The result is a list of NUL-terminated strings, ended by an empty string.
+    Ok(buffer
+        .split(|&c| c == 0)
+        .take_while(|path| !path.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect())
+}
+
+/// Converts a null-terminated wide string buffer to a Rust string.
+fn string_from_wide_nul(buffer: &[u16]) -> String {
+    let end = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..end])
+}
+
+/// Validates a volume device path and strips trailing backslashes, which `CreateFileW`
+/// would otherwise treat as a request for the volume's root directory.
+fn normalize_device_path(device_path: &str) -> Result<String, UsnError> {
+    let trimmed = device_path.trim_end_matches('\\');
+    if !trimmed.starts_with(r"\\?\") && !trimmed.starts_with(r"\\.\") {
+        return Err(UsnError::InvalidMountPointError(device_path.to_string()));
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Opens a handle to an NTFS/ReFS volume using a device path.
+fn get_volume_handle_from_device_path(device_path: &str) -> Result<HANDLE, UsnError> {
+    if !privilege::is_elevated()? {
+        return Err(privilege::permission_error());
+    }
+
+    debug!("Using device path: {device_path}");
+
+    match unsafe {
         CreateFileW(
-            &HSTRING::from(&volume_path),
+            &HSTRING::from(device_path),
             FILE_GENERIC_READ.0,
             FILE_SHARE_READ | FILE_SHARE_WRITE,
             None,
             OPEN_EXISTING,
             FILE_FLAGS_AND_ATTRIBUTES::default(),
             None,
-        )?
-    };
+        )
+    } {
+        Ok(handle) => Ok(handle),
+        Err(err) if err == ERROR_ACCESS_DENIED.into() => Err(privilege::permission_error()),
+        Err(err) => Err(UsnError::WinApiError(err)),
+    }
+}
 
-    Ok(volume_handle)
+fn share_handle(handle: HANDLE) -> Arc<VolumeHandle> {
+    Arc::new(VolumeHandle(unsafe { Owned::new(handle) }))
 }
 
-fn share_handle(handle: HANDLE) -> Rc<Owned<HANDLE>> {
-    Rc::new(unsafe { Owned::new(handle) })
+/// Returns the serial number of the volume that the open file or volume `handle` is on.
+pub(crate) fn serial_number_of(handle: HANDLE) -> Result<u32, UsnError> {
+    let mut serial_number = 0u32;
+    unsafe {
+        GetVolumeInformationByHandleW(handle, None, Some(&mut serial_number), None, None, None)?
+    };
+    Ok(serial_number)
 }
 
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::sync::Arc;
     use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, HANDLE};
 
-    use crate::{errors::UsnError, volume::Volume};
+    use crate::{
+        errors::UsnError,
+        volume::{
+            FilesystemType, Volume, VolumeSurveyError, drive_letter_of, is_network_path,
+            normalize_device_path, string_from_wide_nul,
+        },
+    };
 
     // Integration tests that require actual filesystem access
     mod integration_tests {
         use super::*;
-        use std::rc::Rc;
+        use std::sync::Arc;
 
         #[test]
         fn test_get_volume_handle_from_valid_drive_letter() -> Result<(), UsnError> {
@@ -193,7 +813,220 @@ mod tests {
                     assert!(volume.mount_point.is_none(), "Mount point should be None");
                     Ok(())
                 }
-                Err(UsnError::PermissionError) => {
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_open_for_journal_returns_journal_data() -> Result<(), UsnError> {
+            match Volume::open_for_journal('C') {
+                Ok((volume, journal_data)) => {
+                    assert!(!volume.handle().is_invalid());
+                    assert_eq!(volume.drive_letter, Some('C'));
+                    assert_ne!(journal_data.journal_id, 0, "Journal ID should be populated");
+                    assert!(journal_data.maximum_size > 0);
+                    assert!(journal_data.next_usn >= journal_data.first_usn);
+                    Ok(())
+                }
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_enumerate_files_yields_absolute_paths_under_root() -> Result<(), UsnError> {
+            let files = match Volume::enumerate_files('C') {
+                Ok(files) => files,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let mut resolved = 0;
+            for (entry, path) in files.flatten() {
+                assert!(path.is_absolute(), "{} is not absolute", path.display());
+                assert!(path.starts_with("C:\\"), "{} is outside C:", path.display());
+                if entry.fid != entry.parent_fid {
+                    assert_eq!(path.file_name(), Some(entry.file_name.as_os_str()));
+                }
+                resolved += 1;
+            }
+            assert!(resolved > 0);
+            Ok(())
+        }
+
+        #[test]
+        fn test_survey_includes_system_volume() -> Result<(), UsnError> {
+            let statuses = match Volume::survey() {
+                Ok(statuses) => statuses,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let system = statuses
+                .iter()
+                .flatten()
+                .find(|status| status.mount_points.iter().any(|path| path == r"C:\"))
+                .expect("system volume should be surveyed");
+            assert!(system.volume.starts_with(r"\\?\Volume{"));
+            assert!(!system.fs_type.is_empty());
+            if system.journal_active {
+                assert!(system.max_size > 0);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_enumerate_includes_system_drive() -> Result<(), UsnError> {
+            let volumes = match Volume::enumerate() {
+                Ok(volumes) => volumes,
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+
+            let system = volumes
+                .iter()
+                .find(|volume| volume.drive_letter == Some('C'))
+                .expect("system drive should be enumerated");
+            assert!(system.mount_points.iter().any(|path| path == r"C:\"));
+            assert!(matches!(
+                system.file_system_name()?.as_str(),
+                "NTFS" | "ReFS"
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn test_from_path_opens_containing_volume() -> Result<(), UsnError> {
+            for path in [r"C:\Windows\System32\kernel32.dll", r"C:\Windows", r"C:\"] {
+                match Volume::from_path(path.as_ref()) {
+                    Ok(volume) => {
+                        assert_eq!(volume.drive_letter, Some('C'), "{path}");
+                        assert!(!volume.handle().is_invalid());
+                    }
+                    Err(UsnError::PermissionError(_)) => {
+                        eprintln!("Skipping test - requires admin privileges");
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_from_path_rejects_network_paths() {
+            let path = r"\\server\share\file.txt";
+            assert!(matches!(
+                Volume::from_path(path.as_ref()),
+                Err(UsnError::InvalidMountPointError(p)) if p == path
+            ));
+        }
+
+        #[test]
+        fn test_label_and_serial_number() -> Result<(), UsnError> {
+            match Volume::from_drive_letter('C') {
+                Ok(volume) => {
+                    let label = volume.label()?;
+                    assert!(!label.contains('\0'), "Label should be trimmed at NUL");
+                    assert_ne!(volume.serial_number()?, 0, "Serial should be populated");
+                    Ok(())
+                }
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_guid_path_of_system_volume() -> Result<(), UsnError> {
+            match Volume::from_drive_letter('C') {
+                Ok(volume) => {
+                    let guid_path = volume.guid_path()?;
+                    assert!(guid_path.starts_with(r"\\?\Volume{"), "{guid_path}");
+                    assert!(guid_path.ends_with('\\'));
+                    assert_eq!(volume.guid_path()?, guid_path, "GUID path should be cached");
+                    assert_ne!(volume.serial_number()?, 0);
+                    Ok(())
+                }
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_filesystem_type_of_system_volume() -> Result<(), UsnError> {
+            match Volume::from_drive_letter('C') {
+                Ok(volume) => {
+                    let filesystem_type = volume.filesystem_type()?;
+                    assert!(
+                        matches!(filesystem_type, FilesystemType::Ntfs | FilesystemType::Refs),
+                        "{filesystem_type:?}"
+                    );
+                    assert_eq!(volume.filesystem_type()?, filesystem_type);
+                    Ok(())
+                }
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_identity_is_stable_across_reopens() -> Result<(), UsnError> {
+            let open = || Volume::from_drive_letter('C');
+            match open() {
+                Ok(volume) => {
+                    let identity = volume.identity()?;
+                    assert_ne!(identity.serial, 0);
+                    assert!(!identity.fs_type.is_empty());
+                    drop(volume);
+
+                    assert_eq!(open()?.identity()?, identity);
+                    Ok(())
+                }
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+
+        #[test]
+        fn test_close_releases_handle() -> Result<(), UsnError> {
+            match Volume::from_drive_letter('C') {
+                Ok(volume) => {
+                    volume.close()?;
+
+                    // The volume can still be opened and used afterwards.
+                    let reopened = Volume::from_drive_letter('C')?;
+                    assert_ne!(reopened.serial_number()?, 0);
+                    reopened.close()
+                }
+                Err(UsnError::PermissionError(_)) => {
                     eprintln!("Skipping test - requires admin privileges");
                     Ok(())
                 }
@@ -220,12 +1053,12 @@ mod tests {
                         cloned.handle(),
                         "Clone should share the same handle"
                     );
-                    assert!(Rc::ptr_eq(&volume.handle, &cloned.handle));
+                    assert!(Arc::ptr_eq(&volume.handle, &cloned.handle));
                     assert_eq!(volume.drive_letter, cloned.drive_letter);
                     assert_eq!(volume.mount_point, cloned.mount_point);
                     Ok(())
                 }
-                Err(UsnError::PermissionError) => {
+                Err(UsnError::PermissionError(_)) => {
                     eprintln!("Skipping test - requires admin privileges");
                     Ok(())
                 }
@@ -233,6 +1066,42 @@ mod tests {
             }
         }
 
+        #[test]
+        fn test_dropping_volumes_closes_handles() -> Result<(), UsnError> {
+            use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessHandleCount};
+
+            let handle_count = || -> Result<u32, UsnError> {
+                let mut count = 0;
+                unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count)? };
+                Ok(count)
+            };
+
+            // Open once up front so lazily created process handles don't skew the count.
+            match Volume::from_drive_letter('C') {
+                Ok(_) => {}
+                Err(UsnError::PermissionError(_)) => {
+                    eprintln!("Skipping test - requires admin privileges");
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+
+            let before = handle_count()?;
+            for _ in 0..200 {
+                let volume = Volume::from_drive_letter('C')?;
+                let clone = volume.clone();
+                drop(volume);
+                assert!(!clone.handle().is_invalid());
+            }
+            let after = handle_count()?;
+
+            assert!(
+                after <= before + 10,
+                "handle count grew from {before} to {after}"
+            );
+            Ok(())
+        }
+
         #[test]
         fn test_get_volume_handle_from_invalid_drive_letter() {
             let drive_letter = 'W'; // Assuming W is not a valid drive letter
@@ -246,7 +1115,7 @@ mod tests {
 
             // Log the specific error for debugging purposes
             match result {
-                Err(UsnError::PermissionError) => {
+                Err(UsnError::PermissionError(_)) => {
                     eprintln!("Got permission error - test requires admin privileges");
                 }
                 Err(UsnError::WinApiError(err)) if err.code() == ERROR_FILE_NOT_FOUND.into() => {
@@ -271,6 +1140,70 @@ mod tests {
                 "Should return an error for invalid mount point"
             );
         }
+
+        #[test]
+        fn test_from_device_path_missing_shadow_copy() {
+            let device_path = r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy99999";
+            let result = Volume::from_device_path(device_path);
+            assert!(
+                result.is_err(),
+                "Should return an error for a missing shadow copy"
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_device_path_accepts_shadow_copy_paths() {
+        let shadow_copy = r"\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3";
+
+        assert_eq!(normalize_device_path(shadow_copy).unwrap(), shadow_copy);
+        assert_eq!(
+            normalize_device_path(&format!("{shadow_copy}\\")).unwrap(),
+            shadow_copy
+        );
+        assert_eq!(
+            normalize_device_path(r"\\.\HarddiskVolumeShadowCopy3").unwrap(),
+            r"\\.\HarddiskVolumeShadowCopy3"
+        );
+    }
+
+    #[test]
+    fn test_normalize_device_path_rejects_non_device_paths() {
+        for path in [r"C:\mount", r"\\?\", "HarddiskVolumeShadowCopy3", ""] {
+            assert!(matches!(
+                normalize_device_path(path),
+                Err(UsnError::InvalidMountPointError(p)) if p == path
+            ));
+        }
+    }
+
+    #[test]
+    fn test_volume_survey_error_names_volume_and_cause() {
+        let error = VolumeSurveyError {
+            volume: r"\\?\Volume{1234}\".to_string(),
+            error: UsnError::JournalNotActive,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            r"Volume \\?\Volume{1234}\ could not be surveyed: USN journal is not active on this volume"
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_string_from_wide_nul() {
+        let mut buffer = [0u16; 8];
+        for (slot, unit) in buffer.iter_mut().zip("Data".encode_utf16()) {
+            *slot = unit;
+        }
+
+        assert_eq!(string_from_wide_nul(&buffer), "Data");
+        assert_eq!(string_from_wide_nul(&[0u16; 4]), "");
+        assert_eq!(
+            string_from_wide_nul(&"Full".encode_utf16().collect::<Vec<_>>()),
+            "Full"
+        );
     }
 
     #[test]
@@ -280,8 +1213,58 @@ mod tests {
         let cloned = volume.clone();
 
         assert!(cloned.handle().is_invalid());
-        assert!(Rc::ptr_eq(&volume.handle, &cloned.handle));
+        assert!(Arc::ptr_eq(&volume.handle, &cloned.handle));
         assert_eq!(cloned.drive_letter, Some('T'));
         assert_eq!(cloned.mount_point, None);
     }
+
+    #[test]
+    fn test_close_refuses_shared_handle() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let cloned = volume.clone();
+
+        assert!(matches!(volume.close(), Err(UsnError::OtherError(_))));
+        // Once it is the last owner, closing an invalid handle is a no-op.
+        assert!(cloned.close().is_ok());
+    }
+
+    #[test]
+    fn test_guid_path_requires_a_mount_point() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), None, None);
+        assert!(matches!(
+            volume.guid_path(),
+            Err(UsnError::InvalidMountPointError(_))
+        ));
+    }
+
+    #[test]
+    fn test_filesystem_type_from_name() {
+        assert_eq!(FilesystemType::from_name("NTFS"), FilesystemType::Ntfs);
+        assert_eq!(FilesystemType::from_name("ReFS"), FilesystemType::Refs);
+        assert_eq!(FilesystemType::from_name("REFS"), FilesystemType::Refs);
+        assert_eq!(
+            FilesystemType::from_name("exFAT"),
+            FilesystemType::Other("exFAT".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_network_path() {
+        assert!(is_network_path(r"\\server\share"));
+        assert!(is_network_path("//server/share/file.txt"));
+        assert!(is_network_path(r"\\?\UNC\server\share"));
+        assert!(is_network_path(r"\\?\unc\server\share"));
+        assert!(!is_network_path(r"C:\Windows"));
+        assert!(!is_network_path(r"\\?\C:\Windows"));
+        assert!(!is_network_path(r"\\?\Volume{1234}\"));
+        assert!(!is_network_path(r"\\.\C:"));
+    }
+
+    #[test]
+    fn test_drive_letter_of_mount_points() {
+        assert_eq!(drive_letter_of(r"C:\"), Some('C'));
+        assert_eq!(drive_letter_of("D:"), Some('D'));
+        assert_eq!(drive_letter_of(r"C:\Mounts\Data\"), None);
+        assert_eq!(drive_letter_of(r"\\?\Volume{1234}\"), None);
+    }
 }
@@ -0,0 +1,390 @@
+//! High-level file system watching on top of the USN journal.
+//!
+//! A [`Watcher`] tails the journal of a volume and turns its records into [`FsEvent`]s with
+//! resolved paths, for callers who want `notify`-style events rather than raw USN records.
+
+use crate::{
+    FileId, UsnReason, UsnResult,
+    adapters::{CoalesceRenames, UsnEvent},
+    journal::{DATA_CHANGES, EnumOptions, UsnEntry, UsnJournalIter},
+    path::PathResolver,
+    volume::Volume,
+};
+use log::debug;
+use std::{collections::HashMap, path::PathBuf};
+
+/// A change to a file or directory, as reported by [`Watcher::watch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    Created(PathBuf),
+    /// The contents of the file, or one of its streams, changed.
+    Modified(PathBuf),
+    Deleted(PathBuf),
+    /// The file or directory was renamed or moved from `from` to `to`.
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+/// Watches a volume for changes, reporting them as [`FsEvent`]s.
+///
+/// # Example
+/// ```rust,no_run
+/// use usn_journal_rs::{USN_REASON_MASK_ALL, volume::Volume, watch::Watcher};
+///
+/// let volume = Volume::from_drive_letter('C')?;
+/// let watcher = Watcher::new(&volume);
+/// for event in watcher.watch(USN_REASON_MASK_ALL)? {
+///     println!("{:?}", event?);
+/// }
+/// # Ok::<(), usn_journal_rs::UsnError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    volume: Volume,
+}
+
+impl Watcher {
+    /// Creates a watcher for `volume`, sharing its handle.
+    pub fn new(volume: &Volume) -> Self {
+        Watcher {
+            volume: volume.clone(),
+        }
+    }
+
+    /// Tails the journal from its current end, reporting the changes selected by `reason_mask`.
+    ///
+    /// Reads use `wait_for_more`, so the iterator blocks until more records are written and
+    /// only ends on an error; see [`FsEvents`] for how records map to events. The journal is
+    /// never created here; use [`UsnJournal::ensure_active`] first if it may be missing.
+    /// `reason_mask` is passed on as
+    /// [`EnumOptions::reason_mask`]; renames are only reported as [`FsEvent::Renamed`] if it
+    /// includes both `USN_REASON_RENAME_OLD_NAME` and `USN_REASON_RENAME_NEW_NAME`.
+    ///
+    /// # Errors
+    /// Returns `UsnError::JournalNotActive` if the volume has no journal, or another error if
+    /// the journal cannot be queried.
+    ///
+    /// [`UsnJournal::ensure_active`]: crate::journal::UsnJournal::ensure_active
+    pub fn watch(&self, reason_mask: u32) -> UsnResult<FsEvents<'_>> {
+        let journal = self.volume.journal();
+        let journal_data = journal.query()?;
+        let iter = journal.iter_with_options(EnumOptions {
+            start_usn: journal_data.next_usn,
+            reason_mask,
+            wait_for_more: true,
+            ..Default::default()
+        })?;
+        Ok(FsEvents::new(
+            iter.coalesce_renames(),
+            PathResolver::new_with_cache(&self.volume),
+        ))
+    }
+}
+
+/// Iterator over the [`FsEvent`]s of a volume.
+///
+/// Created by [`Watcher::watch`]. The two records of a rename are paired into one
+/// [`FsEvent::Renamed`]. Records between a file being opened and closed are coalesced: each
+/// of `Created` and `Modified` is reported once per open, and `Modified` not at all for a
+/// file created in the same open, until the `CLOSE` record ends it. Records that only change
+/// metadata yield no event. A rename whose `RENAME_OLD_NAME` record was not seen is reported
+/// as `Created` at the new path.
+///
+/// Paths are resolved through the parent directory, so deleted files resolve as long as their
+/// directory still exists. Events whose path cannot be resolved are skipped. Renamed and
+/// deleted directories are updated in, or dropped from, the resolver's cache, so later events
+/// below them do not report the old path.
+pub struct FsEvents<'v, I = CoalesceRenames<UsnJournalIter>> {
+    iter: I,
+    resolver: PathResolver<'v>,
+    // Reasons of the last record of each file that has not been closed since.
+    open: HashMap<FileId, UsnReason>,
+}
+
+impl<'v, I> FsEvents<'v, I> {
+    pub(crate) fn new(iter: I, resolver: PathResolver<'v>) -> Self {
+        FsEvents {
+            iter,
+            resolver,
+            open: HashMap::new(),
+        }
+    }
+
+    fn handle(&mut self, event: UsnEvent) -> Option<FsEvent> {
+        match event {
+            UsnEvent::Entry(entry) => self.handle_entry(entry),
+            UsnEvent::Renamed {
+                fid,
+                old_name,
+                old_parent,
+                new_name,
+                new_parent,
+                ..
+            } => {
+                // A directory is renamed in the cache below if it is there at all.
+                let Some(to) = self
+                    .resolver
+                    .resolve_from_parts(new_parent, &new_name, false)
+                else {
+                    self.resolver.forget_directory(fid);
+                    return None;
+                };
+                let from = old_parent.zip(old_name).and_then(|(parent, name)| {
+                    self.resolver.resolve_from_parts(parent, &name, false)
+                });
+                self.resolver.rename_cached_directory(fid, &to, &new_name);
+                Some(match from {
+                    Some(from) => FsEvent::Renamed { from, to },
+                    None => FsEvent::Created(to),
+                })
+            }
+        }
+    }
+
+    fn handle_entry(&mut self, entry: UsnEntry) -> Option<FsEvent> {
+        // Each record of an open file carries every reason since it was opened, so the
+        // reasons of its previous record have been reported already.
+        let reported = if entry.is_close() || entry.is_delete() {
+            self.open.remove(&entry.fid)
+        } else {
+            self.open.insert(entry.fid, entry.reason)
+        }
+        .unwrap_or(UsnReason::empty());
+
+        let event: fn(PathBuf) -> FsEvent = if entry.is_delete() {
+            if entry.is_dir() {
+                self.resolver.forget_directory(entry.fid);
+            }
+            FsEvent::Deleted
+        } else if entry.is_create() && !reported.contains(UsnReason::FILE_CREATE) {
+            FsEvent::Created
+        } else if entry.is_data_change()
+            && !reported.intersects(DATA_CHANGES | UsnReason::FILE_CREATE)
+        {
+            FsEvent::Modified
+        } else {
+            return None;
+        };
+
+        match self.resolver.resolve_from_parts(
+            entry.parent_fid,
+            &entry.file_name,
+            entry.is_dir() && !entry.is_delete(),
+        ) {
+            Some(path) => Some(event(path)),
+            None => {
+                debug!("Skipping change to {}: path cannot be resolved", entry.fid);
+                None
+            }
+        }
+    }
+}
+
+impl<I> Iterator for FsEvents<'_, I>
+where
+    I: Iterator<Item = UsnResult<UsnEvent>>,
+{
+    type Item = UsnResult<FsEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.iter.next()? {
+                Ok(event) => {
+                    if let Some(event) = self.handle(event) {
+                        return Some(Ok(event));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::OsString, time::SystemTime};
+    use windows::Win32::{
+        Foundation::HANDLE,
+        Storage::FileSystem::FILE_ATTRIBUTE_DIRECTORY,
+        System::Ioctl::{
+            USN_REASON_CLOSE, USN_REASON_DATA_EXTEND, USN_REASON_DATA_OVERWRITE,
+            USN_REASON_FILE_CREATE, USN_REASON_FILE_DELETE, USN_REASON_RENAME_NEW_NAME,
+            USN_REASON_RENAME_OLD_NAME,
+        },
+    };
+
+    fn mock_entry(usn: i64, fid: u64, reason: u32, name: &str) -> UsnEntry {
+        UsnEntry {
+            usn,
+            time: SystemTime::UNIX_EPOCH,
+            fid: fid.into(),
+            parent_fid: FileId(0x10),
+            reason: reason.into(),
+            source_info: 0,
+            file_name: OsString::from(name),
+            file_attributes: 0,
+        }
+    }
+
+    fn mock_dir_entry(usn: i64, fid: u64, reason: u32, name: &str) -> UsnEntry {
+        UsnEntry {
+            file_attributes: FILE_ATTRIBUTE_DIRECTORY.0,
+            ..mock_entry(usn, fid, reason, name)
+        }
+    }
+
+    fn fs_events(volume: &Volume, entries: Vec<UsnEntry>) -> Vec<FsEvent> {
+        let mut resolver = PathResolver::new_with_cache(volume);
+        resolver.cache_directory(FileId(0x10), PathBuf::from(r"T:\Docs"));
+        let iter = CoalesceRenames::new(entries.into_iter().map(Ok));
+        FsEvents::new(iter, resolver)
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_fs_events_coalesce_records_until_close() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let entries = vec![
+            mock_entry(0x100, 0x11, USN_REASON_FILE_CREATE, "new.txt"),
+            mock_entry(
+                0x200,
+                0x11,
+                USN_REASON_FILE_CREATE | USN_REASON_DATA_EXTEND,
+                "new.txt",
+            ),
+            mock_entry(
+                0x300,
+                0x11,
+                USN_REASON_FILE_CREATE | USN_REASON_DATA_EXTEND | USN_REASON_CLOSE,
+                "new.txt",
+            ),
+            mock_entry(0x400, 0x12, USN_REASON_DATA_OVERWRITE, "old.txt"),
+            mock_entry(
+                0x500,
+                0x12,
+                USN_REASON_DATA_OVERWRITE | USN_REASON_DATA_EXTEND,
+                "old.txt",
+            ),
+            mock_entry(
+                0x600,
+                0x12,
+                USN_REASON_DATA_OVERWRITE | USN_REASON_DATA_EXTEND | USN_REASON_CLOSE,
+                "old.txt",
+            ),
+            mock_entry(0x700, 0x12, USN_REASON_DATA_EXTEND, "old.txt"),
+            mock_entry(
+                0x800,
+                0x12,
+                USN_REASON_FILE_DELETE | USN_REASON_CLOSE,
+                "old.txt",
+            ),
+        ];
+
+        assert_eq!(
+            fs_events(&volume, entries),
+            vec![
+                FsEvent::Created(PathBuf::from(r"T:\Docs\new.txt")),
+                FsEvent::Modified(PathBuf::from(r"T:\Docs\old.txt")),
+                FsEvent::Modified(PathBuf::from(r"T:\Docs\old.txt")),
+                FsEvent::Deleted(PathBuf::from(r"T:\Docs\old.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fs_events_pair_renames() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let entries = vec![
+            mock_entry(0x100, 0x11, USN_REASON_RENAME_OLD_NAME, "draft.txt"),
+            mock_entry(0x200, 0x11, USN_REASON_RENAME_NEW_NAME, "final.txt"),
+            mock_entry(
+                0x300,
+                0x11,
+                USN_REASON_RENAME_NEW_NAME | USN_REASON_CLOSE,
+                "final.txt",
+            ),
+            // The old name of this rename was written before the watch started.
+            mock_entry(0x400, 0x12, USN_REASON_RENAME_NEW_NAME, "moved.txt"),
+        ];
+
+        assert_eq!(
+            fs_events(&volume, entries),
+            vec![
+                FsEvent::Renamed {
+                    from: PathBuf::from(r"T:\Docs\draft.txt"),
+                    to: PathBuf::from(r"T:\Docs\final.txt"),
+                },
+                FsEvent::Created(PathBuf::from(r"T:\Docs\moved.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fs_events_follow_renamed_directory() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        resolver.cache_directory(FileId(0x10), PathBuf::from(r"T:\Docs"));
+        resolver.cache_directory(FileId(0x20), PathBuf::from(r"T:\Docs\Drafts"));
+        resolver.cache_directory(FileId(0x30), PathBuf::from(r"T:\Docs\Drafts\Old"));
+        let entries = vec![
+            mock_dir_entry(0x100, 0x20, USN_REASON_RENAME_OLD_NAME, "Drafts"),
+            mock_dir_entry(0x200, 0x20, USN_REASON_RENAME_NEW_NAME, "Final"),
+            UsnEntry {
+                parent_fid: FileId(0x20),
+                ..mock_entry(0x300, 0x21, USN_REASON_FILE_CREATE, "report.txt")
+            },
+            UsnEntry {
+                parent_fid: FileId(0x30),
+                ..mock_entry(0x400, 0x31, USN_REASON_FILE_CREATE, "notes.txt")
+            },
+        ];
+        let iter = CoalesceRenames::new(entries.into_iter().map(Ok));
+        let events = FsEvents::new(iter, resolver)
+            .collect::<UsnResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                FsEvent::Renamed {
+                    from: PathBuf::from(r"T:\Docs\Drafts"),
+                    to: PathBuf::from(r"T:\Docs\Final"),
+                },
+                FsEvent::Created(PathBuf::from(r"T:\Docs\Final\report.txt")),
+                FsEvent::Created(PathBuf::from(r"T:\Docs\Final\Old\notes.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fs_events_forget_deleted_directory() {
+        let volume = Volume::from_handle(HANDLE(std::ptr::null_mut()), Some('T'), None);
+        let mut resolver = PathResolver::new_with_cache(&volume);
+        resolver.cache_directory(FileId(0x10), PathBuf::from(r"T:\Docs"));
+        resolver.cache_directory(FileId(0x20), PathBuf::from(r"T:\Docs\Drafts"));
+        let entries = vec![mock_dir_entry(
+            0x100,
+            0x20,
+            USN_REASON_FILE_DELETE | USN_REASON_CLOSE,
+            "Drafts",
+        )];
+        let mut events = FsEvents::new(CoalesceRenames::new(entries.into_iter().map(Ok)), resolver);
+
+        assert_eq!(
+            events.next().unwrap().unwrap(),
+            FsEvent::Deleted(PathBuf::from(r"T:\Docs\Drafts"))
+        );
+        assert!(events.next().is_none());
+        let cached = events
+            .resolver
+            .cache_dump()
+            .into_iter()
+            .map(|(fid, _, _)| fid)
+            .collect::<Vec<_>>();
+        assert_eq!(cached, vec![FileId(0x10)]);
+    }
+}
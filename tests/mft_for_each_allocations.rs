@@ -0,0 +1,75 @@
+//! Checks that `Mft::for_each` does not allocate per entry.
+//!
+//! Lives in its own test binary because it installs a counting global allocator, which
+//! would otherwise apply to every unit test of the crate.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    ops::ControlFlow,
+};
+use usn_journal_rs::{UsnError, mft::Mft, volume::Volume};
+
+// Counts heap allocations made by the current thread, so tests running in parallel
+// don't disturb each other's counts.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn thread_allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Visits up to `limit` entries, returning how many were visited and the allocations made.
+fn count_for_each_allocations(mft: &Mft, limit: usize) -> Result<(usize, usize), UsnError> {
+    let mut visited = 0;
+    let before = thread_allocations();
+    mft.for_each(|entry| {
+        visited += 1;
+        std::hint::black_box(entry.file_name_eq("file.txt"));
+        if visited == limit {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+    Ok((visited, thread_allocations() - before))
+}
+
+#[test]
+fn for_each_allocates_independently_of_entry_count() -> Result<(), UsnError> {
+    let volume = match Volume::from_drive_letter('C') {
+        Ok(volume) => volume,
+        Err(UsnError::PermissionError(_)) => {
+            eprintln!("Skipping test - requires admin privileges");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let mft = Mft::new(&volume);
+
+    let (few, few_allocations) = count_for_each_allocations(&mft, 3)?;
+    let (many, many_allocations) = count_for_each_allocations(&mft, 20_000)?;
+
+    assert_eq!(few, 3);
+    assert!(many > few);
+    // Only the read buffer is allocated, however many entries are visited.
+    assert_eq!(many_allocations, few_allocations);
+    Ok(())
+}